@@ -1,45 +1,52 @@
 use bytes::BytesMut;
 use color_eyre::eyre::Result;
 use protocol::{
-    traits::{FrameGenerator, ParseProtocol, ProtocolSplit},
-    types::Command,
+    dynamic::ProtocolSwitch,
+    traits::{FrameGenerator, MessageProtocol, ParseProtocol},
+    types::{Command, ConnectionId, FrameRecord, MessageRecord},
 };
 use stream::traits::{AsyncFrameReader, AsyncFrameWriter, AsyncStreamSplit};
 
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
 use tokio::sync::mpsc;
 use tracing::{info, instrument};
-use ui::traits::RenderUi;
+use ui::traits::{HandleMessage, RenderUi};
 
 /// `LazyApp` 是一个封装了应用核心逻辑的结构体.
 ///
 /// 它现在作为一个高级别的"引导程序" (bootstrapper), 负责初始化网络流和协议,
 /// 并通过其 `run` 方法启动核心的读/写异步任务.
+/// 应用对协议的类型化消息 (`MessageProtocol::Message`) 是泛型的:
+/// 解码得到的 `Command` 在读任务中被转换为类型化消息后交给界面, 转换错误会被记录并一同交给界面.
 pub struct LazyApp<P, Io, U>
 where
-    P: ProtocolSplit,
+    P: MessageProtocol,
     Io: AsyncStreamSplit,
     U: RenderUi,
 {
     /// 用于异步读写数据帧的网络流.
     stream: Io,
-    /// 实现了 `ProtocolSplit` 的协议处理器.
+    /// 实现了 `MessageProtocol` 的协议处理器.
     protocol: P,
     ui: U,
     interval: tokio::time::Interval,
+    /// 当前连接的标识, 附加在每条收发记录上.
+    connection_id: ConnectionId,
+    /// 运行期间切换协议的句柄, 设置后可按 `Tab` 切换到下一个已注册的协议.
+    protocol_switch: Option<ProtocolSwitch>,
 }
 
 impl<P, Io, U> LazyApp<P, Io, U>
 where
-    P: ProtocolSplit,
+    P: MessageProtocol,
     Io: AsyncStreamSplit,
-    // 在这里添加一个trait, 用于更新UI
-    U: RenderUi + Send + 'static,
+    U: RenderUi + HandleMessage<MessageRecord<P::Message>> + Send + 'static,
 {
     /// 创建一个新的 `LazyApp` 实例.
     ///
     /// # 参数
     /// - `stream`: 一个实现了 `AsyncStreamSplit` 的网络流.
-    /// - `protocol`: 一个实现了 `ProtocolSplit` 的协议处理器.
+    /// - `protocol`: 一个实现了 `MessageProtocol` 的协议处理器.
     ///
     /// # 返回
     /// 一个新的 `LazyApp` 实例, 准备好通过调用 `.run()` 来启动.
@@ -49,9 +56,17 @@ where
             protocol,
             ui,
             interval: tokio::time::interval(duration),
+            connection_id: ConnectionId::next(),
+            protocol_switch: None,
         }
     }
 
+    /// 设置运行期间切换协议的句柄, 通常与 `SwitchableProtocol` 共享同一个 `ProtocolSwitch`.
+    pub fn protocol_switch(mut self, protocol_switch: ProtocolSwitch) -> Self {
+        self.protocol_switch = Some(protocol_switch);
+        self
+    }
+
     /// 运行应用的主循环.
     /// # 返回
     /// 如果 `tokio::join!` 正常返回 (即读写任务都已结束), 返回 `Ok(())`.
@@ -64,27 +79,47 @@ where
         Io::Reader: AsyncFrameReader + Send + 'static,
         P::Encoder: FrameGenerator + Send + 'static,
         P::Decode: ParseProtocol + Send + 'static,
-        // U: HandleCommand,
+        P::Message: Clone + Send + 'static,
     {
         let mut terminal = ratatui::init();
         // 1. 分离网络流和协议处理器
         let (mut stream_reader, mut stream_writer) = self.stream.into_split();
         let (mut protocol_decoder, protocol_encoder) = self.protocol.into_split();
-        let ui = self.ui;
+        let mut ui = self.ui;
         let mut interval = self.interval;
+        let connection_id = self.connection_id;
 
         // 2. 创建用于外部与 Writer Task 通信的通道
-        let (_command_sender, mut command_receiver) = mpsc::channel::<Command>(10);
-        let (ui_sender, mut ui_receiver) = mpsc::channel::<Command>(10);
+        let (_command_sender, mut command_receiver) = mpsc::channel::<P::Message>(10);
+        let (ui_sender, mut ui_receiver) = mpsc::channel::<MessageRecord<P::Message>>(10);
 
         // --- Writer 任务 ---
+        // 写任务只持有弱引用, 界面任务仍然在读任务结束后退出
+        let tx_sender = ui_sender.downgrade();
         let writer_handle = tokio::spawn(async move {
-            while let Some(command) = command_receiver.recv().await {
+            while let Some(message) = command_receiver.recv().await {
+                // 发送记录需要保留类型化消息, 因此转换前先克隆一份
+                let command: Command = match message.clone().try_into() {
+                    Ok(command) => command,
+                    Err(e) => {
+                        info!("[Writer Task] Failed to convert message: {}", e);
+                        continue;
+                    }
+                };
                 match protocol_encoder.create_frame(command) {
                     Ok(frame) => {
                         if let Err(e) = stream_writer.write_frame(&frame).await {
                             info!("[Writer Task] Failed to write frame: {}", e);
+                            continue;
                         }
+                        // 将实际发送的帧回显给界面, 以便日志同时记录收发两个方向
+                        let Some(sender) = tx_sender.upgrade() else {
+                            continue;
+                        };
+                        let record = FrameRecord::tx(connection_id, Ok(message), frame);
+                        let _ = sender.try_send(record).map_err(|f| {
+                            info!("[Writer Task] Failed to send record: {}", f);
+                        });
                     }
                     Err(e) => info!("[Writer Task] Failed to create frame: {}", e),
                 }
@@ -102,11 +137,20 @@ where
                     Ok(_len) => {
                         if let Some(commands) = protocol_decoder.parse_protocol_frame(&mut buf) {
                             for command in commands {
-                                let _ = sender.try_send(command).map_err(|f| {
+                                let record =
+                                    FrameRecord::rx(connection_id, command).decode::<P::Message>();
+                                if let Err(ref e) = record.message {
+                                    info!("[Reader Task] Failed to convert command: {}", e);
+                                }
+                                let _ = sender.try_send(record).map_err(|f| {
                                     info!("[Reader Task] Failed to send command: {}", f);
                                 });
                             }
                         }
+                        // 解码器会限制缓冲区的积压, 被丢弃的数据以诊断事件的形式报告
+                        for diagnostic in protocol_decoder.take_diagnostics() {
+                            info!("[Reader Task] Decoder diagnostic: {}", diagnostic);
+                        }
                     }
                     Err(e) => {
                         info!("[Reader Task] Failed to read from stream: {}", e);
@@ -115,10 +159,27 @@ where
                 }
             }
         });
+        // --- 输入线程 ---
+        // 终端事件的读取是阻塞的, 因此放在独立线程中, 进程退出时随之结束
+        if let Some(protocol_switch) = self.protocol_switch {
+            std::thread::spawn(move || {
+                while let Ok(event) = event::read() {
+                    if let Event::Key(key) = event
+                        && key.kind == KeyEventKind::Press
+                        && key.code == KeyCode::Tab
+                    {
+                        let name = protocol_switch.switch_next();
+                        info!("[Input Thread] Switched protocol to {}", name);
+                    }
+                }
+            });
+        }
+
         let ui_handle = tokio::spawn(async move {
             loop {
                 tokio::select! {
                      _ = interval.tick() => {
+                        ui.on_tick();
                         if let Err(e) = terminal.draw(|frame| {
                             ui.render(frame, frame.area());
                         }) {
@@ -127,14 +188,11 @@ where
                         }
                     },
                     recv = ui_receiver.recv() => {
-                        if let Some(_command)=recv{
-                            //TODO: 增加ui的处理
-                        }
-                        else{
+                        if let Some(record) = recv {
+                            ui.handle_message(record);
+                        } else {
                             break;
                         }
-
-                        // ui.add_line(command);
                     }
                 }
             }
@@ -149,4 +207,3 @@ where
         Ok(())
     }
 }
-
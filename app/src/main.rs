@@ -1,6 +1,13 @@
 use app::app::LazyApp;
 use bln::{protocol::BlnProtocol, tui::BlnTui};
+use clap::Parser;
 use color_eyre::Result;
+use modbus::protocol::ModbusProtocol;
+use protocol::{
+    detect::ProtocolDetector,
+    dynamic::{ProtocolRegistry, ProtocolSwitch, SwitchableProtocol},
+    raw::RawProtocol,
+};
 use stream::client::connect;
 use tracing_appender::{non_blocking, rolling};
 use tracing_error::ErrorLayer;
@@ -11,10 +18,35 @@ use tracing_subscriber::{
     util::SubscriberInitExt,
 };
 
+/// lazyframe: 连接设备并实时显示协议帧.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Args {
+    /// 设备地址.
+    #[arg(long, default_value = "192.168.1.101:5006")]
+    addr: String,
+    /// 启动时使用的协议, 运行中可按 Tab 切换.
+    /// 为 "auto" 时根据最初收到的数据自动检测, 检测完成前以 raw 协议显示.
+    #[arg(long, default_value = "bln")]
+    protocol: String,
+}
+
+/// 自动检测协议时使用的协议名.
+const AUTO_PROTOCOL: &str = "auto";
+
+/// 内置协议的注册表. 自定义协议可以在此通过 `register` 以新名称加入.
+fn registry() -> ProtocolRegistry {
+    ProtocolRegistry::default()
+        .register("bln", BlnProtocol::default())
+        .register("modbus", ModbusProtocol::default())
+        .register("raw", RawProtocol)
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // 安装 color_eyre 错误处理
     color_eyre::install()?;
+    let args = Args::parse();
 
     // -- 文件日志 --
     let file_appender = rolling::daily("logs", "lazy_net_log");
@@ -40,17 +72,23 @@ async fn main() -> Result<()> {
         .with(console_layer)
         .init();
 
-    // let mut protocol: Box<dyn Protocol> = Box::new(BlnProtocol::default());
+    let (protocol_switch, protocol) = if args.protocol == AUTO_PROTOCOL {
+        let protocol_switch = ProtocolSwitch::new(registry(), "raw")?;
+        let protocol = SwitchableProtocol::new(protocol_switch.clone())
+            .auto_detect(ProtocolDetector::new(registry()));
+        (protocol_switch, protocol)
+    } else {
+        let protocol_switch = ProtocolSwitch::new(registry(), &args.protocol)?;
+        let protocol = SwitchableProtocol::new(protocol_switch.clone());
+        (protocol_switch, protocol)
+    };
     let app = LazyApp::new(
-        connect(
-            "192.168.1.101:5006",
-            tokio::time::Duration::from_millis(5000),
-        )
-        .await?,
-        BlnProtocol::default(),
-        BlnTui::default(),
+        connect(args.addr, tokio::time::Duration::from_millis(5000)).await?,
+        protocol,
+        BlnTui::default().protocol_switch(protocol_switch.clone()),
         tokio::time::Duration::from_millis(100),
-    );
+    )
+    .protocol_switch(protocol_switch);
 
     app.run().await?;
     Ok(())
@@ -0,0 +1,25 @@
+use std::{env, fs, path::PathBuf};
+
+/// 根据 `src/lib.rs` 在 `OUT_DIR` 中生成 C 头文件 `bln.h`.
+///
+/// 构建脚本不会修改源码目录. 提交在仓库中的 `include/bln.h` 只在设置了
+/// `BLN_FFI_UPDATE_HEADER` 环境变量时被更新, 测试会检查它与生成的头文件一致.
+fn main() {
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("cargo 总会设置该变量"));
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("cargo 总会设置该变量"));
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+        .expect("cbindgen.toml 格式错误");
+    let header = out_dir.join("bln.h");
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("生成 C 头文件失败")
+        .write_to_file(&header);
+    if env::var_os("BLN_FFI_UPDATE_HEADER").is_some() {
+        fs::copy(&header, crate_dir.join("include/bln.h")).expect("更新 include/bln.h 失败");
+    }
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=BLN_FFI_UPDATE_HEADER");
+}
@@ -0,0 +1,142 @@
+#ifndef BLN_H
+#define BLN_H
+
+/* 由 cbindgen 根据 bln-ffi/src/lib.rs 生成, 不要手动修改. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * C 接口的返回值.
+ */
+typedef enum BlnResult {
+  /**
+   * 成功.
+   */
+  BLN_RESULT_OK = 0,
+  /**
+   * 必需的指针参数为空.
+   */
+  BLN_RESULT_NULL_POINTER = -1,
+  /**
+   * 输出缓冲区不够长.
+   */
+  BLN_RESULT_BUFFER_TOO_SMALL = -2,
+  /**
+   * 负载超过长度字段能表示的最大值.
+   */
+  BLN_RESULT_PAYLOAD_TOO_LARGE = -3,
+  /**
+   * 响应状态超出 3 位.
+   */
+  BLN_RESULT_INVALID_STATUS = -4,
+} BlnResult;
+
+/**
+ * 流式 BLN 解码器, 由 [`bln_decoder_new`] 创建, [`bln_decoder_free`] 释放.
+ */
+typedef struct BlnDecoder BlnDecoder;
+
+/**
+ * 一个 BLN 帧的内容, 编码时由调用方填写, 解码时由回调读取.
+ */
+typedef struct BlnFrame {
+  /**
+   * 命令字.
+   */
+  uint8_t cmd_id;
+  /**
+   * 响应状态, 请求帧为 0.
+   */
+  uint8_t status;
+  /**
+   * 保留字段.
+   */
+  uint8_t reserved[4];
+  /**
+   * 负载数据, 长度为 0 时可以为空.
+   */
+  const uint8_t *payload;
+  /**
+   * 负载长度.
+   */
+  size_t payload_len;
+} BlnFrame;
+
+/**
+ * 解码出一个帧时调用的回调. `frame` 及其负载只在回调期间有效.
+ */
+typedef void (*BlnFrameCallback)(void *user_data, const struct BlnFrame *frame);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * 计算数据的块校验码 (所有字节的异或).
+ *
+ * # Safety
+ * `len` 不为 0 时 `data` 必须指向 `len` 个可读的字节.
+ */
+uint8_t bln_bcc(const uint8_t *data, size_t len);
+
+/**
+ * 负载长度为 `payload_len` 的帧编码后的长度.
+ */
+size_t bln_frame_len(size_t payload_len);
+
+/**
+ * 将帧编码到 `out` 中, 成功时将写入的字节数存入 `written`.
+ *
+ * # Safety
+ * `frame` 必须指向有效的 [`BlnFrame`], 其负载满足 [`BlnFrame::payload`] 的约定;
+ * `out` 必须指向 `out_len` 个可写的字节; `written` 必须指向可写的 `size_t`.
+ */
+enum BlnResult bln_encode_frame(const struct BlnFrame *frame,
+                                uint8_t *out,
+                                size_t out_len,
+                                size_t *written);
+
+/**
+ * 创建一个流式解码器, 使用完毕后用 [`bln_decoder_free`] 释放.
+ */
+struct BlnDecoder *bln_decoder_new(void);
+
+/**
+ * 释放解码器, `decoder` 为空时什么也不做.
+ *
+ * # Safety
+ * `decoder` 必须由 [`bln_decoder_new`] 创建, 且只能释放一次.
+ */
+void bln_decoder_free(struct BlnDecoder *decoder);
+
+/**
+ * 写入接收到的字节, 对其中每个完整的帧调用一次 `callback`, 返回解码出的帧数.
+ *
+ * 不完整的帧保留在解码器中, 与下一次写入的字节拼接. 参数无效时返回 0.
+ *
+ * # Safety
+ * `decoder` 必须由 [`bln_decoder_new`] 创建; `len` 不为 0 时 `data` 必须指向 `len` 个可读的字节.
+ */
+size_t bln_decoder_feed(struct BlnDecoder *decoder,
+                        const uint8_t *data,
+                        size_t len,
+                        BlnFrameCallback callback,
+                        void *user_data);
+
+/**
+ * 解码器因校验失败、长度超限等原因丢弃的帧数.
+ *
+ * # Safety
+ * `decoder` 必须为空或由 [`bln_decoder_new`] 创建.
+ */
+size_t bln_decoder_dropped(const struct BlnDecoder *decoder);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* BLN_H */
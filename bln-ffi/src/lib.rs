@@ -0,0 +1,219 @@
+//! BLN 协议的 C 接口.
+//!
+//! 供 LabVIEW 和 C++ 工具复用与 Rust 端相同的组帧逻辑: 由命令字和负载生成帧,
+//! 将字节流解码后逐帧交给回调, 以及计算 BCC. 头文件由 cbindgen 在构建时生成到 `OUT_DIR`,
+//! 仓库中的 `include/bln.h` 是它的副本, 设置 `BLN_FFI_UPDATE_HEADER` 环境变量构建时更新.
+//!
+//! 所有函数都不会 panic 穿过 FFI 边界, 错误以 [`BlnResult`] 返回.
+use std::{ffi::c_void, ptr, slice};
+
+use bln::protocol::types::{BlnCommandDecode, BlnCommandEncoder};
+use bytes::BytesMut;
+use protocol::{
+    traits::{FrameGenerator, ParseProtocol},
+    types::{Command, ProtocolError},
+    utils::calculate_bcc,
+};
+
+/// 帧头、命令字、保留字段、长度字段和 BCC 的总长度.
+const FRAME_OVERHEAD: usize = 10;
+
+/// C 接口的返回值.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlnResult {
+    /// 成功.
+    Ok = 0,
+    /// 必需的指针参数为空.
+    NullPointer = -1,
+    /// 输出缓冲区不够长.
+    BufferTooSmall = -2,
+    /// 负载超过长度字段能表示的最大值.
+    PayloadTooLarge = -3,
+    /// 响应状态超出 3 位.
+    InvalidStatus = -4,
+}
+
+impl From<ProtocolError> for BlnResult {
+    fn from(error: ProtocolError) -> Self {
+        match error {
+            ProtocolError::PayloadTooLarge { .. } => Self::PayloadTooLarge,
+            // 编码器只会返回负载过长和状态越界两种错误
+            _ => Self::InvalidStatus,
+        }
+    }
+}
+
+/// 一个 BLN 帧的内容, 编码时由调用方填写, 解码时由回调读取.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct BlnFrame {
+    /// 命令字.
+    pub cmd_id: u8,
+    /// 响应状态, 请求帧为 0.
+    pub status: u8,
+    /// 保留字段.
+    pub reserved: [u8; 4],
+    /// 负载数据, 长度为 0 时可以为空.
+    pub payload: *const u8,
+    /// 负载长度.
+    pub payload_len: usize,
+}
+
+/// 解码出一个帧时调用的回调. `frame` 及其负载只在回调期间有效.
+pub type BlnFrameCallback =
+    Option<unsafe extern "C" fn(user_data: *mut c_void, frame: *const BlnFrame)>;
+
+/// 流式 BLN 解码器, 由 [`bln_decoder_new`] 创建, [`bln_decoder_free`] 释放.
+pub struct BlnDecoder {
+    /// BLN 协议解码器.
+    decoder: BlnCommandDecode,
+    /// 尚未组成完整帧的字节.
+    buf: BytesMut,
+    /// 因校验失败等原因被丢弃的帧数.
+    dropped: usize,
+}
+
+/// 将可能为空的指针和长度转换为切片.
+///
+/// # Safety
+/// `len` 不为 0 时 `data` 必须指向 `len` 个可读的字节.
+unsafe fn bytes_of<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    match (data.is_null(), len) {
+        (_, 0) => Some(&[]),
+        (true, _) => None,
+        // SAFETY: 由调用方保证
+        (false, _) => Some(unsafe { slice::from_raw_parts(data, len) }),
+    }
+}
+
+/// 计算数据的块校验码 (所有字节的异或).
+///
+/// # Safety
+/// `len` 不为 0 时 `data` 必须指向 `len` 个可读的字节.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bln_bcc(data: *const u8, len: usize) -> u8 {
+    // SAFETY: 由调用方保证
+    unsafe { bytes_of(data, len) }.map_or(0, calculate_bcc)
+}
+
+/// 负载长度为 `payload_len` 的帧编码后的长度.
+#[unsafe(no_mangle)]
+pub extern "C" fn bln_frame_len(payload_len: usize) -> usize {
+    FRAME_OVERHEAD + payload_len
+}
+
+/// 将帧编码到 `out` 中, 成功时将写入的字节数存入 `written`.
+///
+/// # Safety
+/// `frame` 必须指向有效的 [`BlnFrame`], 其负载满足 [`BlnFrame::payload`] 的约定;
+/// `out` 必须指向 `out_len` 个可写的字节; `written` 必须指向可写的 `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bln_encode_frame(
+    frame: *const BlnFrame,
+    out: *mut u8,
+    out_len: usize,
+    written: *mut usize,
+) -> BlnResult {
+    if frame.is_null() || out.is_null() || written.is_null() {
+        return BlnResult::NullPointer;
+    }
+    // SAFETY: 指针非空, 其余由调用方保证
+    let frame = unsafe { &*frame };
+    let Some(payload) = (unsafe { bytes_of(frame.payload, frame.payload_len) }) else {
+        return BlnResult::NullPointer;
+    };
+    let command = Command::new(frame.cmd_id)
+        .with_status(frame.status)
+        .with_reserved(frame.reserved.to_vec())
+        .with_payload(payload.to_vec());
+    let encoded = match BlnCommandEncoder.create_frame(command) {
+        Ok(encoded) => encoded,
+        Err(error) => return error.into(),
+    };
+    if encoded.len() > out_len {
+        return BlnResult::BufferTooSmall;
+    }
+    // SAFETY: `out` 至少有 `out_len` 个字节, 且不会与刚分配的 `encoded` 重叠
+    unsafe {
+        ptr::copy_nonoverlapping(encoded.as_ptr(), out, encoded.len());
+        *written = encoded.len();
+    }
+    BlnResult::Ok
+}
+
+/// 创建一个流式解码器, 使用完毕后用 [`bln_decoder_free`] 释放.
+#[unsafe(no_mangle)]
+pub extern "C" fn bln_decoder_new() -> *mut BlnDecoder {
+    Box::into_raw(Box::new(BlnDecoder {
+        decoder: BlnCommandDecode::default(),
+        buf: BytesMut::new(),
+        dropped: 0,
+    }))
+}
+
+/// 释放解码器, `decoder` 为空时什么也不做.
+///
+/// # Safety
+/// `decoder` 必须由 [`bln_decoder_new`] 创建, 且只能释放一次.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bln_decoder_free(decoder: *mut BlnDecoder) {
+    if !decoder.is_null() {
+        // SAFETY: 由调用方保证
+        drop(unsafe { Box::from_raw(decoder) });
+    }
+}
+
+/// 写入接收到的字节, 对其中每个完整的帧调用一次 `callback`, 返回解码出的帧数.
+///
+/// 不完整的帧保留在解码器中, 与下一次写入的字节拼接. 参数无效时返回 0.
+///
+/// # Safety
+/// `decoder` 必须由 [`bln_decoder_new`] 创建; `len` 不为 0 时 `data` 必须指向 `len` 个可读的字节.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bln_decoder_feed(
+    decoder: *mut BlnDecoder,
+    data: *const u8,
+    len: usize,
+    callback: BlnFrameCallback,
+    user_data: *mut c_void,
+) -> usize {
+    if decoder.is_null() {
+        return 0;
+    }
+    // SAFETY: 由调用方保证
+    let (decoder, Some(data)) = (unsafe { &mut *decoder }, unsafe { bytes_of(data, len) }) else {
+        return 0;
+    };
+    decoder.buf.extend_from_slice(data);
+    let commands = decoder
+        .decoder
+        .parse_protocol_frame(&mut decoder.buf)
+        .unwrap_or_default();
+    decoder.dropped += decoder.decoder.take_diagnostics().len();
+
+    for command in &commands {
+        let frame = BlnFrame {
+            cmd_id: command.cmd_id(),
+            status: command.response_status().unwrap_or(0),
+            reserved: command.reserved().try_into().unwrap_or([0; 4]),
+            payload: command.payload().as_ptr(),
+            payload_len: command.payload().len(),
+        };
+        if let Some(callback) = callback {
+            // SAFETY: `frame` 在回调期间有效, 回调本身由调用方保证
+            unsafe { callback(user_data, &frame) };
+        }
+    }
+    commands.len()
+}
+
+/// 解码器因校验失败、长度超限等原因丢弃的帧数.
+///
+/// # Safety
+/// `decoder` 必须为空或由 [`bln_decoder_new`] 创建.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bln_decoder_dropped(decoder: *const BlnDecoder) -> usize {
+    // SAFETY: 由调用方保证
+    unsafe { decoder.as_ref() }.map_or(0, |decoder| decoder.dropped)
+}
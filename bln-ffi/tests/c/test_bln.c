@@ -0,0 +1,80 @@
+/* bln-ffi 的 C 测试程序, 由 tests/c_api.rs 编译并运行. 所有检查通过时返回 0. */
+#include <stdio.h>
+#include <string.h>
+
+#include "bln.h"
+
+#define CHECK(cond)                                                            \
+  do {                                                                         \
+    if (!(cond)) {                                                             \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+      return 1;                                                                \
+    }                                                                          \
+  } while (0)
+
+struct Received {
+  size_t count;
+  uint8_t cmd_id;
+  uint8_t status;
+  uint8_t reserved[4];
+  uint8_t payload[16];
+  size_t payload_len;
+};
+
+static void on_frame(void *user_data, const BlnFrame *frame) {
+  struct Received *received = user_data;
+  received->count++;
+  received->cmd_id = frame->cmd_id;
+  received->status = frame->status;
+  memcpy(received->reserved, frame->reserved, sizeof received->reserved);
+  received->payload_len = frame->payload_len;
+  if (frame->payload_len <= sizeof received->payload) {
+    memcpy(received->payload, frame->payload, frame->payload_len);
+  }
+}
+
+int main(void) {
+  /* GetPositionRsq: 55 AA 33 00 00 00 00 00 00 33 */
+  const uint8_t get_position[] = {0x55, 0xAA, 0x33, 0, 0, 0, 0, 0, 0, 0x33};
+  CHECK(bln_bcc(get_position + 2, sizeof get_position - 3) == 0x33);
+  CHECK(bln_bcc(NULL, 0) == 0);
+
+  const uint8_t payload[] = {1, 2, 3};
+  BlnFrame frame = {0x93, 2, {0, 7, 0, 0}, payload, sizeof payload};
+  uint8_t out[32];
+  size_t written = 0;
+  CHECK(bln_encode_frame(&frame, out, sizeof out, &written) == BLN_RESULT_OK);
+  CHECK(written == bln_frame_len(sizeof payload));
+  CHECK(out[0] == 0x55 && out[1] == 0xAA && out[2] == 0x93);
+  CHECK(out[written - 1] == bln_bcc(out + 2, written - 3));
+
+  CHECK(bln_encode_frame(&frame, out, 4, &written) == BLN_RESULT_BUFFER_TOO_SMALL);
+  CHECK(bln_encode_frame(NULL, out, sizeof out, &written) == BLN_RESULT_NULL_POINTER);
+  BlnFrame bad_status = frame;
+  bad_status.status = 8;
+  CHECK(bln_encode_frame(&bad_status, out, sizeof out, &written) == BLN_RESULT_INVALID_STATUS);
+
+  /* 分两次写入, 前面夹带一个校验错误的帧 */
+  CHECK(bln_encode_frame(&frame, out, sizeof out, &written) == BLN_RESULT_OK);
+  uint8_t corrupted[sizeof get_position];
+  memcpy(corrupted, get_position, sizeof corrupted);
+  corrupted[sizeof corrupted - 1] ^= 0xFF;
+
+  struct Received received = {0};
+  BlnDecoder *decoder = bln_decoder_new();
+  CHECK(decoder != NULL);
+  CHECK(bln_decoder_feed(decoder, corrupted, sizeof corrupted, on_frame, &received) == 0);
+  CHECK(bln_decoder_feed(decoder, out, 5, on_frame, &received) == 0);
+  CHECK(bln_decoder_feed(decoder, out + 5, written - 5, on_frame, &received) == 1);
+  CHECK(received.count == 1);
+  CHECK(received.cmd_id == 0x93 && received.status == 2);
+  CHECK(received.reserved[1] == 7);
+  CHECK(received.payload_len == sizeof payload);
+  CHECK(memcmp(received.payload, payload, sizeof payload) == 0);
+  CHECK(bln_decoder_dropped(decoder) == 1);
+  bln_decoder_free(decoder);
+  bln_decoder_free(NULL);
+
+  puts("bln-ffi C test passed");
+  return 0;
+}
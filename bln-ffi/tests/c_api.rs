@@ -0,0 +1,46 @@
+//! 编译并运行 `tests/c/test_bln.c`, 通过生成的头文件和动态库验证 C 接口.
+use std::{env, fs, path::PathBuf, process::Command};
+
+/// 构建脚本在 `OUT_DIR` 中生成的头文件所在的目录.
+const GENERATED_DIR: &str = env!("OUT_DIR");
+
+#[test]
+fn test_header_is_up_to_date() {
+    let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let committed = fs::read_to_string(crate_dir.join("include/bln.h")).unwrap();
+    let generated = fs::read_to_string(PathBuf::from(GENERATED_DIR).join("bln.h")).unwrap();
+    assert!(
+        committed == generated,
+        "include/bln.h 已过期, 请运行 `BLN_FFI_UPDATE_HEADER=1 cargo build -p bln-ffi` 更新"
+    );
+}
+
+#[test]
+fn test_c_program() {
+    let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // 测试程序和 cargo test 构建的动态库都位于 target/<profile>/deps
+    let lib_dir = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let exe = lib_dir.join("bln_ffi_c_test");
+
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".into()))
+        .arg(crate_dir.join("tests/c/test_bln.c"))
+        .arg("-I")
+        .arg(GENERATED_DIR)
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror"])
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lbln_ffi")
+        .arg("-o")
+        .arg(&exe)
+        .status()
+        .expect("找不到 C 编译器, 可以通过 CC 环境变量指定");
+    assert!(status.success(), "编译 C 测试程序失败");
+
+    let output = Command::new(&exe).output().unwrap();
+    assert!(
+        output.status.success(),
+        "C 测试程序失败: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,132 @@
+use bln::protocol::types::{BlnCommandDecode, BlnCommandEncoder, BlnDeviceStatus, BlnProtocolType};
+use bytes::BytesMut;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use protocol::{
+    traits::{FrameGenerator, ParseProtocol},
+    types::Command,
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// 统计分配次数的全局分配器, 用于衡量每帧的内存分配开销.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// 每个测试流中的帧数量.
+const FRAMES: usize = 20_000;
+/// 模拟一次 socket 读取的字节数.
+const READ_SIZE: usize = 1024;
+
+/// 简单的 xorshift 伪随机数生成器, 保证每次运行生成相同的噪声.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next_u8(&mut self) -> u8 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 as u8
+    }
+}
+
+fn frame(message: BlnProtocolType) -> Vec<u8> {
+    BlnCommandEncoder
+        .create_frame(Command::try_from(message).unwrap())
+        .unwrap()
+        .to_vec()
+}
+
+/// 生成只包含有效帧的字节流.
+fn clean_stream() -> Vec<u8> {
+    let mut stream = vec![];
+    for i in 0..FRAMES {
+        let message = if i % 2 == 0 {
+            BlnProtocolType::GetPositionRsp(i as f32, -(i as f32), BlnDeviceStatus::MOVING)
+        } else {
+            BlnProtocolType::PositionReached(i as f32, 0.5)
+        };
+        stream.extend(frame(message));
+    }
+    stream
+}
+
+/// 生成在有效帧之间混入随机噪声 (包括伪造帧头) 的字节流.
+fn noisy_stream() -> Vec<u8> {
+    let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+    let mut stream = vec![];
+    for i in 0..FRAMES {
+        for _ in 0..(rng.next_u8() % 32) {
+            stream.push(rng.next_u8());
+        }
+        if i % 8 == 0 {
+            // 伪造帧头, 其后的长度字段是随机的
+            stream.extend([0x55, 0xAA]);
+        }
+        stream.extend(frame(BlnProtocolType::GetPositionRsp(
+            i as f32,
+            1.0,
+            BlnDeviceStatus::empty(),
+        )));
+    }
+    stream
+}
+
+/// 按固定大小分块喂给解码器, 返回解析出的帧数量.
+fn decode(stream: &[u8]) -> usize {
+    let mut decoder = BlnCommandDecode::default();
+    let mut buf = BytesMut::with_capacity(READ_SIZE * 2);
+    let mut count = 0;
+    for chunk in stream.chunks(READ_SIZE) {
+        buf.extend_from_slice(chunk);
+        if let Some(commands) = decoder.parse_protocol_frame(&mut buf) {
+            count += commands.len();
+        }
+    }
+    count
+}
+
+/// 统计解码干净字节流时每帧的平均分配次数.
+fn report_allocations() {
+    let stream = clean_stream();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let frames = decode(&stream);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!(
+        "bln_decode/allocations: {:.2} per frame ({allocations} for {frames} frames)",
+        allocations as f64 / frames as f64
+    );
+}
+
+fn bench_decode(c: &mut Criterion) {
+    report_allocations();
+
+    let mut group = c.benchmark_group("bln_decode");
+    for (name, stream) in [("clean", clean_stream()), ("noisy", noisy_stream())] {
+        group.throughput(Throughput::Bytes(stream.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &stream, |b, stream| {
+            b.iter(|| decode(black_box(stream)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_decode);
+criterion_main!(benches);
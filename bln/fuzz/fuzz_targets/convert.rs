@@ -0,0 +1,28 @@
+#![no_main]
+
+use bln::protocol::types::BlnProtocolType;
+use libfuzzer_sys::fuzz_target;
+use protocol::types::Command;
+
+// 由任意字节构造 `Command` 并尝试转换为 `BlnProtocolType`, 断言不会 panic,
+// 且转换成功的消息可以无损地转换回 `Command`.
+// 负载中可能包含 NaN, 因此比较重新编码后的字节而不是消息本身.
+fuzz_target!(|data: &[u8]| {
+    let [cmd, status, payload @ ..] = data else {
+        return;
+    };
+    let command = Command::new(*cmd)
+        .with_status(*status)
+        .with_payload(payload.to_vec());
+
+    if let Ok(message) = BlnProtocolType::try_from(command) {
+        let encoded = Command::try_from(message).expect("已解析的消息必须可以重新编码");
+        let decoded =
+            BlnProtocolType::try_from(encoded.clone()).expect("重新编码的消息必须可以解析");
+        let reencoded = Command::try_from(decoded).expect("已解析的消息必须可以重新编码");
+        assert_eq!(reencoded.cmd_id(), encoded.cmd_id());
+        assert_eq!(reencoded.response_status(), encoded.response_status());
+        assert_eq!(reencoded.payload(), encoded.payload());
+        assert_eq!(reencoded.reserved(), encoded.reserved());
+    }
+});
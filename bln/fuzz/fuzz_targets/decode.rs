@@ -0,0 +1,30 @@
+#![no_main]
+
+use bln::protocol::types::BlnCommandDecode;
+use bytes::BytesMut;
+use libfuzzer_sys::fuzz_target;
+use protocol::traits::ParseProtocol;
+
+// 将任意字节流按数据本身决定的块大小喂给解码器, 断言不会 panic 且内存占用有界.
+fuzz_target!(|data: &[u8]| {
+    let Some((&chunk, bytes)) = data.split_first() else {
+        return;
+    };
+    let chunk = usize::from(chunk).max(1);
+
+    let mut decoder = BlnCommandDecode::default();
+    let mut buf = BytesMut::new();
+    let mut decoded_payload = 0;
+    for piece in bytes.chunks(chunk) {
+        buf.extend_from_slice(piece);
+        if let Some(commands) = decoder.parse_protocol_frame(&mut buf) {
+            decoded_payload += commands
+                .iter()
+                .map(|c| c.payload().len())
+                .sum::<usize>();
+        }
+        // 缓冲区中保留的数据不可能超过已输入的数据.
+        assert!(buf.len() <= bytes.len());
+    }
+    assert!(decoded_payload + buf.len() <= bytes.len());
+});
@@ -0,0 +1,888 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use bytes::BytesMut;
+use protocol::{
+    traits::{FrameGenerator, ParseProtocol},
+    types::{Command, ProtocolError},
+};
+use stream::traits::{AsyncFrameReader, AsyncFrameWriter, AsyncStreamSplit};
+use thiserror::Error;
+use tokio::time;
+use tracing::{debug, info, instrument, warn};
+
+use crate::protocol::{
+    motion::{MoveError, MoveState, MoveTracker},
+    types::{
+        BlnAxis, BlnCommandDecode, BlnCommandEncoder, BlnConfigId, BlnDeviceStatus, BlnErrorCause,
+        BlnProtocolType,
+    },
+    validate::{CommandValidator, ValidationError},
+};
+
+/// `BlnClient` 在调用过程中可能返回的错误.
+#[derive(Error, Debug)]
+pub enum BlnClientError {
+    #[error("设备返回错误: {0:?}")]
+    Device(BlnErrorCause),
+    #[error(transparent)]
+    Protocol(#[from] ProtocolError),
+    #[error(transparent)]
+    Move(#[from] MoveError),
+    #[error("命令未通过发送前校验: {0}")]
+    Invalid(#[from] ValidationError),
+    #[error("dry-run 模式下命令没有发送, 不会有响应")]
+    DryRun,
+    #[error("运动被停止或急停中断")]
+    Stopped,
+    #[error("等待响应超时")]
+    Timeout,
+    #[error("连接已关闭")]
+    Closed,
+    #[error("传输错误: {0}")]
+    Transport(color_eyre::Report),
+}
+
+/// `BlnClient` 是 BLN 设备的高级异步客户端.
+///
+/// 它持有传输层的读写两端以及 BLN 编解码器, 对外提供类型化的异步方法
+/// (如 [`BlnClient::move_to`], [`BlnClient::position`]), 调用方无需手动构造 `Command`.
+/// 设备返回的 `ErrorRsp` 会被映射为 [`BlnClientError::Device`].
+pub struct BlnClient<R, W> {
+    /// 传输层的读取端.
+    reader: R,
+    /// 传输层的写入端.
+    writer: W,
+    /// BLN 协议编码器.
+    encoder: BlnCommandEncoder,
+    /// BLN 协议解码器.
+    decoder: BlnCommandDecode,
+    /// 读取缓冲区, 保存尚未组成完整帧的字节.
+    buf: BytesMut,
+    /// 已解码但尚未被消费的消息.
+    pending: VecDeque<Result<BlnProtocolType, ProtocolError>>,
+    /// 跟踪 `move_to` 两阶段事务的状态机.
+    move_tracker: MoveTracker,
+    /// 普通请求等待响应的超时时间.
+    timeout: Duration,
+    /// 回零、相对移动等运动命令在确认后等待完成的超时时间.
+    motion_timeout: Duration,
+    /// 发送前对命令参数的校验, 以及 dry-run 模式.
+    validator: CommandValidator,
+}
+
+impl<R, W> BlnClient<R, W>
+where
+    R: AsyncFrameReader + Send,
+    W: AsyncFrameWriter + Send,
+{
+    /// 普通请求等待响应的默认超时时间.
+    const TIMEOUT: Duration = Duration::from_secs(1);
+    /// 运动命令等待完成的默认超时时间.
+    const MOTION_TIMEOUT: Duration = Duration::from_secs(30);
+    /// 读取缓冲区的初始容量.
+    const BUFFER_CAPACITY: usize = 1024;
+
+    /// 基于任意实现了 `AsyncStreamSplit` 的传输层创建客户端.
+    pub fn new<Io>(stream: Io) -> Self
+    where
+        Io: AsyncStreamSplit<Reader = R, Writer = W>,
+    {
+        let (reader, writer) = stream.into_split();
+        Self {
+            reader,
+            writer,
+            encoder: BlnCommandEncoder,
+            decoder: BlnCommandDecode::default(),
+            buf: BytesMut::with_capacity(Self::BUFFER_CAPACITY),
+            pending: VecDeque::new(),
+            move_tracker: MoveTracker::default(),
+            timeout: Self::TIMEOUT,
+            motion_timeout: Self::MOTION_TIMEOUT,
+            validator: CommandValidator::default(),
+        }
+    }
+
+    /// 设置普通请求等待响应的超时时间.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 设置运动命令 (回零、相对移动、停止等) 在确认后等待完成的超时时间.
+    pub fn motion_timeout(mut self, motion_timeout: Duration) -> Self {
+        self.motion_timeout = motion_timeout;
+        self
+    }
+
+    /// 设置 `move_to` 使用的移动状态机, 可用于配置确认和到达的超时时间.
+    pub fn move_tracker(mut self, move_tracker: MoveTracker) -> Self {
+        self.move_tracker = move_tracker;
+        self
+    }
+
+    /// 设置发送前的命令校验, 包括软限位、步长、速度上限和 dry-run 模式.
+    pub fn validator(mut self, validator: CommandValidator) -> Self {
+        self.validator = validator;
+        self
+    }
+
+    /// 返回最近一次移动的状态.
+    pub fn move_state(&self) -> MoveState {
+        self.move_tracker.state()
+    }
+
+    /// 移动到指定位置, 并等待设备报告到达.
+    ///
+    /// # 返回
+    /// 设备在 `PositionReached` 中回报的实际位置.
+    #[instrument(skip(self), err)]
+    pub async fn move_to(&mut self, x: f32, y: f32) -> Result<(f32, f32), BlnClientError> {
+        // 排队的移动在之后才会发出, 因此在提交给状态机之前就完成校验, 之后直接发出.
+        let request = BlnProtocolType::SetPositionRsq(x, y);
+        self.locate(&request).await?;
+        self.validator.validate(&request)?;
+        if self.validator.is_dry_run() {
+            self.transmit(request).await?;
+            return Err(BlnClientError::DryRun);
+        }
+        // 之前的 `move_to` 被取消时, 它的移动可能仍停留在进行中, 先按截止时间结束它.
+        let now = Instant::now();
+        self.move_tracker.poll_timeout(now);
+        if let Some(request) = self.move_tracker.request_move(x, y, now)? {
+            self.transmit_move(request).await?;
+        }
+        // 移动进行中时截止时间总是存在, 移动结束 (到达、失败或超时) 后截止时间被清除.
+        // 响应由 `recv` 交给状态机.
+        while let Some(deadline) = self.move_tracker.deadline() {
+            match time::timeout_at(deadline.into(), self.recv()).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(BlnClientError::Protocol(e))) => {
+                    warn!("[BlnClient] Failed to convert command: {}", e);
+                }
+                Ok(Err(e)) => {
+                    // 连接已经不可用, 不会再收到这次移动的响应.
+                    self.move_tracker.abort();
+                    return Err(e);
+                }
+                Err(_) => {
+                    self.move_tracker.poll_timeout(Instant::now());
+                }
+            }
+        }
+        match self.move_tracker.state() {
+            MoveState::Reached { position, .. } => Ok(position),
+            MoveState::Failed { cause, .. } => Err(BlnClientError::Device(cause)),
+            MoveState::Stopped { .. } => Err(BlnClientError::Stopped),
+            _ => Err(BlnClientError::Timeout),
+        }
+    }
+
+    /// 查询设备的当前位置.
+    ///
+    /// # 返回
+    /// 设备在 `GetPositionRsp` 中回报的 `(x, y, status)`.
+    #[instrument(skip(self), err)]
+    pub async fn position(&mut self) -> Result<(f32, f32, BlnDeviceStatus), BlnClientError> {
+        self.request(BlnProtocolType::GetPositionRsq, |message| match message {
+            BlnProtocolType::GetPositionRsp(x, y, status) => Some((x, y, status)),
+            _ => None,
+        })
+        .await
+    }
+
+    /// 读取一个配置项的值.
+    #[instrument(skip(self), err)]
+    pub async fn read_config(&mut self, item: BlnConfigId) -> Result<f32, BlnClientError> {
+        self.request(
+            BlnProtocolType::ReadConfigRsq(item),
+            |message| match message {
+                BlnProtocolType::ReadConfigRsp(read, value) if read == item => Some(value),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// 写入一个配置项的值. 写入的值需要通过 [`BlnClient::save_config`] 保存, 否则掉电后丢失.
+    #[instrument(skip(self), err)]
+    pub async fn write_config(
+        &mut self,
+        item: BlnConfigId,
+        value: f32,
+    ) -> Result<(), BlnClientError> {
+        self.request(
+            BlnProtocolType::WriteConfigRsq(item, value),
+            |message| match message {
+                BlnProtocolType::WriteConfigRsp(written) if written == item => Some(()),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// 列出设备支持的配置项.
+    #[instrument(skip(self), err)]
+    pub async fn list_config(&mut self) -> Result<Vec<BlnConfigId>, BlnClientError> {
+        self.request(BlnProtocolType::ListConfigRsq, |message| match message {
+            BlnProtocolType::ListConfigRsp(mask) => Some(BlnConfigId::from_mask(mask).collect()),
+            _ => None,
+        })
+        .await
+    }
+
+    /// 将当前配置保存到设备的闪存.
+    #[instrument(skip(self), err)]
+    pub async fn save_config(&mut self) -> Result<(), BlnClientError> {
+        self.request(BlnProtocolType::SaveConfigRsq, |message| {
+            (message == BlnProtocolType::SaveConfigRsp).then_some(())
+        })
+        .await
+    }
+
+    /// 急停, 设备立即停止所有轴.
+    #[instrument(skip(self), err)]
+    pub async fn emergency_stop(&mut self) -> Result<(), BlnClientError> {
+        self.request(BlnProtocolType::EmergencyStopRsq, |message| {
+            (message == BlnProtocolType::EmergencyStopRsp).then_some(())
+        })
+        .await
+    }
+
+    /// 减速停止当前的运动, 并等待设备报告已停止.
+    ///
+    /// # 返回
+    /// 设备在 `Stopped` 中回报的停止位置.
+    #[instrument(skip(self), err)]
+    pub async fn stop(&mut self) -> Result<(f32, f32), BlnClientError> {
+        self.motion(
+            BlnProtocolType::StopRsq,
+            BlnProtocolType::StopRsp,
+            |message| match message {
+                BlnProtocolType::Stopped(x, y) => Some((x, y)),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// 回零, 并等待设备报告回零完成.
+    ///
+    /// # 返回
+    /// 设备在 `HomeReached` 中回报的回零后位置.
+    #[instrument(skip(self), err)]
+    pub async fn home(&mut self) -> Result<(f32, f32), BlnClientError> {
+        self.motion(
+            BlnProtocolType::HomeRsq,
+            BlnProtocolType::HomeRsp,
+            |message| match message {
+                BlnProtocolType::HomeReached(x, y) => Some((x, y)),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// 从当前位置移动给定的位移, 并等待设备报告到达.
+    ///
+    /// # 返回
+    /// 设备在 `MoveRelativeReached` 中回报的到达后的绝对位置.
+    #[instrument(skip(self), err)]
+    pub async fn move_by(&mut self, dx: f32, dy: f32) -> Result<(f32, f32), BlnClientError> {
+        let request = BlnProtocolType::MoveRelativeRsq(dx, dy);
+        self.locate(&request).await?;
+        self.motion(
+            request,
+            BlnProtocolType::MoveRelativeRsp,
+            |message| match message {
+                BlnProtocolType::MoveRelativeReached(x, y) => Some((x, y)),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// 以给定速度点动一个轴, 速度的符号表示方向. 设备确认后立即返回, 轴持续运动直到 [`BlnClient::jog_stop`].
+    #[instrument(skip(self), err)]
+    pub async fn jog_start(&mut self, axis: BlnAxis, velocity: f32) -> Result<(), BlnClientError> {
+        self.request(BlnProtocolType::JogStartRsq(axis, velocity), |message| {
+            (message == BlnProtocolType::JogStartRsp).then_some(())
+        })
+        .await
+    }
+
+    /// 停止一个轴的点动, 并等待设备报告已停止.
+    ///
+    /// # 返回
+    /// 设备在 `JogStopped` 中回报的停止位置.
+    #[instrument(skip(self), err)]
+    pub async fn jog_stop(&mut self, axis: BlnAxis) -> Result<(f32, f32), BlnClientError> {
+        self.motion(
+            BlnProtocolType::JogStopRsq(axis),
+            BlnProtocolType::JogStopRsp,
+            |message| match message {
+                BlnProtocolType::JogStopped(x, y) => Some((x, y)),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// 设置之后的运动使用的速度, 不会写入闪存.
+    #[instrument(skip(self), err)]
+    pub async fn set_velocity(&mut self, velocity: f32) -> Result<(), BlnClientError> {
+        self.request(BlnProtocolType::SetVelocityRsq(velocity), |message| {
+            (message == BlnProtocolType::SetVelocityRsp).then_some(())
+        })
+        .await
+    }
+
+    /// 发送一个两阶段的运动命令: 在普通超时时间内等待确认 `ack`,
+    /// 再在运动超时时间内等待 `accept` 接受的完成响应.
+    ///
+    /// 等待完成期间收到 `EmergencyStopRsp` 时返回 [`BlnClientError::Stopped`].
+    async fn motion<T>(
+        &mut self,
+        request: BlnProtocolType,
+        ack: BlnProtocolType,
+        mut accept: impl FnMut(BlnProtocolType) -> Option<T>,
+    ) -> Result<T, BlnClientError> {
+        self.request(request, |message| (message == ack).then_some(()))
+            .await?;
+        let done = self
+            .wait(self.motion_timeout, |message| match message {
+                BlnProtocolType::EmergencyStopRsp => Some(None),
+                message => accept(message).map(Some),
+            })
+            .await?;
+        done.ok_or(BlnClientError::Stopped)
+    }
+
+    /// 校验 `message` 需要当前位置而位置尚未得知时 (如连接后的第一次移动), 先向设备查询位置.
+    ///
+    /// dry-run 模式下不会查询, 校验会跳过依赖位置的检查.
+    async fn locate(&mut self, message: &BlnProtocolType) -> Result<(), BlnClientError> {
+        if self.validator.requires_position(message) && !self.validator.is_dry_run() {
+            self.position().await?;
+        }
+        Ok(())
+    }
+
+    /// 发送一个请求, 并在超时时间内等待 `accept` 接受的响应.
+    ///
+    /// 设备返回的 `ErrorRsp` 映射为 [`BlnClientError::Device`], 其余与请求无关的响应被忽略.
+    async fn request<T>(
+        &mut self,
+        request: BlnProtocolType,
+        accept: impl FnMut(BlnProtocolType) -> Option<T>,
+    ) -> Result<T, BlnClientError> {
+        self.send(request).await?;
+        if self.validator.is_dry_run() {
+            return Err(BlnClientError::DryRun);
+        }
+        self.wait(self.timeout, accept).await
+    }
+
+    /// 在 `timeout` 内等待 `accept` 接受的响应, `ErrorRsp` 映射为 [`BlnClientError::Device`].
+    async fn wait<T>(
+        &mut self,
+        timeout: Duration,
+        mut accept: impl FnMut(BlnProtocolType) -> Option<T>,
+    ) -> Result<T, BlnClientError> {
+        let deadline = time::Instant::now() + timeout;
+        loop {
+            match time::timeout_at(deadline, self.recv()).await {
+                Ok(Ok(BlnProtocolType::ErrorRsp(cause))) => {
+                    return Err(BlnClientError::Device(cause));
+                }
+                Ok(Ok(message)) => match accept(message) {
+                    Some(value) => return Ok(value),
+                    None => debug!(?message, "忽略与请求无关的响应"),
+                },
+                Ok(Err(BlnClientError::Protocol(e))) => {
+                    warn!("[BlnClient] Failed to convert command: {}", e);
+                }
+                Ok(Err(e)) => return Err(e),
+                Err(_) => return Err(BlnClientError::Timeout),
+            }
+        }
+    }
+
+    /// 将一条 BLN 消息校验并编码后发送给设备.
+    ///
+    /// 未通过校验的消息以 [`BlnClientError::Invalid`] 返回.
+    /// dry-run 模式下只记录编码后的帧, 不写入传输层.
+    pub async fn send(&mut self, message: BlnProtocolType) -> Result<(), BlnClientError> {
+        self.validator.validate(&message)?;
+        self.transmit(message).await
+    }
+
+    /// 将一条已校验的 BLN 消息编码后发送给设备, dry-run 模式下只记录编码后的帧.
+    async fn transmit(&mut self, message: BlnProtocolType) -> Result<(), BlnClientError> {
+        let command = Command::try_from(message)?;
+        let frame = self.encoder.create_frame(command)?;
+        if self.validator.is_dry_run() {
+            info!(?message, ?frame, "dry-run: 帧未发送");
+            return Ok(());
+        }
+        self.writer
+            .write_frame(&frame)
+            .await
+            .map_err(BlnClientError::Transport)?;
+        Ok(())
+    }
+
+    /// 发出移动状态机给出的 `SetPositionRsq`. 发送失败时放弃这次移动, 避免状态机一直停留在进行中.
+    async fn transmit_move(&mut self, request: BlnProtocolType) -> Result<(), BlnClientError> {
+        let result = self.transmit(request).await;
+        if result.is_err() {
+            self.move_tracker.abort();
+        }
+        result
+    }
+
+    /// 接收下一条来自设备的 BLN 消息.
+    ///
+    /// 收到的每条消息都会交给移动状态机, 因此即使 `move_to` 被取消, 之后通过任何方法收到的
+    /// 到达或错误响应也会结束那次移动. 当前移动到达后, 状态机开始的下一个排队移动也在这里发出.
+    ///
+    /// 无法转换为 `BlnProtocolType` 的帧会以 [`BlnClientError::Protocol`] 返回,
+    /// 不会影响后续消息的接收.
+    pub async fn recv(&mut self) -> Result<BlnProtocolType, BlnClientError> {
+        loop {
+            if let Some(message) = self.pending.pop_front() {
+                let message = message?;
+                self.validator.observe(&message);
+                if let Some(next) = self.move_tracker.handle_response(&message, Instant::now()) {
+                    self.transmit_move(next).await?;
+                }
+                return Ok(message);
+            }
+            let len = self
+                .reader
+                .read_frame(&mut self.buf)
+                .await
+                .map_err(BlnClientError::Transport)?;
+            if len == 0 {
+                return Err(BlnClientError::Closed);
+            }
+            if let Some(commands) = self.decoder.parse_protocol_frame(&mut self.buf) {
+                self.pending
+                    .extend(commands.into_iter().map(BlnProtocolType::try_from));
+            }
+            for diagnostic in self.decoder.take_diagnostics() {
+                warn!("[BlnClient] Decoder diagnostic: {}", diagnostic);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{motion::MovePolicy, validate::AxisLimits};
+    use stream::client::connect;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{
+            TcpListener,
+            tcp::{OwnedReadHalf, OwnedWriteHalf},
+        },
+    };
+
+    type TestClient = BlnClient<OwnedReadHalf, OwnedWriteHalf>;
+
+    // 将一条 BLN 响应编码为完整的字节帧.
+    fn frame(message: BlnProtocolType) -> Vec<u8> {
+        BlnCommandEncoder
+            .create_frame(message.try_into().unwrap())
+            .unwrap()
+            .to_vec()
+    }
+
+    // 启动一个模拟设备: 读取一个请求后依次回复给定的帧, 并保持连接直到客户端断开.
+    async fn device(replies: Vec<Vec<u8>>) -> (TestClient, tokio::task::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 64];
+            let len = socket.read(&mut request).await.unwrap();
+            request.truncate(len);
+            for reply in replies {
+                socket.write_all(&reply).await.unwrap();
+            }
+            let _ = socket.read(&mut [0; 1]).await;
+            request
+        });
+        let stream = connect(addr.to_string(), Duration::from_secs(1))
+            .await
+            .unwrap();
+        (BlnClient::new(stream), handle)
+    }
+
+    #[tokio::test]
+    async fn test_move_to_reached() {
+        let (mut client, handle) = device(vec![
+            frame(BlnProtocolType::SetPositionRsp),
+            frame(BlnProtocolType::PositionReached(1.5, 2.5)),
+        ])
+        .await;
+
+        assert_eq!(client.move_to(1.5, 2.5).await.unwrap(), (1.5, 2.5));
+        assert!(matches!(client.move_state(), MoveState::Reached { .. }));
+        drop(client);
+        assert_eq!(handle.await.unwrap()[2], 0x31);
+    }
+
+    #[tokio::test]
+    async fn test_move_to_device_error() {
+        let (mut client, _handle) = device(vec![frame(BlnProtocolType::ErrorRsp(
+            BlnErrorCause::InvalidArgument,
+        ))])
+        .await;
+
+        let result = client.move_to(1000.0, 0.0).await;
+        assert!(matches!(
+            result,
+            Err(BlnClientError::Device(BlnErrorCause::InvalidArgument))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_move_to_timeout() {
+        let (client, _handle) = device(vec![frame(BlnProtocolType::SetPositionRsp)]).await;
+        let mut client = client.move_tracker(
+            MoveTracker::default()
+                .ack_timeout(Duration::from_millis(50))
+                .reach_timeout(Duration::from_millis(50)),
+        );
+
+        let result = client.move_to(1.0, 2.0).await;
+        assert!(matches!(result, Err(BlnClientError::Timeout)));
+        assert!(matches!(client.move_state(), MoveState::TimedOut { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_move_to_sends_queued_move() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut requests = Vec::new();
+            for (x, y) in [(1.0, 1.0), (2.0, 2.0)] {
+                let mut request = vec![0; 64];
+                let len = socket.read(&mut request).await.unwrap();
+                requests.push(request[..len].to_vec());
+                // 第一个移动的响应在调用方放弃等待之后才到达.
+                time::sleep(Duration::from_millis(100)).await;
+                socket
+                    .write_all(&frame(BlnProtocolType::SetPositionRsp))
+                    .await
+                    .unwrap();
+                socket
+                    .write_all(&frame(BlnProtocolType::PositionReached(x, y)))
+                    .await
+                    .unwrap();
+            }
+            requests
+        });
+        let stream = connect(addr.to_string(), Duration::from_secs(1))
+            .await
+            .unwrap();
+        let mut client = BlnClient::new(stream)
+            .move_tracker(MoveTracker::default().policy(MovePolicy::Queue(1)));
+
+        let first = time::timeout(Duration::from_millis(20), client.move_to(1.0, 1.0)).await;
+        assert!(first.is_err());
+        assert_eq!(client.move_to(2.0, 2.0).await.unwrap(), (2.0, 2.0));
+
+        let requests = handle.await.unwrap();
+        assert_eq!(
+            requests[1],
+            frame(BlnProtocolType::SetPositionRsq(2.0, 2.0))
+        );
+    }
+
+    // 启动一个模拟设备: 对每个请求依次回复给定的帧, 返回收到的全部请求.
+    async fn scripted_device(
+        script: Vec<Vec<Vec<u8>>>,
+    ) -> (TestClient, tokio::task::JoinHandle<Vec<Vec<u8>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut requests = Vec::new();
+            for replies in script {
+                let mut request = vec![0; 64];
+                let len = socket.read(&mut request).await.unwrap();
+                requests.push(request[..len].to_vec());
+                for reply in replies {
+                    socket.write_all(&reply).await.unwrap();
+                }
+            }
+            let _ = socket.read(&mut [0; 1]).await;
+            requests
+        });
+        let stream = connect(addr.to_string(), Duration::from_secs(1))
+            .await
+            .unwrap();
+        (BlnClient::new(stream), handle)
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_move_does_not_block_next_move() {
+        let (client, handle) = scripted_device(vec![
+            vec![],
+            vec![
+                frame(BlnProtocolType::SetPositionRsp),
+                frame(BlnProtocolType::PositionReached(2.0, 2.0)),
+            ],
+        ])
+        .await;
+        let mut client = client.move_tracker(
+            MoveTracker::default()
+                .policy(MovePolicy::Reject)
+                .ack_timeout(Duration::from_millis(50)),
+        );
+
+        let first = time::timeout(Duration::from_millis(10), client.move_to(1.0, 1.0)).await;
+        assert!(first.is_err());
+        assert!(client.move_state().is_in_flight());
+
+        // 被取消的移动超过确认的截止时间后, 新的移动不再被拒绝
+        time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(client.move_to(2.0, 2.0).await.unwrap(), (2.0, 2.0));
+        drop(client);
+        assert_eq!(handle.await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_responses_to_other_requests_update_move() {
+        let (mut client, _handle) = scripted_device(vec![
+            vec![],
+            vec![
+                frame(BlnProtocolType::SetPositionRsp),
+                frame(BlnProtocolType::PositionReached(1.0, 1.0)),
+                frame(BlnProtocolType::GetPositionRsp(1.0, 1.0, 0.into())),
+            ],
+        ])
+        .await;
+
+        let first = time::timeout(Duration::from_millis(10), client.move_to(1.0, 1.0)).await;
+        assert!(first.is_err());
+
+        // 被取消的移动的响应在等待位置查询时到达, 同样会结束那次移动
+        client.position().await.unwrap();
+        assert!(matches!(
+            client.move_state(),
+            MoveState::Reached {
+                position: (1.0, 1.0),
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_position() {
+        let (mut client, handle) = device(vec![frame(BlnProtocolType::GetPositionRsp(
+            3.0,
+            4.0,
+            0x41.into(),
+        ))])
+        .await;
+
+        let (x, y, status) = client.position().await.unwrap();
+        assert_eq!((x, y), (3.0, 4.0));
+        assert_eq!(
+            status,
+            BlnDeviceStatus::MOVING | BlnDeviceStatus::EMERGENCY_STOP
+        );
+        drop(client);
+        assert_eq!(handle.await.unwrap()[2], 0x33);
+    }
+
+    #[tokio::test]
+    async fn test_read_config_skips_unrelated_responses() {
+        let (mut client, handle) = device(vec![
+            frame(BlnProtocolType::ReadConfigRsp(
+                BlnConfigId::Acceleration,
+                9.0,
+            )),
+            frame(BlnProtocolType::ReadConfigRsp(BlnConfigId::Speed, 20.0)),
+        ])
+        .await;
+
+        assert_eq!(client.read_config(BlnConfigId::Speed).await.unwrap(), 20.0);
+        drop(client);
+        assert_eq!(handle.await.unwrap()[2], 0x35);
+    }
+
+    #[tokio::test]
+    async fn test_config_not_found() {
+        let (mut client, _handle) = device(vec![frame(BlnProtocolType::ErrorRsp(
+            BlnErrorCause::ConfigNotFound,
+        ))])
+        .await;
+
+        let result = client.write_config(BlnConfigId::Other(0x30), 1.0).await;
+        assert!(matches!(
+            result,
+            Err(BlnClientError::Device(BlnErrorCause::ConfigNotFound))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_config() {
+        let mask = BlnConfigId::to_mask([BlnConfigId::Speed, BlnConfigId::SoftLimitMinX]);
+        let (mut client, _handle) = device(vec![frame(BlnProtocolType::ListConfigRsp(mask))]).await;
+
+        assert_eq!(
+            client.list_config().await.unwrap(),
+            vec![BlnConfigId::Speed, BlnConfigId::SoftLimitMinX]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_home_waits_for_completion() {
+        let (mut client, handle) = device(vec![
+            frame(BlnProtocolType::HomeRsp),
+            frame(BlnProtocolType::GetPositionRsp(
+                0.0,
+                0.0,
+                BlnDeviceStatus::MOVING,
+            )),
+            frame(BlnProtocolType::HomeReached(0.5, -0.5)),
+        ])
+        .await;
+
+        assert_eq!(client.home().await.unwrap(), (0.5, -0.5));
+        drop(client);
+        assert_eq!(handle.await.unwrap()[2], 0x41);
+    }
+
+    #[tokio::test]
+    async fn test_motion_interrupted_by_emergency_stop() {
+        let (mut client, _handle) = device(vec![
+            frame(BlnProtocolType::MoveRelativeRsp),
+            frame(BlnProtocolType::EmergencyStopRsp),
+        ])
+        .await;
+
+        let result = client.move_by(10.0, 0.0).await;
+        assert!(matches!(result, Err(BlnClientError::Stopped)));
+    }
+
+    #[tokio::test]
+    async fn test_motion_completion_timeout() {
+        let (client, _handle) = device(vec![frame(BlnProtocolType::JogStopRsp)]).await;
+        let mut client = client.motion_timeout(Duration::from_millis(50));
+
+        let result = client.jog_stop(BlnAxis::X).await;
+        assert!(matches!(result, Err(BlnClientError::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_command_is_not_sent() {
+        let (client, handle) = device(vec![]).await;
+        let mut client = client.validator(
+            CommandValidator::default().limits(BlnAxis::X, AxisLimits::new(-10.0, 10.0).unwrap()),
+        );
+
+        let result = client.move_to(f32::NAN, 0.0).await;
+        assert!(matches!(
+            result,
+            Err(BlnClientError::Invalid(ValidationError::NonFinite(_)))
+        ));
+        let result = client.move_to(20.0, 0.0).await;
+        assert!(matches!(
+            result,
+            Err(BlnClientError::Invalid(ValidationError::OutOfLimits { .. }))
+        ));
+        assert_eq!(client.move_state(), MoveState::Idle);
+        drop(client);
+        assert!(handle.await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_first_move_queries_position_for_step_check() {
+        let (client, handle) = device(vec![frame(BlnProtocolType::GetPositionRsp(
+            0.0,
+            0.0,
+            BlnDeviceStatus::empty(),
+        ))])
+        .await;
+        let mut client = client.validator(CommandValidator::default().max_step(1.0).unwrap());
+
+        let result = client.move_to(5.0, 0.0).await;
+        assert!(matches!(
+            result,
+            Err(BlnClientError::Invalid(
+                ValidationError::StepTooLarge { .. }
+            ))
+        ));
+        assert_eq!(client.move_state(), MoveState::Idle);
+        drop(client);
+        // 只发出了位置查询, 越界的移动没有发送
+        assert_eq!(
+            handle.await.unwrap(),
+            frame(BlnProtocolType::GetPositionRsq)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_does_not_transmit() {
+        let (client, handle) = device(vec![]).await;
+        let mut client = client.validator(CommandValidator::default().dry_run(true));
+
+        client.send(BlnProtocolType::HomeRsq).await.unwrap();
+        assert!(matches!(
+            client.move_to(1.0, 2.0).await,
+            Err(BlnClientError::DryRun)
+        ));
+        assert!(matches!(client.home().await, Err(BlnClientError::DryRun)));
+        assert_eq!(client.move_state(), MoveState::Idle);
+        drop(client);
+        assert!(handle.await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_with_unknown_position() {
+        let (client, handle) = device(vec![]).await;
+        let validator = CommandValidator::default()
+            .max_step(1.0)
+            .unwrap()
+            .limits(BlnAxis::X, AxisLimits::new(-10.0, 10.0).unwrap())
+            .dry_run(true);
+        let mut client = client.validator(validator);
+
+        // 不查询位置, 跳过步长检查后仍然记录将要发送的帧
+        assert!(matches!(
+            client.move_to(5.0, 0.0).await,
+            Err(BlnClientError::DryRun)
+        ));
+        assert!(matches!(
+            client.move_by(0.5, 0.0).await,
+            Err(BlnClientError::DryRun)
+        ));
+        drop(client);
+        assert!(handle.await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recv_reports_closed_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            // 接受连接后立即断开.
+            listener.accept().await.unwrap();
+        });
+        let stream = connect(addr.to_string(), Duration::from_secs(1))
+            .await
+            .unwrap();
+        let mut client: TestClient = BlnClient::new(stream);
+        handle.await.unwrap();
+
+        assert!(matches!(client.recv().await, Err(BlnClientError::Closed)));
+    }
+}
@@ -1,2 +1,10 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod client;
 pub mod protocol;
+#[cfg(feature = "std")]
 pub mod tui;
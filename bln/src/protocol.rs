@@ -1,9 +1,23 @@
+#[cfg(feature = "alloc")]
 mod conversions;
-mod types;
-use protocol::traits::ProtocolSplit;
+#[cfg(feature = "heapless")]
+pub mod fixed;
+#[cfg(feature = "std")]
+pub mod motion;
+#[cfg(feature = "std")]
+pub mod sequence;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod types;
+#[cfg(feature = "std")]
+pub mod validate;
+#[cfg(feature = "alloc")]
+use protocol::traits::{MessageProtocol, ProtocolSplit};
 
-use crate::protocol::types::{BlnCommandDecode, BlnCommandEncoder};
+#[cfg(feature = "alloc")]
+use crate::protocol::types::{BlnCommandDecode, BlnCommandEncoder, BlnProtocolType};
 
+#[cfg(feature = "alloc")]
 #[derive(Default)]
 pub struct BlnProtocol {
     /// 协议的编码器实例.
@@ -12,6 +26,7 @@ pub struct BlnProtocol {
     decode: BlnCommandDecode,
 }
 
+#[cfg(feature = "alloc")]
 impl ProtocolSplit for BlnProtocol {
     /// 定义 `BlnProtocol` 的编码器类型为 `BlnCommandEncoder`.
     type Encoder = BlnCommandEncoder;
@@ -24,3 +39,9 @@ impl ProtocolSplit for BlnProtocol {
         (self.decode, self.encode)
     }
 }
+
+#[cfg(feature = "alloc")]
+impl MessageProtocol for BlnProtocol {
+    /// `BlnProtocol` 的类型化消息为 `BlnProtocolType`.
+    type Message = BlnProtocolType;
+}
@@ -1,124 +1,14 @@
-use bytes::{Buf, BufMut, BytesMut};
-use protocol::types::{Command, ProtocolError};
-use std::convert::TryFrom;
-
-use crate::protocol::types::{BlnProtocolType, BlnResponseStatus};
-
-// 将通用的 `Command` 解析为具体的 `BlnProtocolType`.
-impl TryFrom<Command> for BlnProtocolType {
-    type Error = ProtocolError;
-
-    fn try_from(value: Command) -> Result<Self, Self::Error> {
-        let cmd_byte = *value
-            .cmd_type
-            .first()
-            .ok_or(ProtocolError::InvalidCommandType)?;
-
-        let status: BlnResponseStatus = value
-            .response_status
-            .ok_or(ProtocolError::InvalidPayload)? // 如果不存在则视为无效
-            .into();
-
-        // 1. 首先，统一处理所有命令的“错误”状态
-        if status == BlnResponseStatus::Error {
-            // 如果是错误响应, 则 payload 应该包含 1 字节的错误原因
-            let mut payload = value.payload.ok_or(ProtocolError::InvalidPayload)?;
-            if payload.len() != 1 {
-                return Err(ProtocolError::InvalidPayload);
-            }
-            return Ok(Self::ErrorRsp(payload.get_u8().into()));
-        }
-
-        // 2. 如果不是错误状态，再根据命令字处理各自的“成功”状态
-        match cmd_byte {
-            0x91 => match status {
-                BlnResponseStatus::Ok => {
-                    // 阶段1: 通信确认。payload 必须为空。
-                    if value.payload.is_some() {
-                        return Err(ProtocolError::InvalidPayload);
-                    }
-                    Ok(Self::SetPositionRsp)
-                }
-                BlnResponseStatus::OkWithData => {
-                    // 阶段2: 执行完成确认。payload 必须为 8 字节 (f32 + f32)。
-                    let mut payload = value.payload.ok_or(ProtocolError::InvalidPayload)?;
-                    if payload.len() != 8 {
-                        return Err(ProtocolError::InvalidPayload);
-                    }
-                    Ok(Self::PositionReached(
-                        payload.get_f32_le(),
-                        payload.get_f32_le(),
-                    ))
-                }
-                // 对于 0x91 命令，不应该出现 Error 之外的其他状态
-                _ => Err(ProtocolError::InvalidPayload),
-            },
-            0x93 => {
-                // 校验：成功有数据的响应，其 status 必须是 OkWithData
-                if status != BlnResponseStatus::OkWithData {
-                    return Err(ProtocolError::InvalidPayload); // 状态与命令不符
-                }
-                // 校验：payload 必须为 9 字节
-                let mut payload = value.payload.ok_or(ProtocolError::InvalidPayload)?;
-                if payload.len() != 9 {
-                    return Err(ProtocolError::InvalidPayload);
-                }
-                Ok(Self::GetPositionRsp(
-                    payload.get_f32_le(),
-                    payload.get_f32_le(),
-                    payload.get_u8(),
-                ))
-            }
-            _ => Err(ProtocolError::InvalidCommandType), // 未知的命令类型
-        }
-    }
-}
-
-// 将具体的 `BlnProtocolType` 转换为通用的 `Command` 以便后续生成字节帧.
-impl TryFrom<BlnProtocolType> for Command {
-    type Error = ProtocolError;
-
-    fn try_from(value: BlnProtocolType) -> Result<Self, Self::Error> {
-        match value {
-            BlnProtocolType::SetPositionRsq(pos1, pos2) => {
-                let mut cmd_type = BytesMut::with_capacity(1);
-                cmd_type.put_u8(0x31);
-                let mut load = BytesMut::with_capacity(8);
-                load.put_f32_le(pos1);
-                load.put_f32_le(pos2);
-                Ok(Command {
-                    cmd_type,
-                    response_status: None, // 生成请求时，响应状态通常不适用或为默认值
-                    payload: Some(load),
-                })
-            }
-            BlnProtocolType::GetPositionRsq => {
-                let mut cmd_type = BytesMut::with_capacity(1);
-                cmd_type.put_u8(0x33);
-                Ok(Command {
-                    cmd_type,
-                    response_status: None, // 生成请求时，响应状态通常不适用或为默认值
-                    payload: None,
-                })
-            }
-            // ErrorRsp, SetPositionRsp, PositionReached 等响应类型不应由从机主动创建, 因此不实现转换为 Command
-            _ => Err(ProtocolError::InvalidCommandType),
-        }
-    }
-}
+//! `BlnProtocolType` 与 `Command` 之间的转换由 `#[derive(ProtocolMessage)]` 生成,
+//! 这里测试生成的转换与协议约定一致.
 
 #[cfg(test)]
 mod tests {
-    use crate::protocol::types::{BlnErrorCause, BlnProtocolType, BlnResponseStatus};
+    use crate::protocol::types::{
+        BlnAxis, BlnConfigId, BlnDeviceStatus, BlnErrorCause, BlnProtocolType, BlnResponseStatus,
+    };
+    use alloc::{string::ToString, vec};
     use bytes::{BufMut, BytesMut};
-    use protocol::types::{Command, ProtocolError};
-
-    // Helper to create a BytesMut from a slice
-    fn b(s: &[u8]) -> BytesMut {
-        let mut buf = BytesMut::new();
-        buf.put_slice(s);
-        buf
-    }
+    use protocol::types::{Command, Language, ProtocolError};
 
     // --- Tests for TryFrom<Command> for BlnProtocolType ---
 
@@ -126,11 +16,9 @@ mod tests {
     fn test_parse_error_rsp_success() {
         let mut payload = BytesMut::new();
         payload.put_u8(0x01); // ChecksumError
-        let command = Command {
-            cmd_type: b(&[0x00]), // Dummy command type, doesn't matter for error
-            response_status: Some(BlnResponseStatus::Error.into()),
-            payload: Some(payload),
-        };
+        let command = Command::new(0x00)
+            .with_status(BlnResponseStatus::Error.into())
+            .with_payload(payload);
         let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
         assert_eq!(
             result,
@@ -142,46 +30,70 @@ mod tests {
     fn test_parse_error_rsp_invalid_payload_len() {
         let mut payload = BytesMut::new();
         payload.put_slice(&[0x01, 0x02]); // Too long
-        let command = Command {
-            cmd_type: b(&[0x00]),
-            response_status: Some(BlnResponseStatus::Error.into()),
-            payload: Some(payload),
-        };
+        let command = Command::new(0x00)
+            .with_status(BlnResponseStatus::Error.into())
+            .with_payload(payload);
         let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
-        assert_eq!(result, Err(ProtocolError::InvalidPayload));
+        assert_eq!(
+            result,
+            Err(ProtocolError::PayloadLength {
+                cmd_id: 0x00,
+                expected: 1,
+                actual: 2
+            })
+        );
     }
 
     #[test]
     fn test_parse_error_rsp_no_payload() {
-        let command = Command {
-            cmd_type: b(&[0x00]),
-            response_status: Some(BlnResponseStatus::Error.into()),
-            payload: None,
-        };
+        let command = Command::new(0x00).with_status(BlnResponseStatus::Error.into());
         let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
-        assert_eq!(result, Err(ProtocolError::InvalidPayload));
+        assert_eq!(
+            result,
+            Err(ProtocolError::PayloadLength {
+                cmd_id: 0x00,
+                expected: 1,
+                actual: 0
+            })
+        );
     }
 
     #[test]
     fn test_parse_set_position_rsp_ok() {
-        let command = Command {
-            cmd_type: b(&[0x91]),
-            response_status: Some(BlnResponseStatus::Ok.into()),
-            payload: None,
-        };
+        let command = Command::new(0x91).with_status(BlnResponseStatus::Ok.into());
         let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
         assert_eq!(result, Ok(BlnProtocolType::SetPositionRsp));
     }
 
     #[test]
-    fn test_parse_set_position_rsp_ok_with_data_invalid_status() {
-        let command = Command {
-            cmd_type: b(&[0x91]),
-            response_status: Some(BlnResponseStatus::Ok.into()), // Should be OkWithData
-            payload: Some(b(&[0; 8])),
-        };
+    fn test_parse_set_position_rsp_invalid_status() {
+        let command = Command::new(0x91)
+            .with_status(BlnResponseStatus::Reserved.into())
+            .with_payload(vec![0; 8]);
         let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
-        assert_eq!(result, Err(ProtocolError::InvalidPayload)); // Status mismatch
+        assert_eq!(
+            result,
+            Err(ProtocolError::UnexpectedStatus {
+                cmd_id: 0x91,
+                status: BlnResponseStatus::Reserved.into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_set_position_rsp_ok_invalid_payload_len() {
+        let command = Command::new(0x91)
+            .with_status(BlnResponseStatus::Ok.into())
+            .with_payload(vec![0; 8]); // Should be OkWithData
+        let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
+        assert_eq!(
+            result,
+            Err(ProtocolError::PayloadLength {
+                cmd_id: 0x91,
+                expected: 0,
+                actual: 8
+            })
+        );
     }
 
     #[test]
@@ -189,24 +101,27 @@ mod tests {
         let mut payload = BytesMut::new();
         payload.put_f32_le(10.5);
         payload.put_f32_le(20.5);
-        let command = Command {
-            cmd_type: b(&[0x91]),
-            response_status: Some(BlnResponseStatus::OkWithData.into()),
-            payload: Some(payload),
-        };
+        let command = Command::new(0x91)
+            .with_status(BlnResponseStatus::OkWithData.into())
+            .with_payload(payload);
         let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
         assert_eq!(result, Ok(BlnProtocolType::PositionReached(10.5, 20.5)));
     }
 
     #[test]
     fn test_parse_set_position_rsp_ok_with_data_invalid_payload_len() {
-        let command = Command {
-            cmd_type: b(&[0x91]),
-            response_status: Some(BlnResponseStatus::OkWithData.into()),
-            payload: Some(b(&[0; 7])), // Wrong length
-        };
+        let command = Command::new(0x91)
+            .with_status(BlnResponseStatus::OkWithData.into())
+            .with_payload(vec![0; 7]); // Wrong length
         let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
-        assert_eq!(result, Err(ProtocolError::InvalidPayload));
+        assert_eq!(
+            result,
+            Err(ProtocolError::PayloadLength {
+                cmd_id: 0x91,
+                expected: 8,
+                actual: 7
+            })
+        );
     }
 
     #[test]
@@ -215,24 +130,33 @@ mod tests {
         payload.put_f32_le(10.0);
         payload.put_f32_le(20.0);
         payload.put_u8(5); // Dummy u8
-        let command = Command {
-            cmd_type: b(&[0x93]),
-            response_status: Some(BlnResponseStatus::OkWithData.into()),
-            payload: Some(payload),
-        };
+        let command = Command::new(0x93)
+            .with_status(BlnResponseStatus::OkWithData.into())
+            .with_payload(payload);
         let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
-        assert_eq!(result, Ok(BlnProtocolType::GetPositionRsp(10.0, 20.0, 5)));
+        assert_eq!(
+            result,
+            Ok(BlnProtocolType::GetPositionRsp(
+                10.0,
+                20.0,
+                BlnDeviceStatus::from(5)
+            ))
+        );
     }
 
     #[test]
     fn test_parse_get_position_rsp_invalid_status() {
-        let command = Command {
-            cmd_type: b(&[0x93]),
-            response_status: Some(BlnResponseStatus::Ok.into()), // Should be OkWithData
-            payload: Some(b(&[0; 9])),
-        };
+        let command = Command::new(0x93)
+            .with_status(BlnResponseStatus::Ok.into())
+            .with_payload(vec![0; 9]); // Should be OkWithData
         let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
-        assert_eq!(result, Err(ProtocolError::InvalidPayload)); // Status mismatch
+        assert_eq!(
+            result,
+            Err(ProtocolError::UnexpectedStatus {
+                cmd_id: 0x93,
+                status: BlnResponseStatus::Ok.into()
+            })
+        );
     }
 
     #[test]
@@ -241,35 +165,79 @@ mod tests {
         payload.put_f32_le(10.0);
         payload.put_f32_le(20.0);
         // Missing last u8
-        let command = Command {
-            cmd_type: b(&[0x93]),
-            response_status: Some(BlnResponseStatus::OkWithData.into()),
-            payload: Some(payload),
-        };
+        let command = Command::new(0x93)
+            .with_status(BlnResponseStatus::OkWithData.into())
+            .with_payload(payload);
         let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
-        assert_eq!(result, Err(ProtocolError::InvalidPayload));
+        assert_eq!(
+            result,
+            Err(ProtocolError::PayloadLength {
+                cmd_id: 0x93,
+                expected: 9,
+                actual: 8
+            })
+        );
     }
 
     #[test]
     fn test_parse_invalid_cmd_type() {
-        let command = Command {
-            cmd_type: b(&[0xAA]), // Unknown command
-            response_status: Some(BlnResponseStatus::Ok.into()),
-            payload: None,
-        };
+        let command = Command::new(0xAA).with_status(BlnResponseStatus::Ok.into());
         let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
-        assert_eq!(result, Err(ProtocolError::InvalidCommandType));
+        assert_eq!(
+            result,
+            Err(ProtocolError::InvalidCommandType { cmd_id: 0xAA })
+        );
     }
 
     #[test]
-    fn test_parse_empty_cmd_type() {
-        let command = Command {
-            cmd_type: BytesMut::new(), // Empty command type
-            response_status: Some(BlnResponseStatus::Ok.into()),
-            payload: None,
-        };
+    fn test_parse_set_position_rsq() {
+        let mut payload = BytesMut::new();
+        payload.put_f32_le(1.0);
+        payload.put_f32_le(2.0);
+        let command = Command::new(0x31)
+            .with_status(BlnResponseStatus::Unused.into())
+            .with_payload(payload);
+        let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
+        assert_eq!(result, Ok(BlnProtocolType::SetPositionRsq(1.0, 2.0)));
+    }
+
+    #[test]
+    fn test_parse_get_position_rsq() {
+        let command = Command::new(0x33).with_status(BlnResponseStatus::Unused.into());
+        let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
+        assert_eq!(result, Ok(BlnProtocolType::GetPositionRsq));
+    }
+
+    #[test]
+    fn test_parse_request_with_response_status() {
+        let command = Command::new(0x33).with_status(BlnResponseStatus::Ok.into()); // 请求不应带响应状态
+        let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
+        assert_eq!(
+            result,
+            Err(ProtocolError::UnexpectedStatus {
+                cmd_id: 0x33,
+                status: BlnResponseStatus::Ok.into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_missing_response_status() {
+        let command = Command::new(0x91); // No response status
         let result: Result<BlnProtocolType, ProtocolError> = command.try_into();
-        assert_eq!(result, Err(ProtocolError::InvalidCommandType));
+        assert_eq!(result, Err(ProtocolError::MissingStatus { cmd_id: 0x91 }));
+    }
+
+    #[test]
+    fn test_error_message_languages() {
+        let err = ProtocolError::PayloadLength {
+            cmd_id: 0x93,
+            expected: 9,
+            actual: 8,
+        };
+        assert!(err.message(Language::English).contains("0x93"));
+        assert!(err.message(Language::English).contains("expected 9"));
+        assert_eq!(err.message(Language::Chinese), err.to_string());
     }
 
     // --- Tests for TryFrom<BlnProtocolType> for Command ---
@@ -281,13 +249,16 @@ mod tests {
         assert!(result.is_ok());
         let command = result.unwrap();
 
-        assert_eq!(command.cmd_type.as_ref(), &[0x31]);
-        assert_eq!(command.response_status, None);
+        assert_eq!(command.cmd_id(), 0x31);
+        assert_eq!(
+            command.response_status(),
+            Some(BlnResponseStatus::Unused.into())
+        );
 
         let mut expected_payload = BytesMut::new();
         expected_payload.put_f32_le(10.0);
         expected_payload.put_f32_le(20.0);
-        assert_eq!(command.payload.unwrap().as_ref(), expected_payload.as_ref());
+        assert_eq!(command.payload(), expected_payload.as_ref());
     }
 
     #[test]
@@ -297,22 +268,200 @@ mod tests {
         assert!(result.is_ok());
         let command = result.unwrap();
 
-        assert_eq!(command.cmd_type.as_ref(), &[0x33]);
-        assert_eq!(command.response_status, None);
-        assert_eq!(command.payload, None);
+        assert_eq!(command.cmd_id(), 0x33);
+        assert_eq!(
+            command.response_status(),
+            Some(BlnResponseStatus::Unused.into())
+        );
+        assert!(command.payload().is_empty());
     }
 
     #[test]
-    fn test_create_unsupported_bln_type() {
-        let bln_cmd = BlnProtocolType::SetPositionRsp; // Response type, cannot create
-        let result: Result<Command, ProtocolError> = bln_cmd.try_into();
-        assert_eq!(result, Err(ProtocolError::InvalidCommandType));
+    fn test_create_set_position_rsp() {
+        let command: Command = BlnProtocolType::SetPositionRsp.try_into().unwrap();
+
+        assert_eq!(command.cmd_id(), 0x91);
+        assert_eq!(
+            command.response_status(),
+            Some(BlnResponseStatus::Ok.into())
+        );
+        assert!(command.payload().is_empty());
+    }
+
+    #[test]
+    fn test_create_position_reached() {
+        let command: Command = BlnProtocolType::PositionReached(1.0, 2.0)
+            .try_into()
+            .unwrap();
+
+        assert_eq!(command.cmd_id(), 0x91);
+        assert_eq!(
+            command.response_status(),
+            Some(BlnResponseStatus::OkWithData.into())
+        );
+        assert_eq!(command.payload().len(), 8);
+    }
+
+    #[test]
+    fn test_create_get_position_rsp() {
+        let command: Command =
+            BlnProtocolType::GetPositionRsp(1.0, 2.0, BlnDeviceStatus::from(0x05))
+                .try_into()
+                .unwrap();
+
+        assert_eq!(command.cmd_id(), 0x93);
+        assert_eq!(
+            command.response_status(),
+            Some(BlnResponseStatus::OkWithData.into())
+        );
+        assert_eq!(command.payload()[8], 0x05);
     }
 
     #[test]
     fn test_create_error_rsp_bln_type() {
-        let bln_cmd = BlnProtocolType::ErrorRsp(BlnErrorCause::ChecksumError); // Response type, cannot create
-        let result: Result<Command, ProtocolError> = bln_cmd.try_into();
-        assert_eq!(result, Err(ProtocolError::InvalidCommandType));
+        let bln_cmd = BlnProtocolType::ErrorRsp(BlnErrorCause::ChecksumError);
+        let command: Command = bln_cmd.try_into().unwrap();
+
+        assert_eq!(
+            command.response_status(),
+            Some(BlnResponseStatus::Error.into())
+        );
+        assert_eq!(command.payload(), &[0x01]);
+    }
+
+    #[test]
+    fn test_bln_type_round_trip() {
+        let messages = [
+            BlnProtocolType::SetPositionRsq(1.0, -2.0),
+            BlnProtocolType::SetPositionRsp,
+            BlnProtocolType::PositionReached(3.5, 4.5),
+            BlnProtocolType::GetPositionRsq,
+            BlnProtocolType::GetPositionRsp(5.0, 6.0, BlnDeviceStatus::MOVING),
+            BlnProtocolType::ReadConfigRsq(BlnConfigId::Speed),
+            BlnProtocolType::ReadConfigRsp(BlnConfigId::SoftLimitMaxY, 250.0),
+            BlnProtocolType::WriteConfigRsq(BlnConfigId::Other(0x20), -1.0),
+            BlnProtocolType::WriteConfigRsp(BlnConfigId::HomeOffsetX),
+            BlnProtocolType::ListConfigRsq,
+            BlnProtocolType::ListConfigRsp(0b1_1110),
+            BlnProtocolType::SaveConfigRsq,
+            BlnProtocolType::SaveConfigRsp,
+            BlnProtocolType::EmergencyStopRsq,
+            BlnProtocolType::EmergencyStopRsp,
+            BlnProtocolType::StopRsq,
+            BlnProtocolType::StopRsp,
+            BlnProtocolType::Stopped(7.0, -8.0),
+            BlnProtocolType::HomeRsq,
+            BlnProtocolType::HomeRsp,
+            BlnProtocolType::HomeReached(0.5, 0.0),
+            BlnProtocolType::MoveRelativeRsq(-1.5, 2.0),
+            BlnProtocolType::MoveRelativeRsp,
+            BlnProtocolType::MoveRelativeReached(9.0, 10.0),
+            BlnProtocolType::JogStartRsq(BlnAxis::Y, -12.5),
+            BlnProtocolType::JogStartRsp,
+            BlnProtocolType::JogStopRsq(BlnAxis::Other(0x02)),
+            BlnProtocolType::JogStopRsp,
+            BlnProtocolType::JogStopped(11.0, 12.0),
+            BlnProtocolType::SetVelocityRsq(25.0),
+            BlnProtocolType::SetVelocityRsp,
+            BlnProtocolType::ErrorRsp(BlnErrorCause::StateMismatch),
+        ];
+        for message in messages {
+            let command: Command = message.try_into().unwrap();
+            assert_eq!(BlnProtocolType::try_from(command), Ok(message));
+        }
+    }
+
+    #[test]
+    fn test_config_commands() {
+        let command =
+            Command::try_from(BlnProtocolType::WriteConfigRsq(BlnConfigId::Speed, 1.0)).unwrap();
+        assert_eq!(command.cmd_id(), 0x37);
+        assert_eq!(command.payload(), &[0x01, 0x00, 0x00, 0x80, 0x3F]);
+
+        let command = Command::new(0x95)
+            .with_status(BlnResponseStatus::OkWithData.into())
+            .with_payload(vec![0x01]);
+        assert_eq!(
+            BlnProtocolType::try_from(command),
+            Err(ProtocolError::PayloadLength {
+                cmd_id: 0x95,
+                expected: 5,
+                actual: 1
+            })
+        );
+
+        let command = Command::new(0x35)
+            .with_status(BlnResponseStatus::Error.into())
+            .with_payload(vec![BlnErrorCause::ConfigNotFound.into()]);
+        assert_eq!(
+            BlnProtocolType::try_from(command),
+            Ok(BlnProtocolType::ErrorRsp(BlnErrorCause::ConfigNotFound))
+        );
+    }
+
+    #[test]
+    fn test_motion_commands() {
+        let command = Command::try_from(BlnProtocolType::JogStartRsq(BlnAxis::Y, -1.0)).unwrap();
+        assert_eq!(command.cmd_id(), 0x45);
+        assert_eq!(
+            command.response_status(),
+            Some(BlnResponseStatus::Unused.into())
+        );
+        assert_eq!(command.payload(), &[0x01, 0x00, 0x00, 0x80, 0xBF]);
+
+        // 确认和完成共用命令字, 通过响应状态区分
+        let ack = Command::new(0xA1).with_status(BlnResponseStatus::Ok.into());
+        assert_eq!(BlnProtocolType::try_from(ack), Ok(BlnProtocolType::HomeRsp));
+        let done = Command::try_from(BlnProtocolType::HomeReached(1.0, 2.0)).unwrap();
+        assert_eq!(done.cmd_id(), 0xA1);
+        assert_eq!(
+            done.response_status(),
+            Some(BlnResponseStatus::OkWithData.into())
+        );
+
+        let command = Command::new(0x9D)
+            .with_status(BlnResponseStatus::OkWithData.into())
+            .with_payload(vec![0; 8]);
+        assert_eq!(
+            BlnProtocolType::try_from(command),
+            Err(ProtocolError::UnexpectedStatus {
+                cmd_id: 0x9D,
+                status: BlnResponseStatus::OkWithData.into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_derive_field_endianness_without_status() {
+        use protocol::message::ProtocolMessage;
+
+        #[derive(Debug, PartialEq, ProtocolMessage)]
+        enum Message {
+            #[cmd(0x10)]
+            Speed {
+                #[field(be)]
+                rpm: u16,
+                scale: i16,
+            },
+        }
+
+        let command = Command::try_from(Message::Speed {
+            rpm: 0x0102,
+            scale: -2,
+        })
+        .unwrap();
+        assert_eq!(command.response_status(), None);
+        assert_eq!(command.payload(), &[0x01, 0x02, 0xFE, 0xFF]);
+        assert_eq!(
+            Message::try_from(command),
+            Ok(Message::Speed {
+                rpm: 0x0102,
+                scale: -2
+            })
+        );
+        assert_eq!(
+            Message::decode(0x11, None, &[]),
+            Err(ProtocolError::InvalidCommandType { cmd_id: 0x11 })
+        );
     }
 }
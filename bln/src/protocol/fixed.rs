@@ -0,0 +1,302 @@
+use heapless::Vec;
+use protocol::{message::ProtocolMessage, types::ProtocolError, utils::calculate_bcc};
+
+/// 帧头同步字.
+const FRAME_HEAD: [u8; 2] = [0x55, 0xAA];
+/// 帧头同步字的长度.
+const FRAME_HEAD_LEN: usize = 2;
+/// 协议帧的固定部分长度 (含帧头, 不含可变长的数据体和 BCC).
+const FRAME_FIXED_LEN: usize = 9;
+/// 协议帧的块校验码 (BCC) 长度.
+const FRAME_BCC_LEN: usize = 1;
+/// 协议帧中的保留字段长度.
+const RESERVED_LEN: usize = 4;
+/// 长度字段在帧中的起始位置.
+const DATA_LEN_FRAME_START: usize = 7;
+/// 长度字段的低 13 位为数据长度.
+const DATA_LENGTH_MASK: u16 = 0x1FFF;
+/// 长度字段的高 3 位为响应状态.
+const FLAGS_MAX: u8 = 0x07;
+const FLAGS_SHIFT: u16 = 13;
+
+/// 一个使用固定容量缓冲区的 BLN 帧, 负载最多 `N` 字节.
+///
+/// 它与 `Command` 承载相同的内容, 但不需要堆分配器, 供嵌入式固件使用.
+/// 请求帧的响应状态为 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedFrame<const N: usize> {
+    /// 命令类型/ID.
+    pub cmd_id: u8,
+    /// 响应状态, 位于长度字段的高 3 位.
+    pub response_status: u8,
+    /// 帧中的保留字段.
+    pub reserved: [u8; RESERVED_LEN],
+    /// 负载数据.
+    pub payload: Vec<u8, N>,
+}
+
+impl<const N: usize> FixedFrame<N> {
+    /// 创建一个没有负载的请求帧.
+    pub fn new(cmd_id: u8) -> Self {
+        Self {
+            cmd_id,
+            response_status: 0,
+            reserved: [0; RESERVED_LEN],
+            payload: Vec::new(),
+        }
+    }
+
+    /// 由类型化消息创建帧, 负载超过 `N` 字节时返回 `PayloadTooLarge`.
+    pub fn from_message<M: ProtocolMessage>(message: &M) -> Result<Self, ProtocolError> {
+        let mut frame = Self::new(message.cmd_id());
+        frame.response_status = message.response_status().unwrap_or(0);
+        let len = message.payload_len();
+        frame
+            .payload
+            .resize(len, 0)
+            .map_err(|_| ProtocolError::PayloadTooLarge { len, max: N })?;
+        message.encode_payload(&mut frame.payload)?;
+        Ok(frame)
+    }
+
+    /// 将帧解析为类型化消息.
+    pub fn to_message<M: ProtocolMessage>(&self) -> Result<M, ProtocolError> {
+        M::decode(self.cmd_id, Some(self.response_status), &self.payload)
+    }
+
+    /// 编码后的帧长度.
+    pub fn encoded_len(&self) -> usize {
+        FRAME_FIXED_LEN + self.payload.len() + FRAME_BCC_LEN
+    }
+
+    /// 将帧编码到 `out` 中, 返回写入的字节数.
+    ///
+    /// 与 `BlnCommandEncoder` 生成的字节完全相同. `out` 不够长时返回 `FrameLength`.
+    pub fn encode(&self, out: &mut [u8]) -> Result<usize, ProtocolError> {
+        let data_len = self.payload.len();
+        if data_len > usize::from(DATA_LENGTH_MASK) {
+            return Err(ProtocolError::PayloadTooLarge {
+                len: data_len,
+                max: usize::from(DATA_LENGTH_MASK),
+            });
+        }
+        if self.response_status > FLAGS_MAX {
+            return Err(ProtocolError::UnexpectedStatus {
+                cmd_id: self.cmd_id,
+                status: self.response_status,
+            });
+        }
+        let total_len = self.encoded_len();
+        let Some(out) = out.get_mut(..total_len) else {
+            return Err(ProtocolError::FrameLength {
+                expected: total_len,
+                actual: out.len(),
+            });
+        };
+
+        let len_field = data_len as u16 | u16::from(self.response_status) << FLAGS_SHIFT;
+        out[..FRAME_HEAD_LEN].copy_from_slice(&FRAME_HEAD);
+        out[FRAME_HEAD_LEN] = self.cmd_id;
+        out[FRAME_HEAD_LEN + 1..DATA_LEN_FRAME_START].copy_from_slice(&self.reserved);
+        out[DATA_LEN_FRAME_START..FRAME_FIXED_LEN].copy_from_slice(&len_field.to_be_bytes());
+        out[FRAME_FIXED_LEN..total_len - FRAME_BCC_LEN].copy_from_slice(&self.payload);
+        out[total_len - FRAME_BCC_LEN] =
+            calculate_bcc(&out[FRAME_HEAD_LEN..total_len - FRAME_BCC_LEN]);
+        Ok(total_len)
+    }
+}
+
+/// `FixedDecoder` 在容量为 `N` 字节的固定缓冲区中解析 BLN 帧, 不需要堆分配器.
+///
+/// 接收到的字节通过 [`FixedDecoder::push`] 写入缓冲区, 再通过 [`FixedDecoder::next_frame`] 逐个取出帧.
+/// 与 `BlnCommandDecode` 一样, 它会丢弃帧头之前的噪声, 并在校验失败时跳过帧头重新同步;
+/// 但被丢弃的帧以错误的形式返回, 而不是记录为诊断事件.
+#[derive(Debug, Default)]
+pub struct FixedDecoder<const N: usize> {
+    /// 尚未解析的字节.
+    buf: Vec<u8, N>,
+}
+
+impl<const N: usize> FixedDecoder<N> {
+    /// 创建一个空的解码器.
+    pub fn new() -> Self {
+        Self { buf: Vec::new() }
+    }
+
+    /// 将接收到的字节写入缓冲区, 返回实际写入的字节数.
+    ///
+    /// 缓冲区已满时多余的字节不会被写入, 调用方应先取出帧再写入剩余的字节.
+    pub fn push(&mut self, data: &[u8]) -> usize {
+        let accepted = data.len().min(N - self.buf.len());
+        // 长度已经按剩余容量截断, 不会失败
+        let _ = self.buf.extend_from_slice(&data[..accepted]);
+        accepted
+    }
+
+    /// 缓冲区中尚未解析的字节数.
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// 缓冲区是否为空.
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// 丢弃缓冲区开头的 `count` 个字节.
+    fn discard(&mut self, count: usize) {
+        let len = self.buf.len();
+        self.buf.copy_within(count..len, 0);
+        self.buf.truncate(len - count);
+    }
+
+    /// 从缓冲区中取出下一个帧.
+    ///
+    /// 数据不足一个完整帧时返回 `None`. 帧校验失败或帧长超过缓冲区容量时,
+    /// 跳过该帧头并返回对应的错误, 调用方可以继续调用以解析后续的帧.
+    pub fn next_frame(&mut self) -> Option<Result<FixedFrame<N>, ProtocolError>> {
+        // 丢弃帧头之前的数据, 保留可能是帧头首字节的最后一个字节
+        match self
+            .buf
+            .windows(FRAME_HEAD_LEN)
+            .position(|w| w == FRAME_HEAD)
+        {
+            Some(index) => self.discard(index),
+            None => {
+                let keep = usize::from(self.buf.last() == Some(&FRAME_HEAD[0]));
+                self.discard(self.buf.len() - keep);
+                return None;
+            }
+        }
+        if self.buf.len() < FRAME_FIXED_LEN {
+            return None;
+        }
+
+        let len_field = u16::from_be_bytes([
+            self.buf[DATA_LEN_FRAME_START],
+            self.buf[DATA_LEN_FRAME_START + 1],
+        ]);
+        let data_len = usize::from(len_field & DATA_LENGTH_MASK);
+        let frame_len = FRAME_FIXED_LEN + data_len + FRAME_BCC_LEN;
+        if frame_len > N {
+            self.discard(FRAME_HEAD_LEN);
+            return Some(Err(ProtocolError::PayloadTooLarge {
+                len: data_len,
+                max: N.saturating_sub(FRAME_FIXED_LEN + FRAME_BCC_LEN),
+            }));
+        }
+        if self.buf.len() < frame_len {
+            return None;
+        }
+
+        let bcc_index = frame_len - FRAME_BCC_LEN;
+        let bcc = calculate_bcc(&self.buf[FRAME_HEAD_LEN..bcc_index]);
+        if bcc != self.buf[bcc_index] {
+            let actual = self.buf[bcc_index];
+            self.discard(FRAME_HEAD_LEN);
+            return Some(Err(ProtocolError::ChecksumMismatch {
+                expected: bcc,
+                actual,
+            }));
+        }
+
+        let mut reserved = [0; RESERVED_LEN];
+        reserved.copy_from_slice(&self.buf[FRAME_HEAD_LEN + 1..DATA_LEN_FRAME_START]);
+        let frame = FixedFrame {
+            cmd_id: self.buf[FRAME_HEAD_LEN],
+            response_status: (len_field >> FLAGS_SHIFT) as u8,
+            reserved,
+            // 负载长度不超过缓冲区容量, 不会失败
+            payload: Vec::from_slice(&self.buf[FRAME_FIXED_LEN..bcc_index]).ok()?,
+        };
+        self.discard(frame_len);
+        Some(Ok(frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::types::{BlnDeviceStatus, BlnProtocolType};
+    // 与基于 `Command` 的编码器对照的测试需要 `alloc`.
+    #[cfg(feature = "alloc")]
+    use crate::protocol::types::BlnCommandEncoder;
+    #[cfg(feature = "alloc")]
+    use alloc::vec;
+    #[cfg(feature = "alloc")]
+    use protocol::{traits::FrameGenerator, types::Command};
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_fixed_codec_matches_bln_encoder() {
+        let mut frame = FixedFrame::<64>::new(0x93);
+        frame.response_status = 0x02;
+        frame.payload.extend_from_slice(&[1, 2, 3]).unwrap();
+        let mut out = [0; 64];
+        let len = frame.encode(&mut out).unwrap();
+
+        let expected = BlnCommandEncoder
+            .create_frame(
+                Command::new(0x93)
+                    .with_status(0x02)
+                    .with_payload(vec![1, 2, 3]),
+            )
+            .unwrap();
+        assert_eq!(&out[..len], expected.as_ref());
+
+        let mut decoder = FixedDecoder::<64>::new();
+        assert_eq!(decoder.push(&[0x00, 0x55]), 2);
+        assert_eq!(decoder.next_frame(), None);
+        assert_eq!(decoder.push(&out[1..len]), len - 1);
+        assert_eq!(decoder.next_frame(), Some(Ok(frame)));
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn test_fixed_frame_message_conversion() {
+        let message = BlnProtocolType::GetPositionRsp(1.5, -2.0, BlnDeviceStatus::MOVING);
+        let frame = FixedFrame::<16>::from_message(&message).unwrap();
+        #[cfg(feature = "alloc")]
+        assert_eq!(
+            frame.encoded_len(),
+            BlnCommandEncoder
+                .create_frame(Command::try_from(message).unwrap())
+                .unwrap()
+                .len()
+        );
+        assert_eq!(frame.to_message::<BlnProtocolType>(), Ok(message));
+        assert_eq!(
+            FixedFrame::<4>::from_message(&message),
+            Err(ProtocolError::PayloadTooLarge { len: 9, max: 4 })
+        );
+    }
+
+    #[test]
+    fn test_fixed_decoder_reports_bad_frames() {
+        let mut out = [0; 16];
+        let len = FixedFrame::<16>::new(0x33).encode(&mut out).unwrap();
+        out[len - 1] ^= 0xFF;
+
+        let mut decoder = FixedDecoder::<16>::new();
+        decoder.push(&out[..len]);
+        assert!(matches!(
+            decoder.next_frame(),
+            Some(Err(ProtocolError::ChecksumMismatch { .. }))
+        ));
+        assert_eq!(decoder.next_frame(), None);
+
+        // 声明的长度超过了缓冲区容量
+        decoder.push(&[0x55, 0xAA, 0x31, 0, 0, 0, 0, 0x00, 0x20]);
+        assert_eq!(
+            decoder.next_frame(),
+            Some(Err(ProtocolError::PayloadTooLarge { len: 32, max: 6 }))
+        );
+        assert_eq!(
+            FixedFrame::<16>::new(0x33).encode(&mut [0; 4]),
+            Err(ProtocolError::FrameLength {
+                expected: 10,
+                actual: 4
+            })
+        );
+    }
+}
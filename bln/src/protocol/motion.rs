@@ -0,0 +1,493 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+use tracing::{debug, info};
+
+use crate::protocol::types::{BlnDeviceStatus, BlnErrorCause, BlnProtocolType};
+
+/// 一次移动 (`SetPositionRsq`) 在其生命周期中所处的状态.
+///
+/// 一次移动的完整流程为 `Sent` → `Acknowledged` → `Reached`,
+/// 任意一个等待阶段都可能因错误响应进入 `Failed`, 因停止或急停进入 `Stopped`, 或因超时进入 `TimedOut`.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum MoveState {
+    /// 当前没有任何移动在进行.
+    #[default]
+    Idle,
+    /// 请求已发出, 等待设备的通信确认 (`SetPositionRsp`).
+    Sent { target: (f32, f32) },
+    /// 设备已确认收到请求, 等待执行完成 (`PositionReached`).
+    Acknowledged { target: (f32, f32) },
+    /// 设备报告已到达位置, `position` 为设备回报的实际位置.
+    Reached {
+        target: (f32, f32),
+        position: (f32, f32),
+    },
+    /// 设备返回了错误响应.
+    Failed {
+        target: (f32, f32),
+        cause: BlnErrorCause,
+    },
+    /// 移动被减速停止或急停中断.
+    Stopped { target: (f32, f32) },
+    /// 在规定时间内没有收到确认或完成响应.
+    TimedOut { target: (f32, f32) },
+}
+
+impl MoveState {
+    /// 是否有移动正在进行 (已发出但尚未结束).
+    pub fn is_in_flight(&self) -> bool {
+        matches!(self, Self::Sent { .. } | Self::Acknowledged { .. })
+    }
+}
+
+/// 当已有移动在进行时, 对新的移动请求采取的策略.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum MovePolicy {
+    /// 直接拒绝新的移动请求.
+    #[default]
+    Reject,
+    /// 将新的移动请求排队, 在当前移动到达后依次发出. 参数为队列的最大长度.
+    Queue(usize),
+}
+
+/// 提交移动请求时可能发生的错误.
+#[derive(Error, Debug, PartialEq, Clone, Copy)]
+pub enum MoveError {
+    #[error("已有移动正在进行, 请求被拒绝")]
+    Busy,
+    #[error("移动队列已满")]
+    QueueFull,
+}
+
+/// `MoveTracker` 跟踪 BLN 两阶段移动事务的状态机.
+///
+/// `SetPositionRsq` 发出后, 设备会先回复 `SetPositionRsp` 作为通信确认,
+/// 到达目标后再回复 `PositionReached`. 该结构体记录当前移动所处的阶段,
+/// 并根据 [`MovePolicy`] 决定在移动进行中时如何处理新的请求.
+///
+/// 所有与时间相关的方法都接收一个 `now` 参数, 以便调用方控制时钟.
+#[derive(Debug)]
+pub struct MoveTracker {
+    /// 当前移动的状态.
+    state: MoveState,
+    /// 移动进行中时对新请求的处理策略.
+    policy: MovePolicy,
+    /// 等待通信确认的超时时间.
+    ack_timeout: Duration,
+    /// 等待到达响应的超时时间.
+    reach_timeout: Duration,
+    /// 当前阶段的截止时间, 仅在移动进行中时有效.
+    deadline: Option<Instant>,
+    /// 排队等待发出的移动目标.
+    queue: VecDeque<(f32, f32)>,
+    /// 最近一次 `GetPositionRsp` 回报的设备状态.
+    device_status: Option<BlnDeviceStatus>,
+}
+
+impl Default for MoveTracker {
+    /// 创建一个使用默认策略 (`Reject`) 和默认超时时间的 `MoveTracker`.
+    fn default() -> Self {
+        Self {
+            state: MoveState::Idle,
+            policy: MovePolicy::default(),
+            ack_timeout: Self::ACK_TIMEOUT,
+            reach_timeout: Self::REACH_TIMEOUT,
+            deadline: None,
+            queue: VecDeque::new(),
+            device_status: None,
+        }
+    }
+}
+
+impl MoveTracker {
+    /// 等待通信确认的默认超时时间.
+    const ACK_TIMEOUT: Duration = Duration::from_secs(1);
+    /// 等待到达响应的默认超时时间.
+    const REACH_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// 设置移动进行中时对新请求的处理策略.
+    pub fn policy(mut self, policy: MovePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// 设置等待通信确认的超时时间.
+    pub fn ack_timeout(mut self, ack_timeout: Duration) -> Self {
+        self.ack_timeout = ack_timeout;
+        self
+    }
+
+    /// 设置等待到达响应的超时时间.
+    pub fn reach_timeout(mut self, reach_timeout: Duration) -> Self {
+        self.reach_timeout = reach_timeout;
+        self
+    }
+
+    /// 返回当前移动的状态.
+    pub fn state(&self) -> MoveState {
+        self.state
+    }
+
+    /// 返回当前等待阶段的截止时间, 没有移动进行时返回 `None`.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// 返回最近一次 `GetPositionRsp` 回报的设备状态, 尚未收到时返回 `None`.
+    pub fn device_status(&self) -> Option<BlnDeviceStatus> {
+        self.device_status
+    }
+
+    /// 返回当前排队等待发出的移动数量.
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// 提交一个新的移动请求.
+    ///
+    /// # 返回
+    /// * `Ok(Some(request))`: 可以立即发出, `request` 即为需要发送的 `SetPositionRsq`.
+    /// * `Ok(None)`: 已有移动进行中, 请求已按 `MovePolicy::Queue` 排队.
+    /// * `Err(MoveError)`: 请求被策略拒绝.
+    pub fn request_move(
+        &mut self,
+        x: f32,
+        y: f32,
+        now: Instant,
+    ) -> Result<Option<BlnProtocolType>, MoveError> {
+        if !self.state.is_in_flight() {
+            return Ok(Some(self.start(x, y, now)));
+        }
+        match self.policy {
+            MovePolicy::Reject => Err(MoveError::Busy),
+            MovePolicy::Queue(capacity) if self.queue.len() >= capacity => {
+                Err(MoveError::QueueFull)
+            }
+            MovePolicy::Queue(_) => {
+                self.queue.push_back((x, y));
+                Ok(None)
+            }
+        }
+    }
+
+    /// 根据设备的响应推进状态机.
+    ///
+    /// 由于 `ErrorRsp` 不携带命令字, 移动进行中收到的任何错误响应都被视为该移动失败.
+    /// 收到 `EmergencyStopRsp` 或 `Stopped` 时移动被视为中断.
+    /// `GetPositionRsp` 中的设备状态会被记录; 移动进行中设备报告故障时移动失败,
+    /// 报告急停锁定时移动被视为中断.
+    /// 失败或中断时会清空排队的移动, 避免在异常状态下继续运动.
+    ///
+    /// # 返回
+    /// 如果当前移动已到达且队列中还有移动, 返回下一个需要发送的 `SetPositionRsq`.
+    pub fn handle_response(
+        &mut self,
+        response: &BlnProtocolType,
+        now: Instant,
+    ) -> Option<BlnProtocolType> {
+        if let BlnProtocolType::GetPositionRsp(_, _, status) = response {
+            if self.device_status != Some(*status) {
+                debug!(%status, "设备状态变化");
+            }
+            self.device_status = Some(*status);
+        }
+        match (self.state, response) {
+            (MoveState::Sent { target }, BlnProtocolType::SetPositionRsp) => {
+                self.state = MoveState::Acknowledged { target };
+                self.deadline = Some(now + self.reach_timeout);
+                None
+            }
+            (
+                MoveState::Sent { target } | MoveState::Acknowledged { target },
+                BlnProtocolType::PositionReached(x, y),
+            ) => {
+                self.state = MoveState::Reached {
+                    target,
+                    position: (*x, *y),
+                };
+                self.deadline = None;
+                self.queue.pop_front().map(|(x, y)| self.start(x, y, now))
+            }
+            (
+                MoveState::Sent { target } | MoveState::Acknowledged { target },
+                BlnProtocolType::ErrorRsp(cause),
+            ) => {
+                info!(?target, ?cause, "移动失败");
+                self.state = MoveState::Failed {
+                    target,
+                    cause: *cause,
+                };
+                self.finish();
+                None
+            }
+            (
+                MoveState::Sent { target } | MoveState::Acknowledged { target },
+                BlnProtocolType::EmergencyStopRsp | BlnProtocolType::Stopped(..),
+            ) => {
+                info!(?target, ?response, "移动被停止");
+                self.state = MoveState::Stopped { target };
+                self.finish();
+                None
+            }
+            (
+                MoveState::Sent { target } | MoveState::Acknowledged { target },
+                BlnProtocolType::GetPositionRsp(_, _, status),
+            ) if status.contains(BlnDeviceStatus::FAULT) => {
+                info!(?target, %status, "设备报告故障, 移动失败");
+                self.state = MoveState::Failed {
+                    target,
+                    cause: BlnErrorCause::OperationFailed,
+                };
+                self.finish();
+                None
+            }
+            (
+                MoveState::Sent { target } | MoveState::Acknowledged { target },
+                BlnProtocolType::GetPositionRsp(_, _, status),
+            ) if status.contains(BlnDeviceStatus::EMERGENCY_STOP) => {
+                info!(?target, %status, "设备处于急停, 移动被中断");
+                self.state = MoveState::Stopped { target };
+                self.finish();
+                None
+            }
+            (state, response) => {
+                debug!(?state, ?response, "响应与当前移动无关, 已忽略");
+                None
+            }
+        }
+    }
+
+    /// 检查当前阶段是否已超时. 超时后状态变为 `TimedOut`, 并清空排队的移动.
+    ///
+    /// # 返回
+    /// 如果本次调用触发了超时, 返回 `true`.
+    pub fn poll_timeout(&mut self, now: Instant) -> bool {
+        match (self.state, self.deadline) {
+            (MoveState::Sent { target } | MoveState::Acknowledged { target }, Some(deadline))
+                if now >= deadline =>
+            {
+                info!(?target, "移动超时");
+                self.state = MoveState::TimedOut { target };
+                self.finish();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 放弃当前移动和所有排队的移动, 状态回到 `Idle`.
+    ///
+    /// 用于请求没能发出或连接中断等不会再收到响应的情况, 之后可以立即提交新的移动.
+    pub fn abort(&mut self) {
+        if self.state.is_in_flight() {
+            info!(state = ?self.state, "放弃移动");
+            self.state = MoveState::Idle;
+        }
+        self.finish();
+    }
+
+    /// 开始一次新的移动, 并返回需要发送的请求.
+    fn start(&mut self, x: f32, y: f32, now: Instant) -> BlnProtocolType {
+        self.state = MoveState::Sent { target: (x, y) };
+        self.deadline = Some(now + self.ack_timeout);
+        BlnProtocolType::SetPositionRsq(x, y)
+    }
+
+    /// 结束当前移动并丢弃所有排队的移动.
+    fn finish(&mut self) {
+        self.deadline = None;
+        self.queue.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_reaches_target() {
+        let now = Instant::now();
+        let mut tracker = MoveTracker::default();
+
+        let request = tracker.request_move(1.0, 2.0, now);
+        assert_eq!(request, Ok(Some(BlnProtocolType::SetPositionRsq(1.0, 2.0))));
+        assert_eq!(tracker.state(), MoveState::Sent { target: (1.0, 2.0) });
+
+        tracker.handle_response(&BlnProtocolType::SetPositionRsp, now);
+        assert_eq!(
+            tracker.state(),
+            MoveState::Acknowledged { target: (1.0, 2.0) }
+        );
+
+        tracker.handle_response(&BlnProtocolType::PositionReached(1.0, 2.1), now);
+        assert_eq!(
+            tracker.state(),
+            MoveState::Reached {
+                target: (1.0, 2.0),
+                position: (1.0, 2.1)
+            }
+        );
+        assert!(!tracker.state().is_in_flight());
+    }
+
+    #[test]
+    fn test_move_fails_on_error_rsp() {
+        let now = Instant::now();
+        let mut tracker = MoveTracker::default();
+        tracker.request_move(1.0, 2.0, now).unwrap();
+
+        tracker.handle_response(
+            &BlnProtocolType::ErrorRsp(BlnErrorCause::InvalidArgument),
+            now,
+        );
+        assert_eq!(
+            tracker.state(),
+            MoveState::Failed {
+                target: (1.0, 2.0),
+                cause: BlnErrorCause::InvalidArgument
+            }
+        );
+    }
+
+    #[test]
+    fn test_move_times_out_waiting_for_ack() {
+        let now = Instant::now();
+        let mut tracker = MoveTracker::default().ack_timeout(Duration::from_millis(10));
+        tracker.request_move(1.0, 2.0, now).unwrap();
+
+        assert!(!tracker.poll_timeout(now + Duration::from_millis(5)));
+        assert!(tracker.poll_timeout(now + Duration::from_millis(10)));
+        assert_eq!(tracker.state(), MoveState::TimedOut { target: (1.0, 2.0) });
+    }
+
+    #[test]
+    fn test_move_times_out_waiting_for_reach() {
+        let now = Instant::now();
+        let mut tracker = MoveTracker::default()
+            .ack_timeout(Duration::from_millis(10))
+            .reach_timeout(Duration::from_millis(100));
+        tracker.request_move(1.0, 2.0, now).unwrap();
+
+        let later = now + Duration::from_millis(5);
+        tracker.handle_response(&BlnProtocolType::SetPositionRsp, later);
+        // 确认后应使用到达超时时间, 而不是确认超时时间.
+        assert!(!tracker.poll_timeout(later + Duration::from_millis(50)));
+        assert!(tracker.poll_timeout(later + Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_reject_policy_while_in_flight() {
+        let now = Instant::now();
+        let mut tracker = MoveTracker::default().policy(MovePolicy::Reject);
+        tracker.request_move(1.0, 2.0, now).unwrap();
+
+        assert_eq!(tracker.request_move(3.0, 4.0, now), Err(MoveError::Busy));
+    }
+
+    #[test]
+    fn test_abort_allows_new_move() {
+        let now = Instant::now();
+        let mut tracker = MoveTracker::default().policy(MovePolicy::Queue(1));
+        tracker.request_move(1.0, 2.0, now).unwrap();
+        tracker.request_move(3.0, 4.0, now).unwrap();
+
+        tracker.abort();
+        assert_eq!(tracker.state(), MoveState::Idle);
+        assert_eq!(tracker.deadline(), None);
+        assert_eq!(tracker.queued(), 0);
+        assert_eq!(
+            tracker.request_move(5.0, 6.0, now),
+            Ok(Some(BlnProtocolType::SetPositionRsq(5.0, 6.0)))
+        );
+    }
+
+    #[test]
+    fn test_queue_policy_sends_next_after_reached() {
+        let now = Instant::now();
+        let mut tracker = MoveTracker::default().policy(MovePolicy::Queue(1));
+        tracker.request_move(1.0, 2.0, now).unwrap();
+
+        assert_eq!(tracker.request_move(3.0, 4.0, now), Ok(None));
+        assert_eq!(
+            tracker.request_move(5.0, 6.0, now),
+            Err(MoveError::QueueFull)
+        );
+        assert_eq!(tracker.queued(), 1);
+
+        tracker.handle_response(&BlnProtocolType::SetPositionRsp, now);
+        let next = tracker.handle_response(&BlnProtocolType::PositionReached(1.0, 2.0), now);
+        assert_eq!(next, Some(BlnProtocolType::SetPositionRsq(3.0, 4.0)));
+        assert_eq!(tracker.state(), MoveState::Sent { target: (3.0, 4.0) });
+    }
+
+    #[test]
+    fn test_failure_clears_queue() {
+        let now = Instant::now();
+        let mut tracker = MoveTracker::default().policy(MovePolicy::Queue(4));
+        tracker.request_move(1.0, 2.0, now).unwrap();
+        tracker.request_move(3.0, 4.0, now).unwrap();
+
+        let next = tracker.handle_response(
+            &BlnProtocolType::ErrorRsp(BlnErrorCause::StateMismatch),
+            now,
+        );
+        assert_eq!(next, None);
+        assert_eq!(tracker.queued(), 0);
+    }
+
+    #[test]
+    fn test_stop_interrupts_move() {
+        let now = Instant::now();
+        let mut tracker = MoveTracker::default().policy(MovePolicy::Queue(4));
+        tracker.request_move(1.0, 2.0, now).unwrap();
+        tracker.request_move(3.0, 4.0, now).unwrap();
+        tracker.handle_response(&BlnProtocolType::SetPositionRsp, now);
+
+        let next = tracker.handle_response(&BlnProtocolType::EmergencyStopRsp, now);
+        assert_eq!(next, None);
+        assert_eq!(tracker.state(), MoveState::Stopped { target: (1.0, 2.0) });
+        assert_eq!(tracker.queued(), 0);
+        assert_eq!(tracker.deadline(), None);
+    }
+
+    #[test]
+    fn test_device_status_updates_move() {
+        let now = Instant::now();
+        let mut tracker = MoveTracker::default();
+        assert_eq!(tracker.device_status(), None);
+
+        let moving = BlnDeviceStatus::MOVING | BlnDeviceStatus::HOMED;
+        tracker.request_move(1.0, 2.0, now).unwrap();
+        tracker.handle_response(&BlnProtocolType::SetPositionRsp, now);
+        tracker.handle_response(&BlnProtocolType::GetPositionRsp(0.5, 1.0, moving), now);
+        assert_eq!(tracker.device_status(), Some(moving));
+        assert!(tracker.state().is_in_flight());
+
+        let fault = BlnDeviceStatus::FAULT;
+        tracker.handle_response(&BlnProtocolType::GetPositionRsp(0.6, 1.2, fault), now);
+        assert_eq!(
+            tracker.state(),
+            MoveState::Failed {
+                target: (1.0, 2.0),
+                cause: BlnErrorCause::OperationFailed
+            }
+        );
+
+        tracker.request_move(3.0, 4.0, now).unwrap();
+        let stopped = BlnDeviceStatus::EMERGENCY_STOP;
+        tracker.handle_response(&BlnProtocolType::GetPositionRsp(0.6, 1.2, stopped), now);
+        assert_eq!(tracker.state(), MoveState::Stopped { target: (3.0, 4.0) });
+        assert_eq!(tracker.device_status(), Some(stopped));
+    }
+
+    #[test]
+    fn test_unsolicited_response_is_ignored() {
+        let now = Instant::now();
+        let mut tracker = MoveTracker::default();
+
+        tracker.handle_response(&BlnProtocolType::SetPositionRsp, now);
+        assert_eq!(tracker.state(), MoveState::Idle);
+    }
+}
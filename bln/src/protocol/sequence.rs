@@ -0,0 +1,358 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, PoisonError},
+    time::Instant,
+};
+
+use bytes::{Bytes, BytesMut};
+use protocol::{
+    traits::{FrameGenerator, MessageProtocol, ParseProtocol, ProtocolSplit},
+    types::{Command, Diagnostic, Diagnostics, ProtocolError},
+};
+use tracing::{debug, info};
+
+use crate::protocol::types::BlnResponseStatus;
+
+/// BLN 保留字段的长度.
+const RESERVED_LEN: usize = 4;
+
+/// 读取保留字段前 2 字节中的序列号, 未携带序列号 (为 0) 时返回 `None`.
+///
+/// 序列号与分片的消息标识共用这 2 字节, 因此两者可以叠加使用.
+pub fn sequence_of(command: &Command) -> Option<u16> {
+    match *command.reserved() {
+        [s0, s1, _, _] => Some(u16::from_be_bytes([s0, s1])).filter(|&seq| seq != 0),
+        _ => None,
+    }
+}
+
+/// 一个已发出且带有序列号的请求.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SentRequest {
+    /// 请求的序列号.
+    pub sequence: u16,
+    /// 请求的命令字.
+    pub cmd_id: u8,
+    /// 发出请求的时间.
+    pub sent_at: Instant,
+}
+
+/// `SequenceTracker` 在编码器和解码器之间共享序列号状态.
+///
+/// 编码器为每个请求分配滚动的序列号 (跳过 0) 并记录下来, 解码器据此将响应与请求对应.
+/// 它可以被克隆, 所有克隆共享同一份状态, 上层也可以用它查询响应对应的请求.
+#[derive(Debug, Clone, Default)]
+pub struct SequenceTracker {
+    inner: Arc<Mutex<TrackerState>>,
+}
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    /// 上一个分配的序列号.
+    last_sent: u16,
+    /// 最近发出的请求, 最旧的在前.
+    sent: VecDeque<SentRequest>,
+}
+
+impl SequenceTracker {
+    /// 记住的最近请求数量.
+    const MAX_SENT: usize = 256;
+
+    /// 为一个请求分配下一个序列号并记录下来.
+    pub fn next(&self, cmd_id: u8) -> u16 {
+        let mut state = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        state.last_sent = state.last_sent.wrapping_add(1).max(1);
+        let request = SentRequest {
+            sequence: state.last_sent,
+            cmd_id,
+            sent_at: Instant::now(),
+        };
+        if state.sent.len() == Self::MAX_SENT {
+            state.sent.pop_front();
+        }
+        state.sent.push_back(request);
+        request.sequence
+    }
+
+    /// 查找序列号对应的最近一个请求.
+    pub fn request(&self, sequence: u16) -> Option<SentRequest> {
+        let state = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        state
+            .sent
+            .iter()
+            .rev()
+            .find(|request| request.sequence == sequence)
+            .copied()
+    }
+
+    /// 查找响应所对应的请求, 响应未携带序列号或请求未知时返回 `None`.
+    pub fn request_for(&self, response: &Command) -> Option<SentRequest> {
+        self.request(sequence_of(response)?)
+    }
+}
+
+/// `Sequenced` 为 BLN 协议加上序列号: 发送时将序列号写入保留字段, 接收时检查响应的序列号.
+///
+/// 设备需要在响应中原样返回请求的保留字段. 解码器会丢弃重复的响应,
+/// 并对序列号跳跃和找不到对应请求的响应记录诊断事件.
+/// 与 [`Fragmented`](protocol::fragment::Fragmented) 叠加时应将 `Sequenced` 放在外层,
+/// 这样同一条消息的所有分片共用一个序列号.
+pub struct Sequenced<P> {
+    /// 底层协议.
+    protocol: P,
+    /// 编码器和解码器共享的序列号状态.
+    tracker: SequenceTracker,
+}
+
+impl<P> Sequenced<P> {
+    /// 包装底层协议.
+    pub fn new(protocol: P) -> Self {
+        Self {
+            protocol,
+            tracker: SequenceTracker::default(),
+        }
+    }
+
+    /// 共享的序列号状态, 可用于将响应与请求对应.
+    pub fn tracker(&self) -> SequenceTracker {
+        self.tracker.clone()
+    }
+}
+
+impl<P: ProtocolSplit> ProtocolSplit for Sequenced<P> {
+    type Encoder = SequenceEncoder<P::Encoder>;
+    type Decode = SequenceDecoder<P::Decode>;
+
+    fn into_split(self) -> (Self::Decode, Self::Encoder) {
+        let (decode, encoder) = self.protocol.into_split();
+        (
+            SequenceDecoder::new(decode, self.tracker.clone()),
+            SequenceEncoder::new(encoder, self.tracker),
+        )
+    }
+}
+
+impl<P: MessageProtocol> MessageProtocol for Sequenced<P> {
+    type Message = P::Message;
+}
+
+/// `SequenceEncoder` 为请求帧分配序列号并写入保留字段.
+///
+/// 已携带序列号的命令和响应帧 (状态不为 `Unused`) 保持不变.
+pub struct SequenceEncoder<E> {
+    /// 底层编码器.
+    inner: E,
+    /// 共享的序列号状态.
+    tracker: SequenceTracker,
+}
+
+impl<E> SequenceEncoder<E> {
+    /// 包装底层编码器.
+    pub fn new(inner: E, tracker: SequenceTracker) -> Self {
+        Self { inner, tracker }
+    }
+}
+
+impl<E: FrameGenerator> FrameGenerator for SequenceEncoder<E> {
+    fn create_frame(&self, command: Command) -> Result<Bytes, ProtocolError> {
+        let is_request = command
+            .response_status()
+            .is_none_or(|status| BlnResponseStatus::from(status) == BlnResponseStatus::Unused);
+        if !is_request || sequence_of(&command).is_some() {
+            return self.inner.create_frame(command);
+        }
+        let sequence = self.tracker.next(command.cmd_id());
+        let mut reserved = [0; RESERVED_LEN];
+        reserved[..2].copy_from_slice(&sequence.to_be_bytes());
+        debug!(
+            sequence,
+            cmd_id = command.cmd_id(),
+            "stamped sequence number"
+        );
+        self.inner
+            .create_frame(command.with_reserved(reserved.to_vec()))
+    }
+}
+
+/// `SequenceDecoder` 检查响应帧的序列号.
+///
+/// 重复的响应以 (序列号, 命令字, 状态) 判定, 因此同一请求的多阶段响应
+/// (如 `SetPositionRsp` 和 `PositionReached`) 不会被误判为重复.
+/// 未携带序列号的帧和请求帧原样交给上层.
+pub struct SequenceDecoder<D> {
+    /// 底层解码器.
+    inner: D,
+    /// 共享的序列号状态.
+    tracker: SequenceTracker,
+    /// 最近收到的响应的序列号, 用于检测跳跃.
+    last_received: Option<u16>,
+    /// 最近收到的响应, 用于检测重复.
+    recent: VecDeque<(u16, u8, Option<u8>)>,
+    /// 尚未被取出的诊断事件.
+    diagnostics: Diagnostics,
+}
+
+impl<D> SequenceDecoder<D> {
+    /// 用于检测重复的最近响应数量.
+    const MAX_RECENT: usize = 64;
+
+    /// 包装底层解码器.
+    pub fn new(inner: D, tracker: SequenceTracker) -> Self {
+        Self {
+            inner,
+            tracker,
+            last_received: None,
+            recent: VecDeque::new(),
+            diagnostics: Diagnostics::default(),
+        }
+    }
+
+    /// 记录一个诊断事件.
+    fn report(&mut self, diagnostic: Diagnostic) {
+        info!("{}", diagnostic);
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// 检查一个响应帧, 返回是否应交给上层.
+    fn check(&mut self, sequence: u16, command: &Command) -> bool {
+        let key = (sequence, command.cmd_id(), command.response_status());
+        if self.recent.contains(&key) {
+            self.report(Diagnostic::DuplicateFrame { sequence });
+            return false;
+        }
+        if self.recent.len() == Self::MAX_RECENT {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(key);
+
+        if let Some(last) = self.last_received {
+            // 差值在前半个序列号空间内视为更新的序列号, 否则视为乱序到达的旧响应
+            let ahead = sequence.wrapping_sub(last);
+            if ahead < u16::MAX / 2 {
+                let expected = last.wrapping_add(1).max(1);
+                if ahead > 1 && sequence != expected {
+                    self.report(Diagnostic::SequenceGap {
+                        expected,
+                        actual: sequence,
+                    });
+                }
+                self.last_received = Some(sequence);
+            }
+        } else {
+            self.last_received = Some(sequence);
+        }
+
+        if self.tracker.request(sequence).is_none() {
+            self.report(Diagnostic::UnmatchedResponse { sequence });
+        }
+        true
+    }
+}
+
+impl<D: ParseProtocol> ParseProtocol for SequenceDecoder<D> {
+    fn parse_protocol_frame(&mut self, buf: &mut BytesMut) -> Option<Vec<Command>> {
+        let commands: Vec<_> = self
+            .inner
+            .parse_protocol_frame(buf)?
+            .into_iter()
+            .filter(|command| {
+                let is_response = command.response_status().is_some_and(|status| {
+                    BlnResponseStatus::from(status) != BlnResponseStatus::Unused
+                });
+                match sequence_of(command) {
+                    Some(sequence) if is_response => self.check(sequence, command),
+                    _ => true,
+                }
+            })
+            .collect();
+        (!commands.is_empty()).then_some(commands)
+    }
+
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.inner.take_diagnostics();
+        diagnostics.extend(self.diagnostics.take());
+        diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{BlnProtocol, types::BlnProtocolType};
+
+    fn response(sequence: u16, message: BlnProtocolType) -> Command {
+        let mut reserved = [0; RESERVED_LEN];
+        reserved[..2].copy_from_slice(&sequence.to_be_bytes());
+        Command::try_from(message)
+            .unwrap()
+            .with_reserved(reserved.to_vec())
+    }
+
+    fn encode(encoder: &SequenceEncoder<impl FrameGenerator>, commands: &[Command]) -> BytesMut {
+        let mut buf = BytesMut::new();
+        for command in commands {
+            buf.extend_from_slice(&encoder.create_frame(command.clone()).unwrap());
+        }
+        buf
+    }
+
+    #[test]
+    fn test_requests_are_stamped_and_matched() {
+        let protocol = Sequenced::new(BlnProtocol::default());
+        let tracker = protocol.tracker();
+        let (_, encoder) = protocol.into_split();
+
+        let request = Command::try_from(BlnProtocolType::GetPositionRsq).unwrap();
+        let frame = encoder.create_frame(request.clone()).unwrap();
+        assert_eq!(&frame[3..7], &[0x00, 0x01, 0x00, 0x00]);
+        let frame = encoder.create_frame(request).unwrap();
+        assert_eq!(&frame[3..7], &[0x00, 0x02, 0x00, 0x00]);
+
+        let matched = tracker
+            .request_for(&response(
+                2,
+                BlnProtocolType::GetPositionRsp(0.0, 0.0, Default::default()),
+            ))
+            .unwrap();
+        assert_eq!(matched.sequence, 2);
+        assert_eq!(matched.cmd_id, 0x33);
+    }
+
+    #[test]
+    fn test_decoder_drops_duplicates_and_reports_gaps() {
+        let protocol = Sequenced::new(BlnProtocol::default());
+        let tracker = protocol.tracker();
+        let (mut decoder, encoder) = protocol.into_split();
+        for _ in 0..3 {
+            tracker.next(0x31);
+        }
+
+        let mut buf = encode(
+            &encoder,
+            &[
+                response(1, BlnProtocolType::SetPositionRsp),
+                response(1, BlnProtocolType::PositionReached(1.0, 2.0)),
+                response(1, BlnProtocolType::SetPositionRsp),
+                response(3, BlnProtocolType::SetPositionRsp),
+                response(9, BlnProtocolType::SetPositionRsp),
+            ],
+        );
+        let commands = decoder.parse_protocol_frame(&mut buf).unwrap();
+        assert_eq!(commands.len(), 4);
+        assert_eq!(
+            decoder.take_diagnostics(),
+            vec![
+                Diagnostic::DuplicateFrame { sequence: 1 },
+                Diagnostic::SequenceGap {
+                    expected: 2,
+                    actual: 3
+                },
+                Diagnostic::SequenceGap {
+                    expected: 4,
+                    actual: 9
+                },
+                Diagnostic::UnmatchedResponse { sequence: 9 },
+            ]
+        );
+    }
+}
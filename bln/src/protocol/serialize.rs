@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::types::{BlnAxis, BlnConfigId, BlnErrorCause, BlnProtocolType};
+
+/// `BlnProtocolType` 的序列化形式.
+///
+/// `BlnProtocolType` 的变体使用元组字段, 无法直接序列化为带标签的对象,
+/// 因此通过这个带命名字段的镜像类型进行转换.
+/// 新增变体时两个 `From` 的匹配都会编译失败; 字段或变体的对应关系由
+/// `test_every_message_round_trips` 遍历所有命令字检查.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub(crate) enum BlnMessage {
+    SetPositionRsq { x: f32, y: f32 },
+    SetPositionRsp,
+    PositionReached { x: f32, y: f32 },
+    GetPositionRsq,
+    GetPositionRsp { x: f32, y: f32, status: u8 },
+    ReadConfigRsq { item: BlnConfigId },
+    ReadConfigRsp { item: BlnConfigId, value: f32 },
+    WriteConfigRsq { item: BlnConfigId, value: f32 },
+    WriteConfigRsp { item: BlnConfigId },
+    ListConfigRsq,
+    ListConfigRsp { mask: u32 },
+    SaveConfigRsq,
+    SaveConfigRsp,
+    EmergencyStopRsq,
+    EmergencyStopRsp,
+    StopRsq,
+    StopRsp,
+    Stopped { x: f32, y: f32 },
+    HomeRsq,
+    HomeRsp,
+    HomeReached { x: f32, y: f32 },
+    MoveRelativeRsq { dx: f32, dy: f32 },
+    MoveRelativeRsp,
+    MoveRelativeReached { x: f32, y: f32 },
+    JogStartRsq { axis: BlnAxis, velocity: f32 },
+    JogStartRsp,
+    JogStopRsq { axis: BlnAxis },
+    JogStopRsp,
+    JogStopped { x: f32, y: f32 },
+    SetVelocityRsq { velocity: f32 },
+    SetVelocityRsp,
+    ErrorRsp { cause: BlnErrorCause },
+}
+
+impl From<BlnProtocolType> for BlnMessage {
+    fn from(value: BlnProtocolType) -> Self {
+        match value {
+            BlnProtocolType::SetPositionRsq(x, y) => Self::SetPositionRsq { x, y },
+            BlnProtocolType::SetPositionRsp => Self::SetPositionRsp,
+            BlnProtocolType::PositionReached(x, y) => Self::PositionReached { x, y },
+            BlnProtocolType::GetPositionRsq => Self::GetPositionRsq,
+            BlnProtocolType::GetPositionRsp(x, y, status) => Self::GetPositionRsp {
+                x,
+                y,
+                status: status.into(),
+            },
+            BlnProtocolType::ReadConfigRsq(item) => Self::ReadConfigRsq { item },
+            BlnProtocolType::ReadConfigRsp(item, value) => Self::ReadConfigRsp { item, value },
+            BlnProtocolType::WriteConfigRsq(item, value) => Self::WriteConfigRsq { item, value },
+            BlnProtocolType::WriteConfigRsp(item) => Self::WriteConfigRsp { item },
+            BlnProtocolType::ListConfigRsq => Self::ListConfigRsq,
+            BlnProtocolType::ListConfigRsp(mask) => Self::ListConfigRsp { mask },
+            BlnProtocolType::SaveConfigRsq => Self::SaveConfigRsq,
+            BlnProtocolType::SaveConfigRsp => Self::SaveConfigRsp,
+            BlnProtocolType::EmergencyStopRsq => Self::EmergencyStopRsq,
+            BlnProtocolType::EmergencyStopRsp => Self::EmergencyStopRsp,
+            BlnProtocolType::StopRsq => Self::StopRsq,
+            BlnProtocolType::StopRsp => Self::StopRsp,
+            BlnProtocolType::Stopped(x, y) => Self::Stopped { x, y },
+            BlnProtocolType::HomeRsq => Self::HomeRsq,
+            BlnProtocolType::HomeRsp => Self::HomeRsp,
+            BlnProtocolType::HomeReached(x, y) => Self::HomeReached { x, y },
+            BlnProtocolType::MoveRelativeRsq(dx, dy) => Self::MoveRelativeRsq { dx, dy },
+            BlnProtocolType::MoveRelativeRsp => Self::MoveRelativeRsp,
+            BlnProtocolType::MoveRelativeReached(x, y) => Self::MoveRelativeReached { x, y },
+            BlnProtocolType::JogStartRsq(axis, velocity) => Self::JogStartRsq { axis, velocity },
+            BlnProtocolType::JogStartRsp => Self::JogStartRsp,
+            BlnProtocolType::JogStopRsq(axis) => Self::JogStopRsq { axis },
+            BlnProtocolType::JogStopRsp => Self::JogStopRsp,
+            BlnProtocolType::JogStopped(x, y) => Self::JogStopped { x, y },
+            BlnProtocolType::SetVelocityRsq(velocity) => Self::SetVelocityRsq { velocity },
+            BlnProtocolType::SetVelocityRsp => Self::SetVelocityRsp,
+            BlnProtocolType::ErrorRsp(cause) => Self::ErrorRsp { cause },
+        }
+    }
+}
+
+impl From<BlnMessage> for BlnProtocolType {
+    fn from(value: BlnMessage) -> Self {
+        match value {
+            BlnMessage::SetPositionRsq { x, y } => Self::SetPositionRsq(x, y),
+            BlnMessage::SetPositionRsp => Self::SetPositionRsp,
+            BlnMessage::PositionReached { x, y } => Self::PositionReached(x, y),
+            BlnMessage::GetPositionRsq => Self::GetPositionRsq,
+            BlnMessage::GetPositionRsp { x, y, status } => {
+                Self::GetPositionRsp(x, y, status.into())
+            }
+            BlnMessage::ReadConfigRsq { item } => Self::ReadConfigRsq(item),
+            BlnMessage::ReadConfigRsp { item, value } => Self::ReadConfigRsp(item, value),
+            BlnMessage::WriteConfigRsq { item, value } => Self::WriteConfigRsq(item, value),
+            BlnMessage::WriteConfigRsp { item } => Self::WriteConfigRsp(item),
+            BlnMessage::ListConfigRsq => Self::ListConfigRsq,
+            BlnMessage::ListConfigRsp { mask } => Self::ListConfigRsp(mask),
+            BlnMessage::SaveConfigRsq => Self::SaveConfigRsq,
+            BlnMessage::SaveConfigRsp => Self::SaveConfigRsp,
+            BlnMessage::EmergencyStopRsq => Self::EmergencyStopRsq,
+            BlnMessage::EmergencyStopRsp => Self::EmergencyStopRsp,
+            BlnMessage::StopRsq => Self::StopRsq,
+            BlnMessage::StopRsp => Self::StopRsp,
+            BlnMessage::Stopped { x, y } => Self::Stopped(x, y),
+            BlnMessage::HomeRsq => Self::HomeRsq,
+            BlnMessage::HomeRsp => Self::HomeRsp,
+            BlnMessage::HomeReached { x, y } => Self::HomeReached(x, y),
+            BlnMessage::MoveRelativeRsq { dx, dy } => Self::MoveRelativeRsq(dx, dy),
+            BlnMessage::MoveRelativeRsp => Self::MoveRelativeRsp,
+            BlnMessage::MoveRelativeReached { x, y } => Self::MoveRelativeReached(x, y),
+            BlnMessage::JogStartRsq { axis, velocity } => Self::JogStartRsq(axis, velocity),
+            BlnMessage::JogStartRsp => Self::JogStartRsp,
+            BlnMessage::JogStopRsq { axis } => Self::JogStopRsq(axis),
+            BlnMessage::JogStopRsp => Self::JogStopRsp,
+            BlnMessage::JogStopped { x, y } => Self::JogStopped(x, y),
+            BlnMessage::SetVelocityRsq { velocity } => Self::SetVelocityRsq(velocity),
+            BlnMessage::SetVelocityRsp => Self::SetVelocityRsp,
+            BlnMessage::ErrorRsp { cause } => Self::ErrorRsp(cause),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{collections::BTreeSet, format, string::ToString, vec::Vec};
+    use protocol::types::Command;
+
+    use crate::protocol::types::{
+        BlnAxis, BlnConfigId, BlnDeviceStatus, BlnErrorCause, BlnProtocolType, BlnResponseStatus,
+    };
+
+    #[test]
+    fn test_every_message_round_trips() {
+        // 字节互不相同且都小于 0x40, 任意 4 个字节组成的 f32 都是有限值,
+        // 字段错位或变体映射错误都会改变重新编码的结果.
+        let payload: Vec<u8> = (0x10..0x30).collect();
+        let mut variants = BTreeSet::new();
+        for cmd_id in 0..=u8::MAX {
+            for status in 0..4 {
+                for len in 0..=payload.len() {
+                    let command = Command::new(cmd_id)
+                        .with_status(status)
+                        .with_payload(payload[..len].to_vec());
+                    let Ok(message) = BlnProtocolType::try_from(command) else {
+                        continue;
+                    };
+                    let json = serde_json::to_value(message).unwrap();
+                    let name = format!("{message:?}");
+                    let name = name.split(['(', ' ']).next().unwrap();
+                    assert_eq!(json["type"], name, "{json}");
+
+                    let decoded = serde_json::from_value::<BlnProtocolType>(json).unwrap();
+                    let expected = Command::try_from(message).unwrap();
+                    let actual = Command::try_from(decoded).unwrap();
+                    assert_eq!(actual.cmd_id(), expected.cmd_id(), "{name}");
+                    assert_eq!(
+                        actual.response_status(),
+                        expected.response_status(),
+                        "{name}"
+                    );
+                    assert_eq!(actual.payload(), expected.payload(), "{name}");
+                    variants.insert(name.to_string());
+                }
+            }
+        }
+        // 遍历应覆盖所有变体, 新增的变体只会让这个数字变大.
+        assert!(variants.len() >= 32, "{variants:?}");
+    }
+
+    #[test]
+    fn test_messages_are_tagged_objects() {
+        let message = BlnProtocolType::GetPositionRsp(1.5, -2.0, BlnDeviceStatus::MOVING);
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"GetPositionRsp","x":1.5,"y":-2.0,"status":1}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<BlnProtocolType>(&json).unwrap(),
+            message
+        );
+
+        let json = serde_json::to_string(&BlnProtocolType::ErrorRsp(BlnErrorCause::StateMismatch))
+            .unwrap();
+        assert_eq!(json, r#"{"type":"ErrorRsp","cause":"StateMismatch"}"#);
+        assert_eq!(
+            serde_json::to_string(&BlnProtocolType::GetPositionRsq).unwrap(),
+            r#"{"type":"GetPositionRsq"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&BlnProtocolType::ReadConfigRsp(BlnConfigId::Speed, 5.0))
+                .unwrap(),
+            r#"{"type":"ReadConfigRsp","item":"Speed","value":5.0}"#
+        );
+        let json = r#"{"type":"JogStartRsq","axis":"X","velocity":-2.5}"#;
+        assert_eq!(
+            serde_json::from_str::<BlnProtocolType>(json).unwrap(),
+            BlnProtocolType::JogStartRsq(BlnAxis::X, -2.5)
+        );
+        assert_eq!(
+            serde_json::from_str::<BlnResponseStatus>(r#""OkWithData""#).unwrap(),
+            BlnResponseStatus::OkWithData
+        );
+    }
+}
@@ -1,31 +1,138 @@
-use std::convert::From;
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
 
+#[cfg(feature = "alloc")]
 use bytes::{Buf, BufMut, Bytes, BytesMut};
+use protocol::message::ProtocolMessage;
+#[cfg(feature = "alloc")]
 use protocol::{
     traits::{FrameGenerator, ParseProtocol},
-    types::{Command, ProtocolError},
+    types::{Command, Diagnostic, Diagnostics, ProtocolError},
     utils::calculate_bcc,
 };
+#[cfg(feature = "std")]
 use tracing::{debug, info, instrument};
 
 /// BLN 协议定义的特定指令类型.
 ///
 /// 这个枚举列出了所有支持的协议命令, 并且可以方便地与 `u8` 类型进行转换,
 /// 以便在协议帧中表示.
-#[derive(Debug, PartialEq, Clone, Copy)]
+///
+/// 启用 `serde` feature 后, 消息被序列化为带 `type` 标签的对象,
+/// 如 `{"type":"GetPositionRsp","x":1.0,"y":2.0,"status":0}`.
+///
+/// 与 `Command` 之间的转换由 `ProtocolMessage` 派生: 请求的状态为 `Unused`,
+/// 任意命令字的 `Error` 状态都解析为 `ErrorRsp`, 数值字段均为小端字节序.
+#[derive(Debug, PartialEq, Clone, Copy, ProtocolMessage)]
+#[protocol(status = BlnResponseStatus)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        into = "crate::protocol::serialize::BlnMessage",
+        from = "crate::protocol::serialize::BlnMessage"
+    )
+)]
 pub enum BlnProtocolType {
     /// 设置位置请求
+    #[cmd(0x31, status = Unused)]
     SetPositionRsq(f32, f32),
     /// 设置位置响应 (第一阶段：通信确认)
+    #[cmd(0x91, status = Ok)]
     SetPositionRsp,
     /// 位置到达响应 (第二阶段：执行完成)
+    #[cmd(0x91, status = OkWithData)]
     PositionReached(f32, f32),
     /// 获取位置请求
+    #[cmd(0x33, status = Unused)]
     GetPositionRsq,
-    /// 获取位置响应
-    GetPositionRsp(f32, f32, u8),
+    /// 获取位置响应, 包含位置和设备状态
+    #[cmd(0x93, status = OkWithData)]
+    GetPositionRsp(f32, f32, #[field(u8)] BlnDeviceStatus),
+    /// 读取配置项请求
+    #[cmd(0x35, status = Unused)]
+    ReadConfigRsq(#[field(u8)] BlnConfigId),
+    /// 读取配置项响应, 包含配置项和它的值
+    #[cmd(0x95, status = OkWithData)]
+    ReadConfigRsp(#[field(u8)] BlnConfigId, f32),
+    /// 写入配置项请求, 写入的值在保存之前掉电会丢失
+    #[cmd(0x37, status = Unused)]
+    WriteConfigRsq(#[field(u8)] BlnConfigId, f32),
+    /// 写入配置项响应, 包含被写入的配置项
+    #[cmd(0x97, status = OkWithData)]
+    WriteConfigRsp(#[field(u8)] BlnConfigId),
+    /// 列出配置项请求
+    #[cmd(0x39, status = Unused)]
+    ListConfigRsq,
+    /// 列出配置项响应, 第 n 位为 1 表示设备支持 ID 为 n 的配置项,
+    /// 可以通过 [`BlnConfigId::from_mask`] 展开
+    #[cmd(0x99, status = OkWithData)]
+    ListConfigRsp(u32),
+    /// 将当前配置保存到闪存请求
+    #[cmd(0x3B, status = Unused)]
+    SaveConfigRsq,
+    /// 保存配置响应
+    #[cmd(0x9B, status = Ok)]
+    SaveConfigRsp,
+    /// 急停请求, 设备立即停止所有轴并放弃正在进行的运动
+    #[cmd(0x3D, status = Unused)]
+    EmergencyStopRsq,
+    /// 急停响应, 急停立即生效, 没有完成阶段
+    #[cmd(0x9D, status = Ok)]
+    EmergencyStopRsp,
+    /// 减速停止请求
+    #[cmd(0x3F, status = Unused)]
+    StopRsq,
+    /// 减速停止响应 (第一阶段：通信确认)
+    #[cmd(0x9F, status = Ok)]
+    StopRsp,
+    /// 已停止响应 (第二阶段：执行完成), 包含停止时的位置
+    #[cmd(0x9F, status = OkWithData)]
+    Stopped(f32, f32),
+    /// 回零请求
+    #[cmd(0x41, status = Unused)]
+    HomeRsq,
+    /// 回零响应 (第一阶段：通信确认)
+    #[cmd(0xA1, status = Ok)]
+    HomeRsp,
+    /// 回零完成响应 (第二阶段：执行完成), 包含回零后的位置
+    #[cmd(0xA1, status = OkWithData)]
+    HomeReached(f32, f32),
+    /// 相对移动请求, 包含每个轴的位移
+    #[cmd(0x43, status = Unused)]
+    MoveRelativeRsq(f32, f32),
+    /// 相对移动响应 (第一阶段：通信确认)
+    #[cmd(0xA3, status = Ok)]
+    MoveRelativeRsp,
+    /// 相对移动到达响应 (第二阶段：执行完成), 包含到达后的绝对位置
+    #[cmd(0xA3, status = OkWithData)]
+    MoveRelativeReached(f32, f32),
+    /// 点动开始请求, 包含轴和速度, 速度的符号表示方向.
+    /// 轴持续运动, 直到收到点动停止请求或到达软限位
+    #[cmd(0x45, status = Unused)]
+    JogStartRsq(#[field(u8)] BlnAxis, f32),
+    /// 点动开始响应, 点动没有完成阶段
+    #[cmd(0xA5, status = Ok)]
+    JogStartRsp,
+    /// 点动停止请求
+    #[cmd(0x47, status = Unused)]
+    JogStopRsq(#[field(u8)] BlnAxis),
+    /// 点动停止响应 (第一阶段：通信确认)
+    #[cmd(0xA7, status = Ok)]
+    JogStopRsp,
+    /// 点动已停止响应 (第二阶段：执行完成), 包含停止时的位置
+    #[cmd(0xA7, status = OkWithData)]
+    JogStopped(f32, f32),
+    /// 设置运动速度请求, 对之后开始的运动生效, 不会写入闪存
+    #[cmd(0x49, status = Unused)]
+    SetVelocityRsq(f32),
+    /// 设置运动速度响应
+    #[cmd(0xA9, status = Ok)]
+    SetVelocityRsp,
     /// 错误响应，包含具体的错误原因。
-    ErrorRsp(BlnErrorCause),
+    /// 解析时不关心命令字, 生成时统一使用 0x00.
+    #[cmd(0x00, status = Error, any_cmd)]
+    ErrorRsp(#[field(u8)] BlnErrorCause),
 }
 
 /// 表示 Bln 协议响应中的状态标志.
@@ -33,6 +140,7 @@ pub enum BlnProtocolType {
 /// 这个枚举定义了响应是成功 (带数据或不带数据), 还是错误状态,
 /// 或是未使用的/保留的值.
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum BlnResponseStatus {
     /// 未使用或未指定的状态。
@@ -73,11 +181,189 @@ impl From<BlnResponseStatus> for u8 {
     }
 }
 
+/// 设备配置项的 ID.
+///
+/// 配置项的值均为 `f32`, 位置相关的配置项与 `SetPositionRsq` 使用相同的单位.
+/// 设备不支持的配置项返回 `BlnErrorCause::ConfigNotFound`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlnConfigId {
+    /// 移动速度, 单位为 位置单位/秒.
+    Speed,
+    /// 加速度, 单位为 位置单位/秒².
+    Acceleration,
+    /// X 轴软限位的最小值.
+    SoftLimitMinX,
+    /// X 轴软限位的最大值.
+    SoftLimitMaxX,
+    /// Y 轴软限位的最小值.
+    SoftLimitMinY,
+    /// Y 轴软限位的最大值.
+    SoftLimitMaxY,
+    /// X 轴回零后的偏移.
+    HomeOffsetX,
+    /// Y 轴回零后的偏移.
+    HomeOffsetY,
+    /// 本协议未定义的配置项.
+    Other(u8),
+}
+
+impl BlnConfigId {
+    /// 本协议定义的所有配置项.
+    pub const ALL: [Self; 8] = [
+        Self::Speed,
+        Self::Acceleration,
+        Self::SoftLimitMinX,
+        Self::SoftLimitMaxX,
+        Self::SoftLimitMinY,
+        Self::SoftLimitMaxY,
+        Self::HomeOffsetX,
+        Self::HomeOffsetY,
+    ];
+
+    /// 展开 `ListConfigRsp` 中的位掩码, 按 ID 从小到大返回配置项.
+    pub fn from_mask(mask: u32) -> impl Iterator<Item = Self> {
+        (0..u32::BITS as u8)
+            .filter(move |id| mask & (1 << id) != 0)
+            .map(Self::from)
+    }
+
+    /// 将配置项合并为 `ListConfigRsp` 中的位掩码, ID 不小于 32 的配置项被忽略.
+    pub fn to_mask(items: impl IntoIterator<Item = Self>) -> u32 {
+        items
+            .into_iter()
+            .map(u8::from)
+            .filter(|&id| u32::from(id) < u32::BITS)
+            .fold(0, |mask, id| mask | 1 << id)
+    }
+}
+
+impl From<u8> for BlnConfigId {
+    /// 将 `u8` 值转换为 `BlnConfigId`, 未定义的 ID 映射到 `Other`.
+    fn from(value: u8) -> Self {
+        match value {
+            0x01 => Self::Speed,
+            0x02 => Self::Acceleration,
+            0x03 => Self::SoftLimitMinX,
+            0x04 => Self::SoftLimitMaxX,
+            0x05 => Self::SoftLimitMinY,
+            0x06 => Self::SoftLimitMaxY,
+            0x07 => Self::HomeOffsetX,
+            0x08 => Self::HomeOffsetY,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl From<BlnConfigId> for u8 {
+    /// 将 `BlnConfigId` 转换为 `u8` 值.
+    fn from(value: BlnConfigId) -> Self {
+        match value {
+            BlnConfigId::Speed => 0x01,
+            BlnConfigId::Acceleration => 0x02,
+            BlnConfigId::SoftLimitMinX => 0x03,
+            BlnConfigId::SoftLimitMaxX => 0x04,
+            BlnConfigId::SoftLimitMinY => 0x05,
+            BlnConfigId::SoftLimitMaxY => 0x06,
+            BlnConfigId::HomeOffsetX => 0x07,
+            BlnConfigId::HomeOffsetY => 0x08,
+            BlnConfigId::Other(id) => id,
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// `GetPositionRsp` 中的设备状态字节.
+    ///
+    /// 本协议未定义的位会被原样保留, 格式化时以十六进制显示.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+    pub struct BlnDeviceStatus: u8 {
+        /// 有轴正在运动.
+        const MOVING = 0x01;
+        /// 设备已回零, 位置有效.
+        const HOMED = 0x02;
+        /// 有轴停在软限位或硬限位上.
+        const AT_LIMIT = 0x04;
+        /// 驱动器或设备报告故障, 需要处理后才能继续运动.
+        const FAULT = 0x08;
+        /// X 轴已使能.
+        const X_ENABLED = 0x10;
+        /// Y 轴已使能.
+        const Y_ENABLED = 0x20;
+        /// 急停已锁定, 回零后解除.
+        const EMERGENCY_STOP = 0x40;
+    }
+}
+
+impl BlnDeviceStatus {
+    /// 设备是否处于可以开始新运动的状态: 没有故障、没有急停锁定且所有轴已使能.
+    pub fn is_ready(self) -> bool {
+        !self.intersects(Self::FAULT | Self::EMERGENCY_STOP)
+            && self.contains(Self::X_ENABLED | Self::Y_ENABLED)
+    }
+}
+
+impl core::fmt::Display for BlnDeviceStatus {
+    /// 以 `MOVING | HOMED` 的形式显示, 没有任何位时显示为空.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        bitflags::parser::to_writer(self, f)
+    }
+}
+
+impl From<u8> for BlnDeviceStatus {
+    /// 将状态字节转换为 `BlnDeviceStatus`, 保留未定义的位.
+    fn from(value: u8) -> Self {
+        Self::from_bits_retain(value)
+    }
+}
+
+impl From<BlnDeviceStatus> for u8 {
+    /// 将 `BlnDeviceStatus` 转换为状态字节.
+    fn from(value: BlnDeviceStatus) -> Self {
+        value.bits()
+    }
+}
+
+/// 运动轴, 用于点动等针对单个轴的命令.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlnAxis {
+    /// X 轴.
+    X,
+    /// Y 轴.
+    Y,
+    /// 本协议未定义的轴.
+    Other(u8),
+}
+
+impl From<u8> for BlnAxis {
+    /// 将 `u8` 值转换为 `BlnAxis`, 未定义的轴映射到 `Other`.
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => Self::X,
+            0x01 => Self::Y,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl From<BlnAxis> for u8 {
+    /// 将 `BlnAxis` 转换为 `u8` 值.
+    fn from(value: BlnAxis) -> Self {
+        match value {
+            BlnAxis::X => 0x00,
+            BlnAxis::Y => 0x01,
+            BlnAxis::Other(axis) => axis,
+        }
+    }
+}
+
 /// 定义了 Bln 协议中各种错误的原因码.
 ///
 /// 这些错误码通常在 `BlnResponseStatus::Error` 状态下,
 /// 通过响应帧的 payload 来传递.
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum BlnErrorCause {
     /// 表示操作成功完成，没有错误。
@@ -134,10 +420,14 @@ impl From<BlnErrorCause> for u8 {
 /// `BlnCommandEncoder` 是一个实现了 `FrameGenerator` trait 的具体编码器.
 ///
 /// 它的唯一职责是将一个 `Command` 对象序列化成符合 BLN 协议规范的字节帧 (`Bytes`).
+/// `Command` 的响应状态会被写入长度字段的高 3 位, 因此请求帧和响应帧都可以被编码.
+/// 4 字节的保留字段会被原样写入, 以便分片等上层机制使用.
 /// 这个结构体是无状态的.
+#[cfg(feature = "alloc")]
 #[derive(Default)]
 pub struct BlnCommandEncoder;
 
+#[cfg(feature = "alloc")]
 impl BlnCommandEncoder {
     // BLN 协议帧结构中使用的常量
     /// 协议帧的头部同步字,用于标识帧的开始.
@@ -150,33 +440,64 @@ impl BlnCommandEncoder {
     const FRAME_BCC_LEN: usize = 1;
     /// 协议帧中的保留字段长度.
     const RESERVED_LEN: usize = 4;
+    // 长度字段 (u16) 的位掩码常量, 用于组合数据长度和标志位
+    const DATA_LENGTH_MASK: u16 = 0x1FFF; // 低 13 位用于实际数据长度
+    const FLAGS_MAX: u8 = 0x07; // 高 3 位用于标志位，如响应状态
+    const FLAGS_SHIFT: u16 = 13;
+
+    /// 将数据长度和响应状态组合为 2 字节的长度字段.
+    fn encode_length_flags(
+        &self,
+        cmd_id: u8,
+        data_len: usize,
+        flags: u8,
+    ) -> Result<u16, ProtocolError> {
+        if data_len > Self::DATA_LENGTH_MASK as usize {
+            return Err(ProtocolError::PayloadTooLarge {
+                len: data_len,
+                max: Self::DATA_LENGTH_MASK as usize,
+            });
+        }
+        if flags > Self::FLAGS_MAX {
+            return Err(ProtocolError::UnexpectedStatus {
+                cmd_id,
+                status: flags,
+            });
+        }
+        Ok(data_len as u16 | (flags as u16) << Self::FLAGS_SHIFT)
+    }
 }
 
+#[cfg(feature = "alloc")]
 impl FrameGenerator for BlnCommandEncoder {
     /// 实现 `create_frame`, 将 `Command` 编码为 `Bytes`.
     fn create_frame(&self, command: Command) -> Result<Bytes, ProtocolError> {
-        if command.cmd_type.len() != 1 {
-            return Err(ProtocolError::InvalidCommandType);
-        }
-        let cmd_byte = command.cmd_type[0];
-
-        let data = command.payload.unwrap_or_default();
+        let data = command.payload();
         let data_len = data.len();
+        // 请求帧没有响应状态, 此时标志位为 0
+        let len_field = self.encode_length_flags(
+            command.cmd_id(),
+            data_len,
+            command.response_status().unwrap_or(0),
+        )?;
 
         let total_len = Self::FRAME_FIXED_LEN + data_len + Self::FRAME_BCC_LEN;
         let mut buf = BytesMut::with_capacity(total_len);
 
         // 按照 BLN 协议格式组装帧
         buf.put_slice(&Self::FRAME_HEAD);
-        buf.put_u8(cmd_byte);
-        buf.put_slice(&[0x00; Self::RESERVED_LEN]); // 保留字段
-        buf.put_u16(data_len as u16);
-        if !data.is_empty() {
-            buf.put_slice(&data);
+        buf.put_u8(command.cmd_id());
+        // 保留字段, 命令没有携带 4 字节的保留字段时写入 0
+        match command.reserved() {
+            reserved if reserved.len() == Self::RESERVED_LEN => buf.put_slice(reserved),
+            _ => buf.put_slice(&[0x00; Self::RESERVED_LEN]),
         }
+        buf.put_u16(len_field);
+        buf.put_slice(data);
         // 计算并附加 BCC 校验码
         buf.put_u8(calculate_bcc(&buf[Self::FRAME_HEAD_LEN..]));
 
+        #[cfg(feature = "std")]
         info!("BLN Frame Created: {:02X?}", buf.as_ref());
         Ok(buf.freeze())
     }
@@ -185,10 +506,58 @@ impl FrameGenerator for BlnCommandEncoder {
 /// `BlnCommandDecode` 是一个实现了 `ParseProtocol` trait 的具体解码器.
 ///
 /// 它的唯一职责是从一个连续的字节流中解析出符合 BLN 协议规范的 `Command` 帧.
-/// 这个结构体是无状态的, 所有的解析状态都通过传入的 `BytesMut` 缓冲区来管理.
-#[derive(Default)]
-pub struct BlnCommandDecode;
+/// 解码器是增量式的: 它会记住缓冲区开头是否已对齐到帧头、当前帧的长度以及已累计的 BCC,
+/// 因此对于正常的数据流, 无论帧被拆分成多少次读取, 每个字节都只会被扫描一次.
+/// 校验失败或长度超限时, 解码器只跳过 2 字节的帧头, 并从其后重新查找帧头,
+/// 这时该帧其余的字节会被再次扫描. 因此"每个字节扫描一次"只是正常数据流上的摊还开销,
+/// 在持续的噪声下, 一个字节可能被其前方一个最大帧长度内的每个候选帧头各扫描一次.
+/// 由于状态与缓冲区内容相对应, 同一个解码器应始终用于同一个缓冲区.
+///
+/// 为防止损坏的长度字段或持续的噪声耗尽内存, 解码器限制了单帧的负载长度.
+/// 超长的帧会被跳过帧头并重新同步, 同时记录一个 [`Diagnostic`]. 解析结束后缓冲区中只保留
+/// 尚未完整的一个帧 (或可能是帧头首字节的一个字节), 因此积压的字节数不会超过一个最大长度的帧.
+#[cfg(feature = "alloc")]
+pub struct BlnCommandDecode {
+    /// 当前的解析状态.
+    state: DecodeState,
+    /// 允许的最大负载长度.
+    max_payload_len: usize,
+    /// 尚未被取出的诊断事件.
+    diagnostics: Diagnostics,
+}
 
+#[cfg(feature = "alloc")]
+impl Default for BlnCommandDecode {
+    fn default() -> Self {
+        Self {
+            state: DecodeState::SeekHead,
+            max_payload_len: Self::MAX_PAYLOAD_LEN,
+            diagnostics: Diagnostics::default(),
+        }
+    }
+}
+
+/// `BlnCommandDecode` 在两次调用之间保存的解析状态.
+///
+/// 除 `SeekHead` 外, 所有状态都意味着缓冲区的开头就是一个帧头.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum DecodeState {
+    /// 正在寻找帧头.
+    #[default]
+    SeekHead,
+    /// 帧头已对齐, 等待长度字段到齐.
+    AwaitLength,
+    /// 帧长度已知, 等待整个帧到齐.
+    /// `checked` 是已计入 `bcc` 的字节位置 (不含帧头), 之前的字节不会被再次计算.
+    AwaitBody {
+        frame_len: usize,
+        checked: usize,
+        bcc: u8,
+    },
+}
+
+#[cfg(feature = "alloc")]
 impl BlnCommandDecode {
     // BLN 协议帧结构中使用的常量
     /// 协议帧的头部同步字,用于标识帧的开始.
@@ -203,11 +572,26 @@ impl BlnCommandDecode {
     const RESERVED_LEN: usize = 4;
     /// 协议帧中的命令类型字段长度.
     const TYPE_LEN: usize = 1;
-    const DATA_LEN_FRAME_LEN: usize = 2;
     const DATA_LEN_FRAME_START: usize = 7;
     // 长度字段 (u16) 的位掩码常量, 用于分离数据长度和标志位
     const DATA_LENGTH_MASK: u16 = 0x1FFF; // 低 13 位用于实际数据长度
     const FLAGS_MASK: u16 = 0xE000; // 高 3 位用于标志位，如响应状态
+    /// 默认允许的最大负载长度, 即长度字段可表示的最大值.
+    const MAX_PAYLOAD_LEN: usize = Self::DATA_LENGTH_MASK as usize;
+
+    /// 设置允许的最大负载长度, 默认为长度字段可表示的 8191 字节, 设置更大的值没有效果.
+    ///
+    /// 缓冲区中积压的字节数不会超过一个负载为该长度的帧. 已知设备只发送较短的帧时,
+    /// 可以设置更小的值, 让损坏的长度字段更快地被跳过.
+    pub fn max_payload_len(mut self, max_payload_len: usize) -> Self {
+        self.max_payload_len = max_payload_len.min(Self::MAX_PAYLOAD_LEN);
+        self
+    }
+
+    /// 丢弃当前的解析状态, 在更换缓冲区或连接重建后使用.
+    pub fn reset(&mut self) {
+        self.state = DecodeState::SeekHead;
+    }
 
     /// 从 2 字节的长度字段中解码出实际数据长度和高位标志 (如响应状态).
     fn decode_length_flags(&self, len_field: u16) -> (usize, u8) {
@@ -217,86 +601,181 @@ impl BlnCommandDecode {
         )
     }
 
-    /// 检查缓冲区中是否包含一个完整的协议帧.
-    /// 如果是, 返回整个帧的长度; 否则返回 `None`.
-    fn is_frame_complete(&self, buf: &BytesMut) -> Option<usize> {
-        let min_len = Self::FRAME_FIXED_LEN + Self::FRAME_BCC_LEN;
-        if buf.len() < min_len {
-            return None; // 数据不足, 无法判断
+    /// 记录一个诊断事件.
+    fn report(&mut self, diagnostic: Diagnostic) {
+        #[cfg(feature = "std")]
+        info!("{}", diagnostic);
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// 在缓冲区中查找帧头, 并丢弃帧头之前的所有数据.
+    ///
+    /// 与 `ParseProtocol::find_frame_head` 不同, 找不到帧头时也会丢弃已扫描过的数据,
+    /// 只保留可能是帧头首字节的最后一个字节, 因此下次调用无需重新扫描.
+    fn seek_head(&self, buf: &mut BytesMut) -> bool {
+        let mut from = 0;
+        while let Some(offset) = buf[from..].iter().position(|&b| b == Self::FRAME_HEAD[0]) {
+            let index = from + offset;
+            match buf.get(index + 1) {
+                // 帧头首字节位于缓冲区末尾, 等待下一个字节
+                None => {
+                    buf.advance(index);
+                    return false;
+                }
+                Some(&b) if b == Self::FRAME_HEAD[1] => {
+                    buf.advance(index);
+                    return true;
+                }
+                Some(_) => from = index + 1,
+            }
         }
+        buf.clear();
+        false
+    }
 
-        // 从帧的特定位置读取长度字段
-        let len_bytes: [u8; Self::DATA_LEN_FRAME_LEN] = if let Ok(len) = buf
-            [Self::DATA_LEN_FRAME_START..(Self::DATA_LEN_FRAME_START + Self::DATA_LEN_FRAME_LEN)]
-            .try_into()
-        {
-            len
-        } else {
-            return None;
-        };
+    /// 从缓冲区开头切出一个已校验的完整帧, 并将其解析为 `Command`.
+    ///
+    /// 切出的帧被冻结为 `Bytes`, `Command` 中的保留字段和负载都是它的切片, 不会复制数据.
+    fn split_frame(&self, buf: &mut BytesMut, frame_len: usize) -> Command {
+        let frame = buf.split_to(frame_len).freeze();
+        let mut header = &frame[Self::FRAME_HEAD_LEN..Self::FRAME_FIXED_LEN];
 
-        let len_field = u16::from_be_bytes(len_bytes);
+        let cmd_id = header.get_u8();
+        header.advance(Self::RESERVED_LEN); // 跳过保留字段
+        let (data_len, flags) = self.decode_length_flags(header.get_u16());
 
-        // 解码出实际的数据负载长度
-        let (data_len, _) = self.decode_length_flags(len_field);
-        // 计算出完整的帧长度
-        let frame_len = data_len + min_len;
+        let reserved_start = Self::FRAME_HEAD_LEN + Self::TYPE_LEN;
+        Command::from_frame(
+            frame,
+            cmd_id,
+            Some(flags),
+            reserved_start..reserved_start + Self::RESERVED_LEN,
+            Self::FRAME_FIXED_LEN..Self::FRAME_FIXED_LEN + data_len,
+        )
+    }
 
-        // 如果缓冲区的数据还不够一个完整帧的长度, 返回 None
-        if buf.len() < frame_len {
-            return None;
+    /// 将一段恰好包含一个完整帧的数据解析为 `Command`.
+    ///
+    /// 与流式的 `parse_protocol_frame` 不同, 这里不会跳过噪声或等待更多数据,
+    /// 帧头、长度或校验码不正确时直接返回带有上下文的错误.
+    pub fn decode_frame(&self, frame: &[u8]) -> Result<Command, ProtocolError> {
+        if !frame.starts_with(&Self::FRAME_HEAD) {
+            return Err(ProtocolError::MissingFrameHead);
+        }
+        let min_len = Self::FRAME_FIXED_LEN + Self::FRAME_BCC_LEN;
+        if frame.len() < min_len {
+            return Err(ProtocolError::FrameLength {
+                expected: min_len,
+                actual: frame.len(),
+            });
+        }
+        let len_field = u16::from_be_bytes([
+            frame[Self::DATA_LEN_FRAME_START],
+            frame[Self::DATA_LEN_FRAME_START + 1],
+        ]);
+        let (data_len, _) = self.decode_length_flags(len_field);
+        if data_len > self.max_payload_len {
+            return Err(ProtocolError::PayloadTooLarge {
+                len: data_len,
+                max: self.max_payload_len,
+            });
+        }
+        let frame_len = min_len + data_len;
+        if frame.len() != frame_len {
+            return Err(ProtocolError::FrameLength {
+                expected: frame_len,
+                actual: frame.len(),
+            });
         }
-        Some(frame_len)
+        let bcc_index = frame_len - Self::FRAME_BCC_LEN;
+        let bcc = calculate_bcc(&frame[Self::FRAME_HEAD_LEN..bcc_index]);
+        if bcc != frame[bcc_index] {
+            return Err(ProtocolError::ChecksumMismatch {
+                expected: bcc,
+                actual: frame[bcc_index],
+            });
+        }
+        Ok(self.split_frame(&mut BytesMut::from(frame), frame_len))
     }
 }
 
+#[cfg(feature = "alloc")]
 impl ParseProtocol for BlnCommandDecode {
-    #[instrument(skip(self, buf))]
+    #[cfg_attr(feature = "std", instrument(skip(self, buf)))]
     /// 实现 `parse_protocol_frame`, 尝试从缓冲区 `buf` 中解析出所有可能的 `Command` 帧.
+    ///
+    /// 解析从上次调用停下的状态继续, 正常情况下每个字节只参与一次帧头查找和一次 BCC 计算.
+    /// 校验失败或长度超限时只跳过帧头, 被拒绝的帧的其余字节会重新参与帧头查找.
     fn parse_protocol_frame(&mut self, buf: &mut bytes::BytesMut) -> Option<Vec<Command>> {
         let mut command_list = vec![];
         // 循环处理, 因为缓冲区中可能包含多个帧
         loop {
-            // 首先找到帧头, 然后检查帧是否完整
-            if self.find_frame_head(buf, &Self::FRAME_HEAD)
-                && let Some(frame_len) = self.is_frame_complete(buf)
-            {
-                // 校验 BCC
-                if calculate_bcc(&buf[Self::FRAME_HEAD_LEN..frame_len - Self::FRAME_BCC_LEN])
-                    == buf[frame_len - Self::FRAME_BCC_LEN]
-                {
-                    // 校验成功, 解析帧内容
-                    let mut frame_data = buf.split_to(frame_len);
-                    frame_data.advance(Self::FRAME_HEAD_LEN); // 跳过帧头
-
-                    let cmd_type_byte = frame_data.get_u8();
-                    frame_data.advance(Self::RESERVED_LEN); // 跳过保留字段
-                    let (data_len, flags) = self.decode_length_flags(frame_data.get_u16());
-
-                    let payload = if data_len > 0 {
-                        Some(frame_data.split_to(data_len))
-                    } else {
-                        None
+            match self.state {
+                DecodeState::SeekHead => {
+                    if !self.seek_head(buf) {
+                        break;
+                    }
+                    self.state = DecodeState::AwaitLength;
+                }
+                DecodeState::AwaitLength => {
+                    if buf.len() < Self::FRAME_FIXED_LEN {
+                        break; // 数据不足, 无法读取长度字段
+                    }
+                    let len_field = u16::from_be_bytes([
+                        buf[Self::DATA_LEN_FRAME_START],
+                        buf[Self::DATA_LEN_FRAME_START + 1],
+                    ]);
+                    let (data_len, _) = self.decode_length_flags(len_field);
+                    if data_len > self.max_payload_len {
+                        // 长度字段很可能已损坏, 跳过帧头重新同步, 而不是等待大量数据
+                        self.report(Diagnostic::OversizeFrame {
+                            payload_len: data_len,
+                            max_payload_len: self.max_payload_len,
+                        });
+                        buf.advance(Self::FRAME_HEAD_LEN);
+                        self.state = DecodeState::SeekHead;
+                        continue;
+                    }
+                    self.state = DecodeState::AwaitBody {
+                        frame_len: Self::FRAME_FIXED_LEN + data_len + Self::FRAME_BCC_LEN,
+                        checked: Self::FRAME_HEAD_LEN,
+                        bcc: 0,
                     };
+                }
+                DecodeState::AwaitBody {
+                    frame_len,
+                    checked,
+                    bcc,
+                } => {
+                    // 只对新到达的字节累计 BCC
+                    let bcc_index = frame_len - Self::FRAME_BCC_LEN;
+                    let end = buf.len().min(bcc_index);
+                    let bcc = bcc ^ calculate_bcc(&buf[checked..end]);
+                    if buf.len() < frame_len {
+                        self.state = DecodeState::AwaitBody {
+                            frame_len,
+                            checked: end,
+                            bcc,
+                        };
+                        break;
+                    }
 
-                    let mut cmd_type = BytesMut::with_capacity(Self::TYPE_LEN);
-                    cmd_type.put_u8(cmd_type_byte);
-
-                    let cmd = Command {
-                        cmd_type,
-                        response_status: Some(flags),
-                        payload,
-                    };
-                    debug!(%cmd);
-                    command_list.push(cmd);
-                } else {
-                    // 校验失败, 丢弃这一个字节, 从下一个字节开始重新寻找帧头
-                    info!("BCC check failed for a frame, discarding it.");
-                    buf.advance(1);
+                    if bcc == buf[bcc_index] {
+                        // 校验成功, 解析帧内容
+                        let cmd = self.split_frame(buf, frame_len);
+                        #[cfg(feature = "std")]
+                        debug!(%cmd);
+                        command_list.push(cmd);
+                    } else {
+                        // 校验失败, 丢弃这个帧头, 从其后重新寻找帧头
+                        self.report(Diagnostic::ChecksumMismatch {
+                            expected: bcc,
+                            actual: buf[bcc_index],
+                        });
+                        buf.advance(Self::FRAME_HEAD_LEN);
+                    }
+                    self.state = DecodeState::SeekHead;
                 }
-            } else {
-                // 在当前缓冲区中再也找不到完整的帧了, 退出循环
-                break;
             }
         }
 
@@ -306,10 +785,17 @@ impl ParseProtocol for BlnCommandDecode {
             Some(command_list)
         }
     }
+
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.diagnostics.take()
+    }
 }
-#[cfg(test)]
+// 这些测试围绕 `Command` 和流式编解码器, 需要 `alloc`.
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
     use super::*;
+    use alloc::string::ToString;
+    use proptest::prelude::*;
 
     #[test]
     fn test_bln_response_status_from_u8() {
@@ -345,6 +831,350 @@ mod tests {
         assert_eq!(BlnErrorCause::from(0x08), BlnErrorCause::UnspecifiedError); // Unknown value
     }
 
+    #[test]
+    fn test_config_id_mask() {
+        let mask = BlnConfigId::to_mask([
+            BlnConfigId::Speed,
+            BlnConfigId::HomeOffsetY,
+            BlnConfigId::Other(31),
+            BlnConfigId::Other(40),
+        ]);
+        assert_eq!(mask, 1 << 1 | 1 << 8 | 1 << 31);
+        assert_eq!(
+            BlnConfigId::from_mask(mask).collect::<Vec<_>>(),
+            vec![
+                BlnConfigId::Speed,
+                BlnConfigId::HomeOffsetY,
+                BlnConfigId::Other(31)
+            ]
+        );
+        for item in BlnConfigId::ALL {
+            assert_eq!(BlnConfigId::from(u8::from(item)), item);
+        }
+    }
+
+    #[test]
+    fn test_device_status() {
+        let status = BlnDeviceStatus::from(0x33);
+        assert_eq!(
+            status,
+            BlnDeviceStatus::MOVING
+                | BlnDeviceStatus::HOMED
+                | BlnDeviceStatus::X_ENABLED
+                | BlnDeviceStatus::Y_ENABLED
+        );
+        assert!(status.is_ready());
+        assert_eq!(status.to_string(), "MOVING | HOMED | X_ENABLED | Y_ENABLED");
+
+        // 未定义的位被保留
+        let status = BlnDeviceStatus::from(0x98);
+        assert_eq!(u8::from(status), 0x98);
+        assert_eq!(status.to_string(), "FAULT | X_ENABLED | 0x80");
+        assert!(!status.is_ready());
+        assert_eq!(BlnDeviceStatus::empty().to_string(), "");
+    }
+
+    // 构造一个 `Command`, 保留字段与解码结果一致, 为 4 个 0 字节.
+    fn command(cmd: u8, status: Option<u8>, payload: &[u8]) -> Command {
+        let command = Command::new(cmd)
+            .with_reserved(Bytes::from_static(&[0; 4]))
+            .with_payload(Bytes::copy_from_slice(payload));
+        match status {
+            Some(status) => command.with_status(status),
+            None => command,
+        }
+    }
+
+    #[test]
+    fn test_encoder_writes_response_status_bits() {
+        let frame = BlnCommandEncoder
+            .create_frame(command(
+                0x93,
+                Some(BlnResponseStatus::OkWithData.into()),
+                &[0xAB],
+            ))
+            .unwrap();
+
+        assert_eq!(
+            frame.as_ref(),
+            &[
+                0x55,
+                0xAA,
+                0x93,
+                0,
+                0,
+                0,
+                0,
+                0x40,
+                0x01,
+                0xAB,
+                0x93 ^ 0x40 ^ 0x01 ^ 0xAB
+            ]
+        );
+    }
+
+    #[test]
+    fn test_encoder_request_has_no_status_bits() {
+        let frame = BlnCommandEncoder
+            .create_frame(command(0x33, None, &[]))
+            .unwrap();
+
+        assert_eq!(
+            frame.as_ref(),
+            &[0x55, 0xAA, 0x33, 0, 0, 0, 0, 0x00, 0x00, 0x33]
+        );
+    }
+
+    #[test]
+    fn test_encoder_rejects_invalid_status() {
+        let result = BlnCommandEncoder.create_frame(command(0x91, Some(0x08), &[]));
+        assert_eq!(
+            result,
+            Err(ProtocolError::UnexpectedStatus {
+                cmd_id: 0x91,
+                status: 0x08
+            })
+        );
+    }
+
+    #[test]
+    fn test_encoder_rejects_oversized_payload() {
+        let result = BlnCommandEncoder.create_frame(command(0x31, None, &[0; 0x2000]));
+        assert_eq!(
+            result,
+            Err(ProtocolError::PayloadTooLarge {
+                len: 0x2000,
+                max: 0x1FFF
+            })
+        );
+    }
+
+    #[test]
+    fn test_decoder_reads_encoded_response() {
+        let expected = command(0x91, Some(BlnResponseStatus::Error.into()), &[0x02]);
+        let frame = BlnCommandEncoder
+            .create_frame(command(
+                0x91,
+                Some(BlnResponseStatus::Error.into()),
+                &[0x02],
+            ))
+            .unwrap();
+
+        let mut buf = BytesMut::from(frame.as_ref());
+        let commands = BlnCommandDecode::default()
+            .parse_protocol_frame(&mut buf)
+            .unwrap();
+        assert_eq!(commands, vec![expected]);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_decoder_preserves_raw_frame() {
+        let mut frame =
+            BytesMut::from(&[0x55, 0xAA, 0x93, 0x01, 0x02, 0x03, 0x04, 0x40, 0x01, 0xAB][..]);
+        frame.put_u8(calculate_bcc(&frame[2..]));
+        let raw = frame.clone();
+
+        let commands = BlnCommandDecode::default()
+            .parse_protocol_frame(&mut frame)
+            .unwrap();
+        assert_eq!(commands[0].frame(), Some(raw.as_ref()));
+        assert_eq!(commands[0].reserved(), &[0x01, 0x02, 0x03, 0x04]);
+        assert_eq!(commands[0].payload(), &[0xAB]);
+    }
+
+    #[test]
+    fn test_decode_frame_reports_context() {
+        let decoder = BlnCommandDecode::default();
+        let frame = BlnCommandEncoder
+            .create_frame(command(0x93, Some(0x02), &[0; 9]))
+            .unwrap();
+        let command = decoder.decode_frame(&frame).unwrap();
+        assert_eq!(command.cmd_id(), 0x93);
+        assert_eq!(command.frame(), Some(frame.as_ref()));
+
+        assert_eq!(
+            decoder.decode_frame(&frame[1..]),
+            Err(ProtocolError::MissingFrameHead)
+        );
+        assert_eq!(
+            decoder.decode_frame(&frame[..frame.len() - 1]),
+            Err(ProtocolError::FrameLength {
+                expected: frame.len(),
+                actual: frame.len() - 1
+            })
+        );
+        let mut corrupted = frame.to_vec();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0xFF;
+        assert_eq!(
+            decoder.decode_frame(&corrupted),
+            Err(ProtocolError::ChecksumMismatch {
+                expected: frame[last],
+                actual: frame[last] ^ 0xFF
+            })
+        );
+    }
+
+    #[test]
+    fn test_encoder_writes_reserved_field() {
+        let command = Command::new(0x33).with_reserved(vec![0x01, 0x02, 0x03, 0x04]);
+        let frame = BlnCommandEncoder.create_frame(command).unwrap();
+        assert_eq!(&frame[3..7], &[0x01, 0x02, 0x03, 0x04]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_fragmented_payload_round_trip() {
+        use protocol::fragment::{FragmentEncoder, ReassemblyDecoder};
+        use std::time::Duration;
+
+        // 每个分片的负载不超过 1024 字节, 接收端重组出完整的负载
+        let payload: Vec<u8> = (0..3000).map(|i| i as u8).collect();
+        let frames = FragmentEncoder::new(BlnCommandEncoder, 1024)
+            .create_frame(command(0x31, None, &payload))
+            .unwrap();
+
+        let mut decoder =
+            ReassemblyDecoder::new(BlnCommandDecode::default(), Duration::from_secs(1));
+        let mut buf = BytesMut::from(frames.as_ref());
+        let commands = decoder.parse_protocol_frame(&mut buf).unwrap();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].cmd_id(), 0x31);
+        assert_eq!(commands[0].payload(), payload.as_slice());
+        assert!(decoder.take_diagnostics().is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_secured_round_trip() {
+        use crate::protocol::BlnProtocol;
+        use protocol::{
+            security::{Role, Secured},
+            traits::ProtocolSplit,
+        };
+
+        const KEY: &[u8] = b"plant-network-key";
+        let (_, encoder) = Secured::new(BlnProtocol::default(), KEY).into_split();
+        let (mut decoder, _) = Secured::new(BlnProtocol::default(), KEY)
+            .role(Role::Device)
+            .into_split();
+
+        // 请求没有保留字段和响应状态, 线上分别是 4 个 0 和标志位 0
+        let request = Command::try_from(BlnProtocolType::GetPositionRsq).unwrap();
+        let frame = encoder.create_frame(request).unwrap();
+        let mut buf = BytesMut::from(frame.as_ref());
+        let commands = decoder.parse_protocol_frame(&mut buf).unwrap();
+        assert!(decoder.take_diagnostics().is_empty());
+        assert_eq!(
+            BlnProtocolType::try_from(commands[0].clone()),
+            Ok(BlnProtocolType::GetPositionRsq)
+        );
+    }
+
+    #[test]
+    fn test_decoder_discards_scanned_junk() {
+        let mut decoder = BlnCommandDecode::default();
+        let mut buf = BytesMut::from(&[0x01, 0x02, 0x55, 0x03, 0x55][..]);
+
+        assert_eq!(decoder.parse_protocol_frame(&mut buf), None);
+        // 只保留可能是帧头首字节的最后一个字节
+        assert_eq!(buf.as_ref(), &[0x55]);
+
+        let frame = BlnCommandEncoder
+            .create_frame(command(0x33, None, &[]))
+            .unwrap();
+        buf.extend_from_slice(&frame[1..]);
+        assert_eq!(
+            decoder.parse_protocol_frame(&mut buf),
+            Some(vec![command(0x33, Some(0), &[])])
+        );
+    }
+
+    #[test]
+    fn test_decoder_resyncs_after_bcc_failure() {
+        let good = BlnCommandEncoder
+            .create_frame(command(0x93, Some(0x02), &[0x01, 0x02]))
+            .unwrap();
+        let mut bad = good.to_vec();
+        *bad.last_mut().unwrap() ^= 0xFF;
+
+        let mut buf = BytesMut::from(&bad[..]);
+        buf.extend_from_slice(&good);
+        let commands = BlnCommandDecode::default().parse_protocol_frame(&mut buf);
+        assert_eq!(
+            commands,
+            Some(vec![command(0x93, Some(0x02), &[0x01, 0x02])])
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_decoder_reports_checksum_mismatch() {
+        let mut frame = BlnCommandEncoder
+            .create_frame(command(0x33, None, &[]))
+            .unwrap()
+            .to_vec();
+        *frame.last_mut().unwrap() = 0x00;
+
+        let mut decoder = BlnCommandDecode::default();
+        assert_eq!(
+            decoder.parse_protocol_frame(&mut BytesMut::from(&frame[..])),
+            None
+        );
+        assert_eq!(
+            decoder.take_diagnostics(),
+            vec![Diagnostic::ChecksumMismatch {
+                expected: 0x33,
+                actual: 0x00
+            }]
+        );
+        assert!(decoder.take_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn test_decoder_skips_oversize_frame() {
+        let good = BlnCommandEncoder
+            .create_frame(command(0x93, Some(0x02), &[0; 9]))
+            .unwrap();
+        // 长度字段声明了 0x1FFF 字节的负载
+        let mut buf = BytesMut::from(&[0x55, 0xAA, 0x93, 0, 0, 0, 0, 0x1F, 0xFF][..]);
+        buf.extend_from_slice(&good);
+
+        let mut decoder = BlnCommandDecode::default().max_payload_len(16);
+        let commands = decoder.parse_protocol_frame(&mut buf);
+        assert_eq!(commands, Some(vec![command(0x93, Some(0x02), &[0; 9])]));
+        assert_eq!(
+            decoder.take_diagnostics(),
+            vec![Diagnostic::OversizeFrame {
+                payload_len: 0x1FFF,
+                max_payload_len: 16
+            }]
+        );
+    }
+
+    #[test]
+    fn test_decoder_accepts_largest_payload_by_default() {
+        // 长度字段可表示的最大负载, 分段到达
+        let payload = [0xA5; 0x1FFF];
+        let frame = BlnCommandEncoder
+            .create_frame(command(0x31, None, &payload))
+            .unwrap();
+        let mut decoder = BlnCommandDecode::default();
+        let mut buf = BytesMut::from(&[0x01, 0x02][..]);
+        buf.extend_from_slice(&frame[..4096]);
+
+        // 噪声被丢弃, 缓冲区中只保留不完整的帧
+        assert_eq!(decoder.parse_protocol_frame(&mut buf), None);
+        assert_eq!(buf.as_ref(), &frame[..4096]);
+        buf.extend_from_slice(&frame[4096..]);
+        assert_eq!(
+            decoder.parse_protocol_frame(&mut buf),
+            Some(vec![command(0x31, Some(0), &payload)])
+        );
+        assert!(decoder.take_diagnostics().is_empty());
+    }
+
     #[test]
     fn test_bln_error_cause_into_u8() {
         assert_eq!(u8::from(BlnErrorCause::Success), 0x00);
@@ -357,4 +1187,96 @@ mod tests {
         assert_eq!(u8::from(BlnErrorCause::NoValidData), 0x07);
         assert_eq!(u8::from(BlnErrorCause::UnspecifiedError), 0xFF);
     }
+
+    // --- 解码器的属性测试 ---
+
+    // 生成任意可编码的 `Command`: 单字节命令字, 3 位响应状态, 以及任意负载.
+    fn arb_command() -> impl Strategy<Value = Command> {
+        (
+            any::<u8>(),
+            0u8..=0x07,
+            proptest::collection::vec(any::<u8>(), 0..=256),
+        )
+            .prop_map(|(cmd, status, payload)| command(cmd, Some(status), &payload))
+    }
+
+    // 将字节流按给定的块大小依次追加到缓冲区, 每追加一块就解析一次, 返回所有解析出的命令.
+    fn decode_in_chunks(bytes: &[u8], chunks: &[usize]) -> (Vec<Command>, BytesMut) {
+        let mut decoder = BlnCommandDecode::default();
+        let mut buf = BytesMut::new();
+        let mut commands = vec![];
+        let mut rest = bytes;
+        let mut sizes = chunks.iter().cycle();
+        while !rest.is_empty() {
+            let size = (*sizes.next().unwrap()).min(rest.len());
+            buf.extend_from_slice(&rest[..size]);
+            rest = &rest[size..];
+            if let Some(decoded) = decoder.parse_protocol_frame(&mut buf) {
+                commands.extend(decoded);
+            }
+        }
+        (commands, buf)
+    }
+
+    proptest! {
+        #[test]
+        fn prop_encode_decode_identity(cmd in arb_command()) {
+            let expected = cmd.clone();
+            let frame = BlnCommandEncoder.create_frame(cmd).unwrap();
+
+            let mut buf = BytesMut::from(frame.as_ref());
+            let commands = BlnCommandDecode::default().parse_protocol_frame(&mut buf);
+            prop_assert_eq!(commands, Some(vec![expected]));
+            prop_assert!(buf.is_empty());
+        }
+
+        #[test]
+        fn prop_decode_frames_split_at_arbitrary_points(
+            cmds in proptest::collection::vec(arb_command(), 1..8),
+            chunks in proptest::collection::vec(1usize..64, 1..16),
+        ) {
+            let mut stream = vec![];
+            let mut expected = vec![];
+            for cmd in cmds {
+                expected.push(cmd.clone());
+                stream.extend_from_slice(&BlnCommandEncoder.create_frame(cmd).unwrap());
+            }
+
+            let (commands, buf) = decode_in_chunks(&stream, &chunks);
+            prop_assert_eq!(commands, expected);
+            prop_assert!(buf.is_empty());
+        }
+
+        #[test]
+        fn prop_decode_frame_after_noise(
+            noise in proptest::collection::vec(any::<u8>().prop_filter("不含帧头首字节", |b| *b != 0x55), 0..128),
+            cmd in arb_command(),
+            chunks in proptest::collection::vec(1usize..64, 1..16),
+        ) {
+            let expected = cmd.clone();
+            let mut stream = noise;
+            stream.extend_from_slice(&BlnCommandEncoder.create_frame(cmd).unwrap());
+
+            let (commands, _) = decode_in_chunks(&stream, &chunks);
+            prop_assert_eq!(commands, vec![expected]);
+        }
+
+        #[test]
+        fn prop_decode_arbitrary_bytes_is_bounded(
+            bytes in proptest::collection::vec(any::<u8>(), 0..2048),
+            chunks in proptest::collection::vec(1usize..64, 1..16),
+        ) {
+            let (commands, buf) = decode_in_chunks(&bytes, &chunks);
+            let decoded_len: usize = commands
+                .iter()
+                .map(|c| BlnCommandDecode::FRAME_FIXED_LEN + BlnCommandDecode::FRAME_BCC_LEN + c.payload().len())
+                .sum();
+            // 解析出的帧和剩余的缓冲区都不可能超过输入的总长度.
+            prop_assert!(decoded_len + buf.len() <= bytes.len());
+            for cmd in commands {
+                // 任意解析结果都可以安全地尝试转换为 `BlnProtocolType`.
+                let _ = BlnProtocolType::try_from(cmd);
+            }
+        }
+    }
 }
@@ -0,0 +1,447 @@
+use thiserror::Error;
+use tracing::debug;
+
+use crate::protocol::types::{BlnAxis, BlnProtocolType};
+
+/// 单个轴的软限位, 目标位置必须落在 `[min, max]` 内.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct AxisLimits {
+    /// 允许的最小位置.
+    min: f32,
+    /// 允许的最大位置.
+    max: f32,
+}
+
+impl AxisLimits {
+    /// 创建一个 `[min, max]` 的软限位.
+    ///
+    /// 边界为 NaN 或 `min > max` 时返回 [`ValidationError::InvalidLimits`].
+    pub fn new(min: f32, max: f32) -> Result<Self, ValidationError> {
+        // NaN 与任何值比较都为 false, 因此这里同时拒绝了 NaN 边界
+        if min <= max {
+            Ok(Self { min, max })
+        } else {
+            Err(ValidationError::InvalidLimits { min, max })
+        }
+    }
+
+    /// 允许的最小位置.
+    pub fn min(&self) -> f32 {
+        self.min
+    }
+
+    /// 允许的最大位置.
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+
+    /// `value` 是否落在软限位内.
+    pub fn contains(&self, value: f32) -> bool {
+        (self.min..=self.max).contains(&value)
+    }
+}
+
+/// 命令未通过发送前校验的原因.
+#[derive(Error, Debug, PartialEq, Clone, Copy)]
+pub enum ValidationError {
+    #[error("参数不是有限值: {0}")]
+    NonFinite(f32),
+    #[error("{axis:?} 轴目标 {value} 超出软限位 [{min}, {max}]")]
+    OutOfLimits {
+        axis: BlnAxis,
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+    #[error("{axis:?} 轴单步位移 {step} 超过上限 {max}")]
+    StepTooLarge { axis: BlnAxis, step: f32, max: f32 },
+    #[error("速度 {velocity} 超过上限 {max}")]
+    VelocityTooHigh { velocity: f32, max: f32 },
+    #[error("当前位置未知, 无法检查移动的步长或目标")]
+    PositionUnknown,
+    #[error("软限位 [{min}, {max}] 无效")]
+    InvalidLimits { min: f32, max: f32 },
+    #[error("上限 {0} 无效, 必须是非负的有限值")]
+    InvalidMaximum(f32),
+}
+
+/// `CommandValidator` 在命令发出前对其参数进行校验.
+///
+/// 设备对越界参数只会回复 `ErrorRsp(InvalidArgument)`, 甚至可能接受 NaN 或无穷大.
+/// 该结构体在本地拒绝这些命令, 校验的内容包括:
+/// * 所有 `f32` 参数必须是有限值;
+/// * `SetPositionRsq` 和 `MoveRelativeRsq` 的目标位置必须落在各轴的软限位内;
+/// * 单次移动在每个轴上的位移不得超过 `max_step`;
+/// * `JogStartRsq` 和 `SetVelocityRsq` 的速度绝对值不得超过 `max_velocity`.
+///
+/// 绝对移动的位移和相对移动的目标位置依赖于当前位置, 需要通过 [`CommandValidator::observe`]
+/// 从设备响应中获取. 配置了对应的检查而当前位置未知时, 移动以 [`ValidationError::PositionUnknown`]
+/// 被拒绝, 调用方应先查询位置 (见 [`CommandValidator::requires_position`]).
+///
+/// 开启 dry-run 模式后, 客户端只记录将要发送的帧, 不会真正发送. dry-run 模式下不会向设备查询位置,
+/// 因此当前位置未知时跳过依赖位置的检查, 而不是拒绝移动.
+#[derive(Debug, Default, Clone)]
+pub struct CommandValidator {
+    /// X 轴软限位, `None` 表示不限制.
+    x_limits: Option<AxisLimits>,
+    /// Y 轴软限位, `None` 表示不限制.
+    y_limits: Option<AxisLimits>,
+    /// 单次移动在每个轴上允许的最大位移.
+    max_step: Option<f32>,
+    /// 点动和设置速度时允许的最大速度绝对值.
+    max_velocity: Option<f32>,
+    /// 是否只记录而不发送帧.
+    dry_run: bool,
+    /// 最近一次从设备响应中得知的位置.
+    position: Option<(f32, f32)>,
+}
+
+impl CommandValidator {
+    /// 设置一个轴的软限位. `BlnAxis::Other` 没有软限位, 设置会被忽略.
+    pub fn limits(mut self, axis: BlnAxis, limits: AxisLimits) -> Self {
+        match axis {
+            BlnAxis::X => self.x_limits = Some(limits),
+            BlnAxis::Y => self.y_limits = Some(limits),
+            BlnAxis::Other(_) => {}
+        }
+        self
+    }
+
+    /// 设置单次移动在每个轴上允许的最大位移.
+    ///
+    /// 上限为负数、NaN 或无穷大时返回 [`ValidationError::InvalidMaximum`].
+    pub fn max_step(mut self, max_step: f32) -> Result<Self, ValidationError> {
+        self.max_step = Some(maximum(max_step)?);
+        Ok(self)
+    }
+
+    /// 设置点动和设置速度时允许的最大速度绝对值.
+    ///
+    /// 上限为负数、NaN 或无穷大时返回 [`ValidationError::InvalidMaximum`].
+    pub fn max_velocity(mut self, max_velocity: f32) -> Result<Self, ValidationError> {
+        self.max_velocity = Some(maximum(max_velocity)?);
+        Ok(self)
+    }
+
+    /// 设置是否开启 dry-run 模式.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// 是否处于 dry-run 模式.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// 返回最近一次从设备响应中得知的位置, 尚未得知时返回 `None`.
+    pub fn position(&self) -> Option<(f32, f32)> {
+        self.position
+    }
+
+    /// 校验 `message` 是否需要先得知当前位置: 配置了步长上限的绝对移动,
+    /// 以及配置了软限位的相对移动, 在当前位置未知时都无法校验.
+    pub fn requires_position(&self, message: &BlnProtocolType) -> bool {
+        if self.position.is_some() {
+            return false;
+        }
+        match message {
+            BlnProtocolType::SetPositionRsq(..) => self.max_step.is_some(),
+            BlnProtocolType::MoveRelativeRsq(..) => {
+                self.x_limits.is_some() || self.y_limits.is_some()
+            }
+            _ => false,
+        }
+    }
+
+    /// 根据设备的响应更新当前位置, 与位置无关的响应被忽略.
+    pub fn observe(&mut self, response: &BlnProtocolType) {
+        let position = match *response {
+            BlnProtocolType::GetPositionRsp(x, y, _)
+            | BlnProtocolType::PositionReached(x, y)
+            | BlnProtocolType::Stopped(x, y)
+            | BlnProtocolType::HomeReached(x, y)
+            | BlnProtocolType::MoveRelativeReached(x, y)
+            | BlnProtocolType::JogStopped(x, y) => (x, y),
+            _ => return,
+        };
+        if self.position != Some(position) {
+            debug!(?position, "校验器记录的位置变化");
+            self.position = Some(position);
+        }
+    }
+
+    /// 校验一条将要发出的消息.
+    ///
+    /// # 返回
+    /// * `Ok(())`: 消息可以发送, 不携带运动参数的消息总是通过.
+    /// * `Err(ValidationError)`: 第一个未通过的检查.
+    pub fn validate(&self, message: &BlnProtocolType) -> Result<(), ValidationError> {
+        match *message {
+            BlnProtocolType::SetPositionRsq(x, y) => {
+                finite(x)?;
+                finite(y)?;
+                self.check_position_known(message)?;
+                if let Some((px, py)) = self.position {
+                    self.check_step(BlnAxis::X, x - px)?;
+                    self.check_step(BlnAxis::Y, y - py)?;
+                }
+                self.check_target(x, y)
+            }
+            BlnProtocolType::MoveRelativeRsq(dx, dy) => {
+                finite(dx)?;
+                finite(dy)?;
+                self.check_step(BlnAxis::X, dx)?;
+                self.check_step(BlnAxis::Y, dy)?;
+                self.check_position_known(message)?;
+                match self.position {
+                    Some((px, py)) => self.check_target(px + dx, py + dy),
+                    None => Ok(()),
+                }
+            }
+            BlnProtocolType::JogStartRsq(_, velocity)
+            | BlnProtocolType::SetVelocityRsq(velocity) => {
+                finite(velocity)?;
+                match self.max_velocity {
+                    Some(max) if velocity.abs() > max => {
+                        Err(ValidationError::VelocityTooHigh { velocity, max })
+                    }
+                    _ => Ok(()),
+                }
+            }
+            BlnProtocolType::WriteConfigRsq(_, value) => finite(value),
+            _ => Ok(()),
+        }
+    }
+
+    /// 检查 `message` 需要的当前位置是否已知. dry-run 模式下跳过依赖位置的检查.
+    fn check_position_known(&self, message: &BlnProtocolType) -> Result<(), ValidationError> {
+        if !self.requires_position(message) {
+            return Ok(());
+        }
+        if self.dry_run {
+            debug!(?message, "dry-run: 当前位置未知, 跳过依赖位置的检查");
+            return Ok(());
+        }
+        Err(ValidationError::PositionUnknown)
+    }
+
+    /// 检查目标位置是否落在两个轴的软限位内.
+    fn check_target(&self, x: f32, y: f32) -> Result<(), ValidationError> {
+        for (axis, limits, value) in [
+            (BlnAxis::X, self.x_limits, x),
+            (BlnAxis::Y, self.y_limits, y),
+        ] {
+            if let Some(limits) = limits
+                && !limits.contains(value)
+            {
+                return Err(ValidationError::OutOfLimits {
+                    axis,
+                    value,
+                    min: limits.min,
+                    max: limits.max,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查一个轴上的位移是否超过 `max_step`.
+    fn check_step(&self, axis: BlnAxis, step: f32) -> Result<(), ValidationError> {
+        match self.max_step {
+            Some(max) if step.abs() > max => Err(ValidationError::StepTooLarge { axis, step, max }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 拒绝 NaN 和无穷大.
+fn finite(value: f32) -> Result<(), ValidationError> {
+    if value.is_finite() {
+        Ok(())
+    } else {
+        Err(ValidationError::NonFinite(value))
+    }
+}
+
+/// 上限必须是非负的有限值: NaN 上限会让检查失效, 负数上限会拒绝所有命令.
+fn maximum(value: f32) -> Result<f32, ValidationError> {
+    if value.is_finite() && value >= 0.0 {
+        Ok(value)
+    } else {
+        Err(ValidationError::InvalidMaximum(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::types::{BlnConfigId, BlnDeviceStatus};
+
+    fn validator() -> CommandValidator {
+        CommandValidator::default()
+            .limits(BlnAxis::X, AxisLimits::new(-10.0, 10.0).unwrap())
+            .limits(BlnAxis::Y, AxisLimits::new(0.0, 5.0).unwrap())
+            .max_step(4.0)
+            .unwrap()
+            .max_velocity(2.0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_rejects_non_finite() {
+        let validator = CommandValidator::default();
+        let result = validator.validate(&BlnProtocolType::SetPositionRsq(f32::NAN, 0.0));
+        assert!(matches!(result, Err(ValidationError::NonFinite(v)) if v.is_nan()));
+        assert_eq!(
+            validator.validate(&BlnProtocolType::SetVelocityRsq(f32::INFINITY)),
+            Err(ValidationError::NonFinite(f32::INFINITY))
+        );
+        assert_eq!(
+            validator.validate(&BlnProtocolType::WriteConfigRsq(
+                BlnConfigId::Speed,
+                f32::NEG_INFINITY
+            )),
+            Err(ValidationError::NonFinite(f32::NEG_INFINITY))
+        );
+        assert_eq!(
+            validator.validate(&BlnProtocolType::SetPositionRsq(1e30, -1e30)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_soft_limits() {
+        let mut validator = CommandValidator::default()
+            .limits(BlnAxis::X, AxisLimits::new(-10.0, 10.0).unwrap())
+            .limits(BlnAxis::Y, AxisLimits::new(0.0, 5.0).unwrap());
+        assert_eq!(
+            validator.validate(&BlnProtocolType::SetPositionRsq(3.0, 2.0)),
+            Ok(())
+        );
+        assert_eq!(
+            validator.validate(&BlnProtocolType::SetPositionRsq(3.0, 6.0)),
+            Err(ValidationError::OutOfLimits {
+                axis: BlnAxis::Y,
+                value: 6.0,
+                min: 0.0,
+                max: 5.0
+            })
+        );
+        // 位置未知时, 无法检查相对移动的目标.
+        let relative = BlnProtocolType::MoveRelativeRsq(-4.0, 0.0);
+        assert!(validator.requires_position(&relative));
+        assert_eq!(
+            validator.validate(&relative),
+            Err(ValidationError::PositionUnknown)
+        );
+        validator.observe(&BlnProtocolType::HomeReached(0.0, 0.0));
+        assert_eq!(validator.validate(&relative), Ok(()));
+    }
+
+    #[test]
+    fn test_axis_limits_must_be_ordered() {
+        assert_eq!(
+            AxisLimits::new(1.0, -1.0),
+            Err(ValidationError::InvalidLimits {
+                min: 1.0,
+                max: -1.0
+            })
+        );
+        assert!(AxisLimits::new(f32::NAN, 1.0).is_err());
+        assert!(AxisLimits::new(0.0, f32::NAN).is_err());
+        let limits = AxisLimits::new(-1.0, 1.0).unwrap();
+        assert!(limits.contains(1.0) && !limits.contains(1.5));
+    }
+
+    #[test]
+    fn test_maximums_must_be_non_negative_and_finite() {
+        for value in [-1.0, f32::NAN, f32::INFINITY] {
+            let step = CommandValidator::default().max_step(value);
+            assert!(
+                matches!(step, Err(ValidationError::InvalidMaximum(v)) if v.to_bits() == value.to_bits())
+            );
+            let velocity = CommandValidator::default().max_velocity(value);
+            assert!(
+                matches!(velocity, Err(ValidationError::InvalidMaximum(v)) if v.to_bits() == value.to_bits())
+            );
+        }
+        let validator = CommandValidator::default().max_step(0.0).unwrap();
+        assert!(
+            validator
+                .validate(&BlnProtocolType::MoveRelativeRsq(0.0, 0.0))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_dry_run_skips_position_checks() {
+        let validator = validator().dry_run(true);
+        assert_eq!(
+            validator.validate(&BlnProtocolType::SetPositionRsq(9.0, 0.0)),
+            Ok(())
+        );
+        // 不依赖位置的检查仍然生效
+        assert!(matches!(
+            validator.validate(&BlnProtocolType::SetPositionRsq(11.0, 0.0)),
+            Err(ValidationError::OutOfLimits { .. })
+        ));
+    }
+
+    #[test]
+    fn test_step_uses_observed_position() {
+        let mut validator = validator();
+        // 位置未知时, 连接后的第一次绝对移动也不能跳过步长检查.
+        assert_eq!(
+            validator.validate(&BlnProtocolType::SetPositionRsq(9.0, 0.0)),
+            Err(ValidationError::PositionUnknown)
+        );
+
+        validator.observe(&BlnProtocolType::GetPositionRsp(
+            0.0,
+            1.0,
+            BlnDeviceStatus::empty(),
+        ));
+        assert_eq!(validator.position(), Some((0.0, 1.0)));
+        assert_eq!(
+            validator.validate(&BlnProtocolType::SetPositionRsq(9.0, 1.0)),
+            Err(ValidationError::StepTooLarge {
+                axis: BlnAxis::X,
+                step: 9.0,
+                max: 4.0
+            })
+        );
+
+        validator.observe(&BlnProtocolType::PositionReached(8.0, 1.0));
+        assert_eq!(
+            validator.validate(&BlnProtocolType::SetPositionRsq(9.0, 1.0)),
+            Ok(())
+        );
+        assert_eq!(
+            validator.validate(&BlnProtocolType::MoveRelativeRsq(3.0, 0.0)),
+            Err(ValidationError::OutOfLimits {
+                axis: BlnAxis::X,
+                value: 11.0,
+                min: -10.0,
+                max: 10.0
+            })
+        );
+    }
+
+    #[test]
+    fn test_velocity_limit() {
+        let validator = validator();
+        assert_eq!(
+            validator.validate(&BlnProtocolType::JogStartRsq(BlnAxis::X, -1.5)),
+            Ok(())
+        );
+        assert_eq!(
+            validator.validate(&BlnProtocolType::JogStartRsq(BlnAxis::Y, -2.5)),
+            Err(ValidationError::VelocityTooHigh {
+                velocity: -2.5,
+                max: 2.0
+            })
+        );
+        assert_eq!(validator.validate(&BlnProtocolType::HomeRsq), Ok(()));
+    }
+}
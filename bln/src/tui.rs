@@ -1,24 +1,193 @@
 mod log_view;
 
-use ratatui::{Frame, prelude::Rect, style::Modifier, widgets::Borders};
+use std::time::Instant;
 
-use ui::{theme::Theme, traits::RenderUi};
+use protocol::{
+    dynamic::{DynMessage, ProtocolSwitch},
+    types::{Direction, FrameRecord, MessageRecord},
+    utils::{time_of_day, to_hex},
+};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout},
+    prelude::Rect,
+    style::{Modifier, Stylize},
+    text::Line,
+    widgets::Borders,
+};
+use tracing::info;
 
-use crate::tui::log_view::BlnLogView;
+use ui::{
+    theme::Theme,
+    traits::{HandleMessage, RenderUi},
+};
+
+use crate::{
+    protocol::{
+        BlnProtocol,
+        motion::{MoveState, MoveTracker},
+        types::{BlnDeviceStatus, BlnProtocolType},
+    },
+    tui::log_view::BlnLogView,
+};
 
 pub struct BlnTui<'a> {
     log_view: Option<BlnLogView<'a>>,
+    /// 跟踪设备当前移动事务的状态机.
+    move_tracker: MoveTracker,
+    /// 使用动态协议时的协议切换句柄, 用于判断当前协议并格式化非 BLN 的消息.
+    protocol_switch: Option<ProtocolSwitch>,
     theme: Theme,
 }
 
+impl<'a> BlnTui<'a> {
+    /// 返回当前移动事务的状态.
+    pub fn move_state(&self) -> MoveState {
+        self.move_tracker.state()
+    }
+
+    /// 设置动态协议的切换句柄, 状态栏会显示当前协议.
+    pub fn protocol_switch(mut self, protocol_switch: ProtocolSwitch) -> Self {
+        self.protocol_switch = Some(protocol_switch);
+        self
+    }
+
+    /// 将一条收发记录连同解码结果和十六进制原文写入日志视图.
+    fn log_record<M>(&mut self, record: &FrameRecord<M>, decoded: String) {
+        if let Some(ref mut log_view) = self.log_view {
+            log_view.push_line(format!(
+                "{} {} {} {decoded} | {}",
+                time_of_day(record.timestamp),
+                record.connection_id,
+                record.direction,
+                to_hex(&record.raw)
+            ));
+        }
+    }
+
+    /// 根据收发的消息更新移动状态.
+    ///
+    /// 界面只观察移动状态: 发出的移动请求开始一次新的事务, 收到的响应推进该事务,
+    /// 排队的后续移动由发送方负责发出.
+    fn observe(&mut self, direction: Direction, message: &BlnProtocolType) {
+        let now = Instant::now();
+        match (direction, *message) {
+            (Direction::Tx, BlnProtocolType::SetPositionRsq(x, y)) => {
+                if let Err(e) = self.move_tracker.request_move(x, y, now) {
+                    info!("[BlnTui] Untracked move request: {}", e);
+                }
+            }
+            (Direction::Tx, _) => {}
+            (Direction::Rx, _) => {
+                self.move_tracker.handle_response(message, now);
+            }
+        }
+    }
+
+    /// 将移动状态格式化为一行带颜色的状态栏.
+    fn move_state_line(&self) -> Line<'static> {
+        let (text, color) = match self.move_tracker.state() {
+            MoveState::Idle => ("空闲".to_string(), self.theme.comment),
+            MoveState::Sent { target } => (format!("已发送 -> {target:?}"), self.theme.yellow),
+            MoveState::Acknowledged { target } => {
+                (format!("移动中 -> {target:?}"), self.theme.blue)
+            }
+            MoveState::Reached { position, .. } => {
+                (format!("已到达 {position:?}"), self.theme.green)
+            }
+            MoveState::Failed { target, cause } => {
+                (format!("失败 {target:?}: {cause:?}"), self.theme.red)
+            }
+            MoveState::Stopped { target } => (format!("已停止 {target:?}"), self.theme.orange),
+            MoveState::TimedOut { target } => (format!("超时 {target:?}"), self.theme.orange),
+        };
+        let status = self.move_tracker.device_status();
+        let device = match status {
+            Some(status) if status.is_empty() => " | device: -".to_string(),
+            Some(status) => format!(" | device: {status}"),
+            None => String::new(),
+        };
+        // 设备报告故障或急停时, 无论移动状态如何都以红色提示
+        let color = if status.is_some_and(|status| {
+            status.intersects(BlnDeviceStatus::FAULT | BlnDeviceStatus::EMERGENCY_STOP)
+        }) {
+            self.theme.red
+        } else {
+            color
+        };
+        let protocol = self
+            .protocol_switch
+            .as_ref()
+            .map(|switch| match switch.detection() {
+                Some(detection) => format!(
+                    " | protocol: {} (auto {:.0}%)",
+                    detection.name,
+                    detection.confidence * 100.0
+                ),
+                None => format!(" | protocol: {}", switch.name()),
+            })
+            .unwrap_or_default();
+        Line::from(format!(" move: {text}{device}{protocol}"))
+            .fg(color)
+            .bg(self.theme.bg_dark)
+    }
+}
+
 impl<'a> RenderUi for BlnTui<'a> {
     fn render(&self, frame: &mut Frame, rect: Rect) {
+        let [status_area, log_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(rect);
+        frame.render_widget(self.move_state_line(), status_area);
         if let Some(ref log_view) = self.log_view {
-            log_view.render(frame, rect);
+            log_view.render(frame, log_area);
         }
     }
 }
 
+impl<'a> HandleMessage<MessageRecord<BlnProtocolType>> for BlnTui<'a> {
+    /// 根据收发的 `BlnProtocolType` 更新移动状态, 并将解码结果 (或转换错误) 和原始帧写入日志视图.
+    fn handle_message(&mut self, record: MessageRecord<BlnProtocolType>) {
+        let decoded = match record.message {
+            Ok(ref bln) => {
+                self.observe(record.direction, bln);
+                format!("{bln:?}")
+            }
+            Err(ref e) => format!("{e:?}"),
+        };
+        self.log_record(&record, decoded);
+    }
+
+    fn on_tick(&mut self) {
+        self.move_tracker.poll_timeout(Instant::now());
+    }
+}
+
+impl<'a> HandleMessage<MessageRecord<DynMessage>> for BlnTui<'a> {
+    /// 当前协议为 BLN 时按 `BlnProtocolType` 处理, 否则使用当前协议格式化后写入日志视图.
+    fn handle_message(&mut self, record: MessageRecord<DynMessage>) {
+        let Some(switch) = self
+            .protocol_switch
+            .clone()
+            .filter(|switch| !switch.current().as_any().is::<BlnProtocol>())
+        else {
+            let record = record
+                .map(|message| message.and_then(|DynMessage(c)| BlnProtocolType::try_from(c)));
+            return self.handle_message(record);
+        };
+        let decoded = match record.message {
+            Ok(DynMessage(ref command)) => switch
+                .describe(command)
+                .unwrap_or_else(|e| format!("{e:?}")),
+            Err(ref e) => format!("{e:?}"),
+        };
+        self.log_record(&record, decoded);
+    }
+
+    fn on_tick(&mut self) {
+        self.move_tracker.poll_timeout(Instant::now());
+    }
+}
+
 impl<'a> Default for BlnTui<'a> {
     fn default() -> Self {
         let theme = Theme::default();
@@ -33,7 +202,47 @@ impl<'a> Default for BlnTui<'a> {
                     .highlight_modifier(Modifier::ITALIC)
                     .borders(Borders::NONE),
             ),
+            move_tracker: MoveTracker::default(),
+            protocol_switch: None,
             theme,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocol::types::{ConnectionId, FrameRecord};
+
+    // 构造一条不带原始帧的收发记录.
+    fn record(direction: Direction, message: BlnProtocolType) -> MessageRecord<BlnProtocolType> {
+        let record = FrameRecord::tx(ConnectionId::next(), Ok(message), Vec::new());
+        FrameRecord {
+            direction,
+            ..record
+        }
+    }
+
+    #[test]
+    fn test_tracks_sent_move_until_reached() {
+        let mut tui = BlnTui::default();
+        tui.handle_message(record(
+            Direction::Tx,
+            BlnProtocolType::SetPositionRsq(1.0, 2.0),
+        ));
+        assert_eq!(tui.move_state(), MoveState::Sent { target: (1.0, 2.0) });
+
+        tui.handle_message(record(Direction::Rx, BlnProtocolType::SetPositionRsp));
+        tui.handle_message(record(
+            Direction::Rx,
+            BlnProtocolType::PositionReached(1.0, 2.0),
+        ));
+        assert_eq!(
+            tui.move_state(),
+            MoveState::Reached {
+                target: (1.0, 2.0),
+                position: (1.0, 2.0)
+            }
+        );
+    }
+}
@@ -34,7 +34,7 @@ pub struct BlnLogView<'a> {
     highlight_modifier: Modifier,
     /// 选中项的符号前缀.
     highlight_symbols: &'a str,
-    /// 列表的标题.
+    /// 列表的标题, 渲染为边框的标题.
     title: &'a str,
     /// 列表的边框样式.
     borders: Borders,
@@ -72,17 +72,14 @@ impl<'a> BlnLogView<'a> {
     /// 但在渲染时会反转显示, 所以新行在 UI 上会出现在底部.
     ///
     /// # 参数
-    /// * `line` - 任何实现了 `Debug` trait 的类型, 其 `Debug` 输出将被格式化为一行.
-    pub fn add_line<T>(&mut self, line: T)
-    where
-        T: Debug,
-    {
+    /// * `line` - 任何可以转换为 `Line` 的类型, 如已经格式化好的 `String`.
+    pub fn push_line(&mut self, line: impl Into<Line<'a>>) {
         // 如果缓冲区已满, 移除最旧的条目. 由于新条目插入到开头, 最旧的在末尾.
         if self.buf.len() == self.buffer_capacity {
             self.buf.pop();
         }
         // 将新行插入到缓冲区开头, 这样在反转显示时它会出现在列表底部.
-        self.buf.insert(0, Line::from(format!("{line:?}")));
+        self.buf.insert(0, line.into());
 
         // 自动滚动到最新条目, 确保新行在列表底部可见.
         // `saturating_sub` 避免了在缓冲区为空或只有一项时出现负数.
@@ -125,12 +122,6 @@ impl<'a> BlnLogView<'a> {
         self
     }
 
-    /// 设置列表的标题.
-    pub fn title(mut self, title: &'a str) -> Self {
-        self.title = title;
-        self
-    }
-
     /// 设置列表的边框样式.
     pub fn borders(mut self, borders: Borders) -> Self {
         self.borders = borders;
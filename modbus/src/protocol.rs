@@ -0,0 +1,32 @@
+mod conversions;
+pub mod types;
+use protocol::traits::{MessageProtocol, ProtocolSplit};
+
+use crate::protocol::types::{ModbusAdu, ModbusDecode, ModbusEncoder};
+
+/// `ModbusProtocol` 是 Modbus TCP 协议的处理器.
+#[derive(Default)]
+pub struct ModbusProtocol {
+    /// 协议的编码器实例.
+    encode: ModbusEncoder,
+    /// 协议的解码器实例.
+    decode: ModbusDecode,
+}
+
+impl ProtocolSplit for ModbusProtocol {
+    /// 定义 `ModbusProtocol` 的编码器类型为 `ModbusEncoder`.
+    type Encoder = ModbusEncoder;
+    /// 定义 `ModbusProtocol` 的解码器类型为 `ModbusDecode`.
+    type Decode = ModbusDecode;
+
+    /// 实现 `into_split`, 消耗 `ModbusProtocol` 实例,
+    /// 并返回其内部持有的编码器和解码器.
+    fn into_split(self) -> (Self::Decode, Self::Encoder) {
+        (self.decode, self.encode)
+    }
+}
+
+impl MessageProtocol for ModbusProtocol {
+    /// `ModbusProtocol` 的类型化消息为 `ModbusAdu`.
+    type Message = ModbusAdu;
+}
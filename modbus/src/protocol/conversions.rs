@@ -0,0 +1,104 @@
+use protocol::types::{Command, ProtocolError};
+
+use crate::protocol::types::{MBAP_LEN, ModbusAdu};
+
+// 将通用的 `Command` 解析为 `ModbusAdu`. 事务标识和单元标识来自保留字段中的 MBAP 报文头.
+impl TryFrom<Command> for ModbusAdu {
+    type Error = ProtocolError;
+
+    fn try_from(value: Command) -> Result<Self, Self::Error> {
+        let cmd_id = value.cmd_id();
+        let [t0, t1, _, _, _, _, unit_id] = *value.reserved() else {
+            return Err(ProtocolError::ReservedLength {
+                cmd_id,
+                expected: MBAP_LEN,
+                actual: value.reserved().len(),
+            });
+        };
+        let adu = Self {
+            transaction_id: u16::from_be_bytes([t0, t1]),
+            unit_id,
+            function: cmd_id,
+            data: value.payload_bytes().clone(),
+        };
+        // 异常响应只携带 1 字节的异常码
+        if adu.is_exception() && adu.data.len() != 1 {
+            return Err(ProtocolError::PayloadLength {
+                cmd_id,
+                expected: 1,
+                actual: adu.data.len(),
+            });
+        }
+        Ok(adu)
+    }
+}
+
+// 将 `ModbusAdu` 转换为通用的 `Command`. 保留字段中的长度会在编码时重新计算.
+impl TryFrom<ModbusAdu> for Command {
+    type Error = ProtocolError;
+
+    fn try_from(value: ModbusAdu) -> Result<Self, Self::Error> {
+        let mut header = [0; MBAP_LEN];
+        header[..2].copy_from_slice(&value.transaction_id.to_be_bytes());
+        header[MBAP_LEN - 1] = value.unit_id;
+        Ok(Command::new(value.function)
+            .with_reserved(header.to_vec())
+            .with_payload(value.data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+    use protocol::traits::{FrameGenerator, ParseProtocol};
+
+    use super::*;
+    use crate::protocol::types::{ModbusDecode, ModbusEncoder};
+
+    #[test]
+    fn test_adu_round_trip() {
+        let adu = ModbusAdu {
+            transaction_id: 0x1234,
+            unit_id: 0x11,
+            function: 0x03,
+            data: Bytes::from_static(&[0x02, 0x00, 0x2A]),
+        };
+        let frame = ModbusEncoder
+            .create_frame(adu.clone().try_into().unwrap())
+            .unwrap();
+
+        let mut buf = BytesMut::from(frame.as_ref());
+        let command = ModbusDecode::default()
+            .parse_protocol_frame(&mut buf)
+            .unwrap()
+            .remove(0);
+        assert_eq!(ModbusAdu::try_from(command), Ok(adu));
+    }
+
+    #[test]
+    fn test_exception_requires_one_byte_code() {
+        let command = Command::new(0x83)
+            .with_reserved(vec![0; MBAP_LEN])
+            .with_payload(vec![0x02, 0x00]);
+        assert_eq!(
+            ModbusAdu::try_from(command),
+            Err(ProtocolError::PayloadLength {
+                cmd_id: 0x83,
+                expected: 1,
+                actual: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_command_requires_mbap_header() {
+        assert_eq!(
+            ModbusAdu::try_from(Command::new(0x03)),
+            Err(ProtocolError::ReservedLength {
+                cmd_id: 0x03,
+                expected: MBAP_LEN,
+                actual: 0
+            })
+        );
+    }
+}
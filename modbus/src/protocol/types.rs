@@ -0,0 +1,200 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use protocol::{
+    traits::{FrameGenerator, ParseProtocol},
+    types::{Command, Diagnostic, ProtocolError},
+};
+use tracing::info;
+
+/// 一个 Modbus TCP 应用数据单元 (ADU).
+///
+/// 功能码中的最高位表示异常响应, 此时 `data` 为 1 字节的异常码.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModbusAdu {
+    /// 事务标识, 用于匹配请求和响应.
+    pub transaction_id: u16,
+    /// 单元标识 (从站地址).
+    pub unit_id: u8,
+    /// 功能码.
+    pub function: u8,
+    /// 功能码之后的数据.
+    pub data: Bytes,
+}
+
+impl ModbusAdu {
+    /// 功能码中表示异常响应的位.
+    pub const EXCEPTION_FLAG: u8 = 0x80;
+
+    /// 是否为异常响应.
+    pub fn is_exception(&self) -> bool {
+        self.function & Self::EXCEPTION_FLAG != 0
+    }
+}
+
+/// MBAP 报文头的长度.
+pub(crate) const MBAP_LEN: usize = 7;
+
+/// `ModbusEncoder` 将 `Command` 编码为 Modbus TCP 帧.
+///
+/// `Command` 的命令字为功能码, 负载为功能码之后的数据.
+/// 保留字段为 7 字节的 MBAP 报文头, 编码时使用其中的事务标识和单元标识, 长度字段会被重新计算.
+/// 没有保留字段时事务标识为 0, 单元标识为 1.
+#[derive(Default)]
+pub struct ModbusEncoder;
+
+impl ModbusEncoder {
+    /// 没有 MBAP 报文头时使用的默认单元标识.
+    const DEFAULT_UNIT_ID: u8 = 0x01;
+}
+
+impl FrameGenerator for ModbusEncoder {
+    fn create_frame(&self, command: Command) -> Result<Bytes, ProtocolError> {
+        let data = command.payload();
+        // 长度字段包含单元标识和功能码
+        let len = data.len() + 2;
+        if len > ModbusDecode::MAX_LENGTH_FIELD {
+            return Err(ProtocolError::PayloadTooLarge {
+                len,
+                max: ModbusDecode::MAX_LENGTH_FIELD,
+            });
+        }
+        let (transaction_id, unit_id) = match command.reserved() {
+            [t0, t1, _, _, _, _, unit] => (u16::from_be_bytes([*t0, *t1]), *unit),
+            _ => (0, Self::DEFAULT_UNIT_ID),
+        };
+
+        let mut buf = BytesMut::with_capacity(MBAP_LEN + 1 + data.len());
+        buf.put_u16(transaction_id);
+        buf.put_u16(ModbusDecode::PROTOCOL_ID);
+        buf.put_u16(len as u16);
+        buf.put_u8(unit_id);
+        buf.put_u8(command.cmd_id());
+        buf.put_slice(data);
+
+        info!("Modbus Frame Created: {:02X?}", buf.as_ref());
+        Ok(buf.freeze())
+    }
+}
+
+/// `ModbusDecode` 从字节流中解析 Modbus TCP 帧.
+///
+/// Modbus TCP 没有帧头同步字, 遇到协议标识不为 0 或长度字段不合法的报文头时,
+/// 解码器每次丢弃 1 字节并重新尝试, 以便从错位的数据中恢复.
+#[derive(Default)]
+pub struct ModbusDecode {
+    /// 尚未被取出的诊断事件.
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ModbusDecode {
+    /// Modbus 协议的协议标识.
+    const PROTOCOL_ID: u16 = 0x0000;
+    /// 长度字段允许的最大值 (单元标识 + 最长 253 字节的 PDU).
+    const MAX_LENGTH_FIELD: usize = 254;
+    /// 诊断事件的最大保留数量, 超出后丢弃最旧的事件.
+    const MAX_DIAGNOSTICS: usize = 64;
+
+    /// 记录一个诊断事件.
+    fn report(&mut self, diagnostic: Diagnostic) {
+        info!("{}", diagnostic);
+        if self.diagnostics.len() == Self::MAX_DIAGNOSTICS {
+            self.diagnostics.remove(0);
+        }
+        self.diagnostics.push(diagnostic);
+    }
+}
+
+impl ParseProtocol for ModbusDecode {
+    fn parse_protocol_frame(&mut self, buf: &mut BytesMut) -> Option<Vec<Command>> {
+        let mut commands = Vec::new();
+        // 至少需要完整的报文头和功能码
+        while buf.len() > MBAP_LEN {
+            let protocol_id = u16::from_be_bytes([buf[2], buf[3]]);
+            let len = usize::from(u16::from_be_bytes([buf[4], buf[5]]));
+            if protocol_id != Self::PROTOCOL_ID || !(2..=Self::MAX_LENGTH_FIELD).contains(&len) {
+                if len > Self::MAX_LENGTH_FIELD {
+                    self.report(Diagnostic::OversizeFrame {
+                        payload_len: len,
+                        max_payload_len: Self::MAX_LENGTH_FIELD,
+                    });
+                }
+                buf.advance(1);
+                continue;
+            }
+            let frame_len = MBAP_LEN - 1 + len;
+            if buf.len() < frame_len {
+                break;
+            }
+            let frame = buf.split_to(frame_len).freeze();
+            let function = frame[MBAP_LEN];
+            commands.push(Command::from_frame(
+                frame,
+                function,
+                None,
+                0..MBAP_LEN,
+                MBAP_LEN + 1..frame_len,
+            ));
+        }
+        (!commands.is_empty()).then_some(commands)
+    }
+
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 读保持寄存器请求: 事务 0x0102, 单元 0x11, 功能码 0x03, 起始地址 0x006B, 数量 3.
+    const READ_REQUEST: [u8; 12] = [
+        0x01, 0x02, 0x00, 0x00, 0x00, 0x06, 0x11, 0x03, 0x00, 0x6B, 0x00, 0x03,
+    ];
+
+    #[test]
+    fn test_decoder_reads_frame() {
+        let mut buf = BytesMut::from(&READ_REQUEST[..]);
+        let commands = ModbusDecode::default()
+            .parse_protocol_frame(&mut buf)
+            .unwrap();
+
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].cmd_id(), 0x03);
+        assert_eq!(commands[0].payload(), &[0x00, 0x6B, 0x00, 0x03]);
+        assert_eq!(commands[0].frame(), Some(&READ_REQUEST[..]));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_decoder_waits_for_complete_frame() {
+        let mut decoder = ModbusDecode::default();
+        let mut buf = BytesMut::from(&READ_REQUEST[..10]);
+        assert_eq!(decoder.parse_protocol_frame(&mut buf), None);
+
+        buf.extend_from_slice(&READ_REQUEST[10..]);
+        assert_eq!(decoder.parse_protocol_frame(&mut buf).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_decoder_skips_invalid_header() {
+        let mut decoder = ModbusDecode::default();
+        let mut buf = BytesMut::from(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF][..]);
+        buf.extend_from_slice(&READ_REQUEST);
+
+        let commands = decoder.parse_protocol_frame(&mut buf).unwrap();
+        assert_eq!(commands[0].frame(), Some(&READ_REQUEST[..]));
+        assert!(!decoder.take_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn test_encoder_round_trip() {
+        let mut buf = BytesMut::from(&READ_REQUEST[..]);
+        let command = ModbusDecode::default()
+            .parse_protocol_frame(&mut buf)
+            .unwrap()
+            .remove(0);
+
+        let frame = ModbusEncoder.create_frame(command).unwrap();
+        assert_eq!(frame.as_ref(), &READ_REQUEST);
+    }
+}
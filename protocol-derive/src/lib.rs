@@ -0,0 +1,503 @@
+//! `#[derive(ProtocolMessage)]` 派生宏, 为协议的消息枚举生成编解码.
+//!
+//! 每个变体使用 `#[cmd(...)]` 声明命令字和响应状态, 字段按声明顺序依次编码到负载中:
+//!
+//! ```ignore
+//! #[derive(ProtocolMessage)]
+//! #[protocol(status = BlnResponseStatus)]
+//! enum Message {
+//!     #[cmd(0x33, status = Unused)]
+//!     GetPositionRsq,
+//!     #[cmd(0x93, status = OkWithData)]
+//!     GetPositionRsp(f32, #[field(be)] f32, u8),
+//!     #[cmd(0x00, status = Error, any_cmd)]
+//!     ErrorRsp(#[field(u8)] ErrorCause),
+//! }
+//! ```
+//!
+//! - `#[protocol(status = Type)]`: 响应状态的类型, 需要实现 `From<Type> for u8`. 省略时协议没有响应状态.
+//! - `#[cmd(id, status = Variant)]`: 变体的命令字和响应状态. 同一命令字可以按状态区分多个变体.
+//! - `#[cmd(id, status = Variant, any_cmd)]`: 任意命令字带有该状态时都解析为这个变体, 编码时使用 `id`.
+//! - `#[field(le)]` / `#[field(be)]`: 数值字段的字节序, 默认为小端.
+//! - `#[field(u8)]`: 以 1 字节编码的字段, 需要实现 `From<u8>` 和 `From<T> for u8`.
+//!
+//! 生成的代码实现 `protocol::message::ProtocolMessage`, 并在 `protocol` 启用 `alloc` feature 时
+//! 生成与 `Command` 之间的 `TryFrom` 转换. 负载长度不符时返回 `PayloadLength`,
+//! 状态与命令不符时返回 `UnexpectedStatus`, 未知的命令字返回 `InvalidCommandType`.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    Attribute, Data, DeriveInput, Error, Fields, Ident, LitInt, Path, Result, Token, Type,
+    parse::ParseStream, parse_macro_input, spanned::Spanned,
+};
+
+#[proc_macro_derive(ProtocolMessage, attributes(protocol, cmd, field))]
+pub fn derive_protocol_message(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// 字段在负载中的编码方式.
+#[derive(Clone, Copy)]
+enum Codec {
+    /// 小端字节序的数值.
+    Le,
+    /// 大端字节序的数值.
+    Be,
+    /// 通过 `u8` 转换的单字节值.
+    Byte,
+}
+
+/// 一个负载字段.
+struct Field {
+    /// 构造和解构变体时使用的名称, 元组变体为 `None`.
+    name: Option<Ident>,
+    /// 字段类型.
+    ty: Type,
+    /// 编码方式.
+    codec: Codec,
+}
+
+impl Field {
+    /// 字段在负载中的长度.
+    fn size(&self) -> TokenStream2 {
+        let ty = &self.ty;
+        match self.codec {
+            Codec::Le | Codec::Be => quote!(::core::mem::size_of::<#ty>()),
+            Codec::Byte => quote!(1usize),
+        }
+    }
+}
+
+/// 一个消息变体.
+struct Variant {
+    /// 变体名.
+    ident: Ident,
+    /// 命令字.
+    cmd_id: u8,
+    /// 响应状态, 为状态类型的变体名.
+    status: Option<Ident>,
+    /// 是否匹配任意命令字.
+    any_cmd: bool,
+    /// 是否为带名字段的变体.
+    named: bool,
+    /// 负载字段.
+    fields: Vec<Field>,
+}
+
+impl Variant {
+    /// 负载的总长度.
+    fn payload_len(&self) -> TokenStream2 {
+        if self.fields.is_empty() {
+            return quote!(0usize);
+        }
+        let sizes = self.fields.iter().map(Field::size);
+        quote!(#(#sizes)+*)
+    }
+
+    /// 每个字段在负载中的起止位置.
+    fn ranges(&self) -> Vec<(TokenStream2, TokenStream2)> {
+        let mut start = quote!(0usize);
+        let mut ranges = Vec::new();
+        for field in &self.fields {
+            let size = field.size();
+            let end = quote!(#start + #size);
+            ranges.push((start, end.clone()));
+            start = end;
+        }
+        ranges
+    }
+
+    /// 绑定字段时使用的变量名.
+    fn bindings(&self) -> Vec<Ident> {
+        (0..self.fields.len())
+            .map(|index| format_ident!("__field{}", index))
+            .collect()
+    }
+
+    /// 匹配这个变体并绑定所有字段的模式.
+    fn pattern(&self) -> TokenStream2 {
+        let ident = &self.ident;
+        let bindings = self.bindings();
+        if self.fields.is_empty() {
+            quote!(Self::#ident)
+        } else if self.named {
+            let names = self.fields.iter().map(|field| &field.name);
+            quote!(Self::#ident { #(#names: #bindings),* })
+        } else {
+            quote!(Self::#ident(#(#bindings),*))
+        }
+    }
+
+    /// 校验负载长度并解析这个变体.
+    fn decode(&self) -> TokenStream2 {
+        let ident = &self.ident;
+        let len = self.payload_len();
+        if self.fields.is_empty() {
+            return quote! {{
+                ::protocol::message::payload_of_len(cmd_id, payload, #len)?;
+                ::core::result::Result::Ok(Self::#ident)
+            }};
+        }
+        let values = self
+            .fields
+            .iter()
+            .zip(self.ranges())
+            .map(|(field, (start, end))| {
+                let ty = &field.ty;
+                match field.codec {
+                    Codec::Le | Codec::Be => {
+                        let from_bytes = match field.codec {
+                            Codec::Be => quote!(from_be_bytes),
+                            _ => quote!(from_le_bytes),
+                        };
+                        quote! {{
+                            let mut bytes = [0u8; ::core::mem::size_of::<#ty>()];
+                            bytes.copy_from_slice(&payload[#start..#end]);
+                            <#ty>::#from_bytes(bytes)
+                        }}
+                    }
+                    Codec::Byte => {
+                        quote!(<#ty as ::core::convert::From<u8>>::from(payload[#start]))
+                    }
+                }
+            });
+        let constructor = if self.named {
+            let names = self.fields.iter().map(|field| &field.name);
+            quote!(Self::#ident { #(#names: #values),* })
+        } else {
+            quote!(Self::#ident(#(#values),*))
+        };
+        quote! {{
+            let payload = ::protocol::message::payload_of_len(cmd_id, payload, #len)?;
+            ::core::result::Result::Ok(#constructor)
+        }}
+    }
+
+    /// 将这个变体的字段写入 `out`.
+    fn encode(&self) -> TokenStream2 {
+        let writes = self
+            .fields
+            .iter()
+            .zip(self.bindings())
+            .zip(self.ranges())
+            .map(|((field, binding), (start, end))| match field.codec {
+                Codec::Le => quote!(out[#start..#end].copy_from_slice(&#binding.to_le_bytes());),
+                Codec::Be => quote!(out[#start..#end].copy_from_slice(&#binding.to_be_bytes());),
+                Codec::Byte => quote! {
+                    out[#start] = u8::from(::core::clone::Clone::clone(#binding));
+                },
+            });
+        quote!(#(#writes)*)
+    }
+}
+
+/// 解析 `#[protocol(status = Type)]`.
+fn parse_status_type(attrs: &[Attribute]) -> Result<Option<Path>> {
+    let mut status = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("protocol")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("status") {
+                status = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("未知的 protocol 属性, 应为 `status = Type`"))
+            }
+        })?;
+    }
+    Ok(status)
+}
+
+/// 解析 `#[cmd(id, status = Variant, any_cmd)]`.
+fn parse_cmd(variant: &syn::Variant) -> Result<(u8, Option<Ident>, bool)> {
+    let mut attrs = variant
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cmd"));
+    let Some(attr) = attrs.next() else {
+        return Err(Error::new(variant.span(), "缺少 `#[cmd(...)]` 属性"));
+    };
+    if let Some(attr) = attrs.next() {
+        return Err(Error::new(attr.span(), "重复的 `#[cmd(...)]` 属性"));
+    }
+    attr.parse_args_with(|input: ParseStream| {
+        let cmd_id = input.parse::<LitInt>()?.base10_parse()?;
+        let mut status = None;
+        let mut any_cmd = false;
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let key: Ident = input.parse()?;
+            if key == "status" {
+                input.parse::<Token![=]>()?;
+                status = Some(input.parse()?);
+            } else if key == "any_cmd" {
+                any_cmd = true;
+            } else {
+                return Err(Error::new(
+                    key.span(),
+                    "未知的 cmd 属性, 应为 `status` 或 `any_cmd`",
+                ));
+            }
+        }
+        Ok((cmd_id, status, any_cmd))
+    })
+}
+
+/// 解析 `#[field(le | be | u8)]`.
+fn parse_codec(field: &syn::Field) -> Result<Codec> {
+    let mut codec = Codec::Le;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("field"))
+    {
+        attr.parse_nested_meta(|meta| {
+            codec = if meta.path.is_ident("le") {
+                Codec::Le
+            } else if meta.path.is_ident("be") {
+                Codec::Be
+            } else if meta.path.is_ident("u8") {
+                Codec::Byte
+            } else {
+                return Err(meta.error("未知的 field 属性, 应为 `le`、`be` 或 `u8`"));
+            };
+            Ok(())
+        })?;
+    }
+    Ok(codec)
+}
+
+fn parse_variant(variant: &syn::Variant, has_status: bool) -> Result<Variant> {
+    let (cmd_id, status, any_cmd) = parse_cmd(variant)?;
+    if has_status != status.is_some() {
+        let message = if has_status {
+            "协议有响应状态, 变体需要声明 `status = ...`"
+        } else {
+            "协议没有响应状态, 需要先在枚举上声明 `#[protocol(status = Type)]`"
+        };
+        return Err(Error::new(variant.span(), message));
+    }
+    if any_cmd && status.is_none() {
+        return Err(Error::new(
+            variant.span(),
+            "`any_cmd` 需要同时声明 `status`",
+        ));
+    }
+    let fields = variant
+        .fields
+        .iter()
+        .map(|field| {
+            Ok(Field {
+                name: field.ident.clone(),
+                ty: field.ty.clone(),
+                codec: parse_codec(field)?,
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(Variant {
+        ident: variant.ident.clone(),
+        cmd_id,
+        status,
+        any_cmd,
+        named: matches!(variant.fields, Fields::Named(_)),
+        fields,
+    })
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new(input.span(), "ProtocolMessage 只能用于枚举"));
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "ProtocolMessage 不支持泛型枚举",
+        ));
+    }
+    let status_type = parse_status_type(&input.attrs)?;
+    let variants = data
+        .variants
+        .iter()
+        .map(|variant| parse_variant(variant, status_type.is_some()))
+        .collect::<Result<Vec<_>>>()?;
+
+    // 同一个命令字和状态只能对应一个变体
+    for (index, variant) in variants.iter().enumerate() {
+        let conflict = variants[..index].iter().find(|other| {
+            other.status == variant.status
+                && (other.any_cmd || variant.any_cmd || other.cmd_id == variant.cmd_id)
+        });
+        if let Some(other) = conflict {
+            return Err(Error::new(
+                variant.ident.span(),
+                format!("与 `{}` 的命令字和状态重复", other.ident),
+            ));
+        }
+    }
+
+    let status_value = |status: &Option<Ident>| {
+        status
+            .as_ref()
+            .map(|status| quote!(u8::from(#status_type::#status)))
+    };
+
+    // 按命令字分组, 保持声明顺序
+    let mut groups: Vec<(u8, Vec<&Variant>)> = Vec::new();
+    for variant in variants.iter().filter(|variant| !variant.any_cmd) {
+        match groups
+            .iter_mut()
+            .find(|(cmd_id, _)| *cmd_id == variant.cmd_id)
+        {
+            Some((_, group)) => group.push(variant),
+            None => groups.push((variant.cmd_id, vec![variant])),
+        }
+    }
+    let arms = groups.iter().map(|(cmd_id, group)| {
+        let body = if status_type.is_some() {
+            let checks = group.iter().map(|variant| {
+                let status = status_value(&variant.status);
+                let decode = variant.decode();
+                quote!(if status == #status #decode)
+            });
+            quote! {
+                #(#checks else)* {
+                    ::core::result::Result::Err(::protocol::types::ProtocolError::UnexpectedStatus {
+                        cmd_id,
+                        status,
+                    })
+                }
+            }
+        } else {
+            group[0].decode()
+        };
+        quote!(#cmd_id => #body,)
+    });
+    let match_cmd = quote! {
+        match cmd_id {
+            #(#arms)*
+            _ => ::core::result::Result::Err(
+                ::protocol::types::ProtocolError::InvalidCommandType { cmd_id },
+            ),
+        }
+    };
+    let decode_body = if status_type.is_some() {
+        let any_cmd = variants
+            .iter()
+            .filter(|variant| variant.any_cmd)
+            .map(|variant| {
+                let status = status_value(&variant.status);
+                let decode = variant.decode();
+                quote! {
+                    if status == #status {
+                        return #decode;
+                    }
+                }
+            });
+        quote! {
+            let status = response_status
+                .ok_or(::protocol::types::ProtocolError::MissingStatus { cmd_id })?;
+            #(#any_cmd)*
+            #match_cmd
+        }
+    } else {
+        quote! {
+            let _ = response_status;
+            #match_cmd
+        }
+    };
+
+    let patterns: Vec<_> = variants.iter().map(Variant::pattern).collect();
+    let wildcard_patterns = variants.iter().map(|variant| {
+        let ident = &variant.ident;
+        match (variant.fields.is_empty(), variant.named) {
+            (true, _) => quote!(Self::#ident),
+            (false, true) => quote!(Self::#ident { .. }),
+            (false, false) => quote!(Self::#ident(..)),
+        }
+    });
+    let wildcard_patterns: Vec<_> = wildcard_patterns.collect();
+    let cmd_ids = variants.iter().map(|variant| variant.cmd_id);
+    let statuses = variants
+        .iter()
+        .map(|variant| match status_value(&variant.status) {
+            Some(status) => quote!(::core::option::Option::Some(#status)),
+            None => quote!(::core::option::Option::None),
+        });
+    let lens: Vec<_> = variants.iter().map(Variant::payload_len).collect();
+    let encodes = variants.iter().map(Variant::encode);
+
+    let ident = &input.ident;
+    Ok(quote! {
+        #[automatically_derived]
+        impl ::protocol::message::ProtocolMessage for #ident {
+            fn decode(
+                cmd_id: u8,
+                response_status: ::core::option::Option<u8>,
+                payload: &[u8],
+            ) -> ::core::result::Result<Self, ::protocol::types::ProtocolError> {
+                #decode_body
+            }
+
+            fn cmd_id(&self) -> u8 {
+                match self {
+                    #(#wildcard_patterns => #cmd_ids,)*
+                }
+            }
+
+            fn response_status(&self) -> ::core::option::Option<u8> {
+                match self {
+                    #(#wildcard_patterns => #statuses,)*
+                }
+            }
+
+            fn payload_len(&self) -> usize {
+                match self {
+                    #(#wildcard_patterns => #lens,)*
+                }
+            }
+
+            fn encode_payload(
+                &self,
+                out: &mut [u8],
+            ) -> ::core::result::Result<usize, ::protocol::types::ProtocolError> {
+                let len = ::protocol::message::ProtocolMessage::payload_len(self);
+                let ::core::option::Option::Some(out) = out.get_mut(..len) else {
+                    return ::core::result::Result::Err(::protocol::types::ProtocolError::FrameLength {
+                        expected: len,
+                        actual: out.len(),
+                    });
+                };
+                match self {
+                    #(#patterns => { #encodes })*
+                }
+                ::core::result::Result::Ok(len)
+            }
+        }
+
+        ::protocol::__alloc_only! {
+            #[automatically_derived]
+            impl ::core::convert::TryFrom<::protocol::types::Command> for #ident {
+                type Error = ::protocol::types::ProtocolError;
+
+                fn try_from(command: ::protocol::types::Command) -> ::core::result::Result<Self, Self::Error> {
+                    <Self as ::protocol::message::ProtocolMessage>::decode(
+                        command.cmd_id(),
+                        command.response_status(),
+                        command.payload(),
+                    )
+                }
+            }
+
+            #[automatically_derived]
+            impl ::core::convert::TryFrom<#ident> for ::protocol::types::Command {
+                type Error = ::protocol::types::ProtocolError;
+
+                fn try_from(message: #ident) -> ::core::result::Result<Self, Self::Error> {
+                    ::protocol::message::to_command(&message)
+                }
+            }
+        }
+    })
+}
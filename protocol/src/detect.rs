@@ -0,0 +1,230 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+use bytes::BytesMut;
+
+use crate::dynamic::ProtocolRegistry;
+
+/// 某个协议对一段样本数据的检测结果.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    /// 协议在注册表中的名称.
+    pub name: String,
+    /// 成功解析 (校验通过) 的帧数量.
+    pub frames: usize,
+    /// 解码器报告的被拒绝次数, 如校验失败或超长帧.
+    pub rejected: usize,
+    /// 置信度, 范围为 `0.0..=1.0`.
+    /// 为成功解析的帧所覆盖的字节比例, 再乘以帧在 (帧 + 拒绝) 中所占的比例.
+    pub confidence: f32,
+}
+
+impl Display for Detection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (置信度 {:.0}%, {} 帧, {} 次拒绝)",
+            self.name,
+            self.confidence * 100.0,
+            self.frames,
+            self.rejected
+        )
+    }
+}
+
+/// `ProtocolDetector` 通过试解析一段样本数据来猜测设备使用的协议.
+///
+/// 样本会被分别交给每个已注册协议的新解码器, 按校验通过的帧所覆盖的字节比例打分,
+/// 并按被拒绝的次数折算, 以免噪声中偶然通过的少数长帧得到高分.
+/// 之所以按覆盖的字节而不是通过的帧数打分, 是因为帧数会偏向把数据切成许多短帧的协议,
+/// 而覆盖比例衡量的是样本中有多少数据能被校验通过的帧解释, 与各协议的帧长无关.
+/// 得分相同时被拒绝次数少的协议优先. 至少需要解析出 `min_frames` 个帧才会被选中,
+/// 这也排除了把整段样本当作一个帧的透传协议.
+#[derive(Clone)]
+pub struct ProtocolDetector {
+    /// 参与检测的协议.
+    registry: ProtocolRegistry,
+    /// 开始检测前需要收集的样本字节数.
+    sample_len: usize,
+    /// 被选中所需的最少帧数.
+    min_frames: usize,
+    /// 收到第一个字节后等待样本的最长时间, 超时后用已收集的数据检测.
+    max_wait: Duration,
+}
+
+impl ProtocolDetector {
+    /// 默认的样本字节数.
+    const SAMPLE_LEN: usize = 256;
+    /// 默认的最少帧数.
+    const MIN_FRAMES: usize = 2;
+    /// 默认的最长等待时间.
+    const MAX_WAIT: Duration = Duration::from_secs(1);
+
+    /// 使用给定注册表中的全部协议创建检测器.
+    pub fn new(registry: ProtocolRegistry) -> Self {
+        Self {
+            registry,
+            sample_len: Self::SAMPLE_LEN,
+            min_frames: Self::MIN_FRAMES,
+            max_wait: Self::MAX_WAIT,
+        }
+    }
+
+    /// 设置开始检测前需要收集的样本字节数.
+    pub fn sample_len(mut self, sample_len: usize) -> Self {
+        self.sample_len = sample_len;
+        self
+    }
+
+    /// 设置被选中所需的最少帧数.
+    pub fn min_frames(mut self, min_frames: usize) -> Self {
+        self.min_frames = min_frames;
+        self
+    }
+
+    /// 设置收到第一个字节后等待样本的最长时间.
+    ///
+    /// 低流量或请求-响应式的设备可能很久才能凑满样本, 超时后直接用已收集的数据检测,
+    /// 而不是一直不显示任何帧.
+    pub fn max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+
+    /// 开始检测前需要收集的样本字节数.
+    pub fn required_len(&self) -> usize {
+        self.sample_len
+    }
+
+    /// 收到第一个字节后等待样本的最长时间.
+    pub fn wait_limit(&self) -> Duration {
+        self.max_wait
+    }
+
+    /// 对样本进行检测, 返回每个协议的结果, 最佳匹配在前.
+    pub fn detect(&self, sample: &[u8]) -> Vec<Detection> {
+        let mut detections: Vec<Detection> = self
+            .registry
+            .names()
+            .filter_map(|name| {
+                let mut decoder = self.registry.get(name).ok()?.decoder();
+                let mut buf = BytesMut::from(sample);
+                let commands = decoder.parse_protocol_frame(&mut buf).unwrap_or_default();
+                let covered: usize = commands
+                    .iter()
+                    .map(|c| c.frame().map_or(0, <[u8]>::len))
+                    .sum();
+                let frames = commands.len();
+                let rejected = decoder.take_diagnostics().len();
+                let coverage = covered as f32 / sample.len().max(1) as f32;
+                let accepted = frames as f32 / (frames + rejected).max(1) as f32;
+                Some(Detection {
+                    name: name.to_string(),
+                    frames,
+                    rejected,
+                    confidence: coverage * accepted,
+                })
+            })
+            .collect();
+        detections.sort_by(|a, b| {
+            b.confidence
+                .total_cmp(&a.confidence)
+                .then(a.rejected.cmp(&b.rejected))
+        });
+        detections
+    }
+
+    /// 返回样本的最佳匹配, 没有协议解析出足够的帧时返回 `None`.
+    pub fn best(&self, sample: &[u8]) -> Option<Detection> {
+        self.detect(sample)
+            .into_iter()
+            .find(|d| d.frames >= self.min_frames && d.confidence > 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::{
+        raw::RawProtocol,
+        traits::{FrameGenerator, MessageProtocol, ParseProtocol, ProtocolSplit},
+        types::{Command, Diagnostic, ProtocolError},
+    };
+
+    // 以 '\n' 结尾的行协议, 以 '#' 开头的行视为校验失败.
+    #[derive(Default)]
+    struct LineProtocol;
+    #[derive(Default)]
+    struct LineDecoder(usize);
+
+    impl ParseProtocol for LineDecoder {
+        fn parse_protocol_frame(&mut self, buf: &mut BytesMut) -> Option<Vec<Command>> {
+            let mut commands = Vec::new();
+            while let Some(end) = buf.iter().position(|&b| b == b'\n') {
+                let frame = buf.split_to(end + 1).freeze();
+                if frame[0] == b'#' {
+                    self.0 += 1;
+                    continue;
+                }
+                commands.push(Command::from_frame(frame, 0, None, 0..0, 0..end));
+            }
+            (!commands.is_empty()).then_some(commands)
+        }
+
+        fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+            let rejected = std::mem::take(&mut self.0);
+            vec![
+                Diagnostic::ChecksumMismatch {
+                    expected: 0,
+                    actual: 0
+                };
+                rejected
+            ]
+        }
+    }
+
+    impl FrameGenerator for LineProtocol {
+        fn create_frame(&self, command: Command) -> Result<Bytes, ProtocolError> {
+            Ok(command.payload_bytes().clone())
+        }
+    }
+
+    impl ProtocolSplit for LineProtocol {
+        type Encoder = LineProtocol;
+        type Decode = LineDecoder;
+
+        fn into_split(self) -> (Self::Decode, Self::Encoder) {
+            (LineDecoder::default(), LineProtocol)
+        }
+    }
+
+    impl MessageProtocol for LineProtocol {
+        type Message = crate::raw::RawFrame;
+    }
+
+    fn detector() -> ProtocolDetector {
+        ProtocolDetector::new(
+            ProtocolRegistry::default()
+                .register("line", LineProtocol)
+                .register("raw", RawProtocol),
+        )
+    }
+
+    #[test]
+    fn test_detects_framed_protocol() {
+        let best = detector().best(b"ab\ncd\nef\n#x\n").unwrap();
+        assert_eq!(best.name, "line");
+        assert_eq!(best.frames, 3);
+        assert_eq!(best.rejected, 1);
+        // 覆盖 9/12 字节, 4 行中 3 行通过
+        assert_eq!(best.confidence, 0.75 * 0.75);
+    }
+
+    #[test]
+    fn test_raw_is_never_selected() {
+        assert_eq!(detector().best(b"no line breaks here"), None);
+        assert_eq!(detector().detect(b"no line breaks here")[0].name, "raw");
+    }
+}
@@ -0,0 +1,522 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+
+use bytes::{Bytes, BytesMut};
+use tracing::info;
+
+use crate::{
+    detect::{Detection, ProtocolDetector},
+    traits::{FrameGenerator, MessageProtocol, ParseProtocol, ProtocolSplit},
+    types::{Command, Diagnostic, Diagnostics, ProtocolError},
+};
+
+/// 类型擦除后的解码器.
+pub type BoxedDecoder = Box<dyn ParseProtocol + Send>;
+/// 类型擦除后的编码器.
+pub type BoxedEncoder = Box<dyn FrameGenerator + Send>;
+
+impl<T: ParseProtocol + ?Sized> ParseProtocol for Box<T> {
+    fn parse_protocol_frame(&mut self, buf: &mut BytesMut) -> Option<Vec<Command>> {
+        (**self).parse_protocol_frame(buf)
+    }
+
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        (**self).take_diagnostics()
+    }
+}
+
+impl<T: FrameGenerator + ?Sized> FrameGenerator for Box<T> {
+    fn create_frame(&self, command: Command) -> Result<Bytes, ProtocolError> {
+        (**self).create_frame(command)
+    }
+}
+
+/// `DynProtocol` 是对象安全的协议接口, 可以以 `Arc<dyn DynProtocol>` 的形式在运行时选择和切换.
+///
+/// 所有满足 `MessageProtocol + Default` 且消息实现了 `Debug` 的协议都会自动实现该 trait,
+/// 自定义协议也可以直接实现它.
+pub trait DynProtocol: Send + Sync {
+    /// 创建一个新的解码器.
+    fn decoder(&self) -> BoxedDecoder;
+
+    /// 创建一个新的编码器.
+    fn encoder(&self) -> BoxedEncoder;
+
+    /// 将 `Command` 转换为该协议的类型化消息并格式化, 用于日志显示.
+    fn describe(&self, command: &Command) -> Result<String, ProtocolError>;
+
+    /// 返回 `&dyn Any`, 以便调用方判断具体的协议类型.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<P> DynProtocol for P
+where
+    P: MessageProtocol + Default + Send + Sync + 'static,
+    P::Decode: ParseProtocol + Send + 'static,
+    P::Encoder: FrameGenerator + Send + 'static,
+    P::Message: Debug,
+{
+    fn decoder(&self) -> BoxedDecoder {
+        Box::new(P::default().into_split().0)
+    }
+
+    fn encoder(&self) -> BoxedEncoder {
+        Box::new(P::default().into_split().1)
+    }
+
+    fn describe(&self, command: &Command) -> Result<String, ProtocolError> {
+        P::Message::try_from(command.clone()).map(|message| format!("{message:?}"))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// `ProtocolRegistry` 按名称保存可用的协议.
+#[derive(Default, Clone)]
+pub struct ProtocolRegistry {
+    protocols: BTreeMap<String, Arc<dyn DynProtocol>>,
+}
+
+impl ProtocolRegistry {
+    /// 以给定名称注册一个协议, 同名的协议会被替换.
+    pub fn register(
+        mut self,
+        name: impl Into<String>,
+        protocol: impl DynProtocol + 'static,
+    ) -> Self {
+        self.protocols.insert(name.into(), Arc::new(protocol));
+        self
+    }
+
+    /// 按名称查找协议.
+    pub fn get(&self, name: &str) -> Result<Arc<dyn DynProtocol>, ProtocolError> {
+        self.protocols
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ProtocolError::UnknownProtocol(name.to_string()))
+    }
+
+    /// 按名称顺序返回所有已注册的协议名.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.protocols.keys().map(String::as_str)
+    }
+}
+
+/// `ProtocolSwitch` 保存当前选中的协议, 可以在连接运行期间切换.
+///
+/// 它可以被廉价地克隆, 所有克隆共享同一个选择. 每次切换都会增加代数,
+/// [`SwitchableDecoder`] 和 [`SwitchableEncoder`] 在下一次使用时会据此重建内部的编解码器.
+#[derive(Clone)]
+pub struct ProtocolSwitch {
+    inner: Arc<SwitchState>,
+}
+
+struct SwitchState {
+    registry: ProtocolRegistry,
+    current: RwLock<(String, Arc<dyn DynProtocol>)>,
+    generation: AtomicU64,
+    detection: RwLock<Option<Detection>>,
+}
+
+impl ProtocolSwitch {
+    /// 创建一个选中 `name` 协议的切换器.
+    pub fn new(registry: ProtocolRegistry, name: &str) -> Result<Self, ProtocolError> {
+        let protocol = registry.get(name)?;
+        Ok(Self {
+            inner: Arc::new(SwitchState {
+                registry,
+                current: RwLock::new((name.to_string(), protocol)),
+                generation: AtomicU64::new(0),
+                detection: RwLock::new(None),
+            }),
+        })
+    }
+
+    /// 切换到 `name` 协议. 协议不存在时保持当前选择并返回错误.
+    pub fn switch_to(&self, name: &str) -> Result<(), ProtocolError> {
+        let protocol = self.inner.registry.get(name)?;
+        *self.inner.current.write().unwrap() = (name.to_string(), protocol);
+        self.inner.generation.fetch_add(1, Ordering::AcqRel);
+        Ok(())
+    }
+
+    /// 按名称顺序切换到下一个已注册的协议, 返回新的协议名.
+    pub fn switch_next(&self) -> String {
+        let name = self.name();
+        let names: Vec<&str> = self.inner.registry.names().collect();
+        let index = names.iter().position(|n| *n == name).map_or(0, |i| i + 1);
+        let next = names[index % names.len()].to_string();
+        // 名称来自注册表本身, 切换不会失败
+        let _ = self.switch_to(&next);
+        next
+    }
+
+    /// 切换到自动检测选出的协议, 并记录检测结果.
+    pub fn apply_detection(&self, detection: Detection) -> Result<(), ProtocolError> {
+        self.switch_to(&detection.name)?;
+        *self.inner.detection.write().unwrap() = Some(detection);
+        Ok(())
+    }
+
+    /// 当前协议的自动检测结果. 当前协议不是由自动检测选出时返回 `None`.
+    pub fn detection(&self) -> Option<Detection> {
+        let name = self.name();
+        self.inner
+            .detection
+            .read()
+            .unwrap()
+            .clone()
+            .filter(|detection| detection.name == name)
+    }
+
+    /// 当前选中的协议名.
+    pub fn name(&self) -> String {
+        self.inner.current.read().unwrap().0.clone()
+    }
+
+    /// 当前选中的协议.
+    pub fn current(&self) -> Arc<dyn DynProtocol> {
+        self.inner.current.read().unwrap().1.clone()
+    }
+
+    /// 使用当前选中的协议格式化 `Command`.
+    pub fn describe(&self, command: &Command) -> Result<String, ProtocolError> {
+        self.current().describe(command)
+    }
+
+    /// 协议注册表.
+    pub fn registry(&self) -> &ProtocolRegistry {
+        &self.inner.registry
+    }
+
+    fn generation(&self) -> u64 {
+        self.inner.generation.load(Ordering::Acquire)
+    }
+}
+
+/// `SwitchableProtocol` 将 [`ProtocolSwitch`] 适配为 `ProtocolSplit`, 使 `LazyApp`
+/// 可以在运行时选择协议, 而不需要为每个协议单独单态化.
+pub struct SwitchableProtocol {
+    switch: ProtocolSwitch,
+    detector: Option<ProtocolDetector>,
+}
+
+impl SwitchableProtocol {
+    /// 基于给定的切换器创建协议.
+    pub fn new(switch: ProtocolSwitch) -> Self {
+        Self {
+            switch,
+            detector: None,
+        }
+    }
+
+    /// 启用自动检测: 收集到足够的样本或等待超时后, 解码器用检测出的协议替换当前协议.
+    /// 没有协议匹配时保持当前协议.
+    pub fn auto_detect(mut self, detector: ProtocolDetector) -> Self {
+        self.detector = Some(detector);
+        self
+    }
+}
+
+impl ProtocolSplit for SwitchableProtocol {
+    type Encoder = SwitchableEncoder;
+    type Decode = SwitchableDecoder;
+
+    fn into_split(self) -> (Self::Decode, Self::Encoder) {
+        let generation = self.switch.generation();
+        let decoder = SwitchableDecoder {
+            inner: self.switch.current().decoder(),
+            generation,
+            switch: self.switch.clone(),
+            detector: self.detector,
+            sampling_since: None,
+            diagnostics: Diagnostics::default(),
+        };
+        let encoder = SwitchableEncoder {
+            inner: Mutex::new((generation, self.switch.current().encoder())),
+            switch: self.switch,
+        };
+        (decoder, encoder)
+    }
+}
+
+impl MessageProtocol for SwitchableProtocol {
+    /// 动态协议的消息在传输中保持为 `Command`, 由界面通过 [`ProtocolSwitch::describe`] 解释.
+    type Message = DynMessage;
+}
+
+/// 动态协议的消息, 即未经类型化的 `Command`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynMessage(pub Command);
+
+impl TryFrom<Command> for DynMessage {
+    type Error = ProtocolError;
+
+    fn try_from(command: Command) -> Result<Self, Self::Error> {
+        Ok(Self(command))
+    }
+}
+
+impl TryFrom<DynMessage> for Command {
+    type Error = ProtocolError;
+
+    fn try_from(message: DynMessage) -> Result<Self, Self::Error> {
+        Ok(message.0)
+    }
+}
+
+/// 跟随 [`ProtocolSwitch`] 的解码器.
+///
+/// 切换协议后, 下一次解析时会换用新协议的解码器. 缓冲区中旧协议的残余数据
+/// 由新解码器按其自身的规则丢弃或重新同步.
+/// 启用自动检测时, 解码器在收集到足够的样本前不会解析任何帧, 样本在检测后仍由选出的协议解析.
+/// 自收到第一个字节起超过检测器的等待时间后, 即使样本不足也会用已收集的数据检测.
+/// 等待时间在数据到达时检查, 因此超时后的第一次读取会触发检测.
+/// 旧解码器尚未取出的诊断事件在切换时被保留, 由下一次 `take_diagnostics` 一并返回.
+pub struct SwitchableDecoder {
+    inner: BoxedDecoder,
+    generation: u64,
+    switch: ProtocolSwitch,
+    detector: Option<ProtocolDetector>,
+    /// 自动检测开始收集样本的时间.
+    sampling_since: Option<Instant>,
+    /// 切换协议前从旧解码器中取出的诊断事件.
+    diagnostics: Diagnostics,
+}
+
+impl SwitchableDecoder {
+    /// 样本足够或等待超时时执行自动检测, 返回是否仍在等待样本.
+    fn detect(&mut self, buf: &BytesMut) -> bool {
+        let Some(ref detector) = self.detector else {
+            return false;
+        };
+        if buf.is_empty() {
+            return true;
+        }
+        let since = *self.sampling_since.get_or_insert_with(Instant::now);
+        if buf.len() < detector.required_len() && since.elapsed() < detector.wait_limit() {
+            return true;
+        }
+        match detector.best(buf) {
+            Some(detection) => {
+                info!("检测到协议: {}", detection);
+                if let Err(e) = self.switch.apply_detection(detection) {
+                    info!("切换协议失败: {}", e);
+                }
+            }
+            None => info!("未能识别协议, 继续使用 {}", self.switch.name()),
+        }
+        self.detector = None;
+        false
+    }
+
+    fn sync(&mut self) {
+        let generation = self.switch.generation();
+        if generation != self.generation {
+            self.diagnostics.extend(self.inner.take_diagnostics());
+            self.inner = self.switch.current().decoder();
+            self.generation = generation;
+        }
+    }
+}
+
+impl ParseProtocol for SwitchableDecoder {
+    fn parse_protocol_frame(&mut self, buf: &mut BytesMut) -> Option<Vec<Command>> {
+        if self.detect(buf) {
+            return None;
+        }
+        self.sync();
+        self.inner.parse_protocol_frame(buf)
+    }
+
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.diagnostics.take();
+        diagnostics.extend(self.inner.take_diagnostics());
+        diagnostics
+    }
+}
+
+/// 跟随 [`ProtocolSwitch`] 的编码器.
+pub struct SwitchableEncoder {
+    inner: Mutex<(u64, BoxedEncoder)>,
+    switch: ProtocolSwitch,
+}
+
+impl FrameGenerator for SwitchableEncoder {
+    fn create_frame(&self, command: Command) -> Result<Bytes, ProtocolError> {
+        let mut inner = self.inner.lock().unwrap();
+        let generation = self.switch.generation();
+        if inner.0 != generation {
+            *inner = (generation, self.switch.current().encoder());
+        }
+        inner.1.create_frame(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw::{RawFrame, RawProtocol};
+    use std::time::Duration;
+
+    // 拒绝所有数据的协议, 每次解析都丢弃缓冲区并记录一次校验失败.
+    #[derive(Default)]
+    struct RejectProtocol;
+    #[derive(Default)]
+    struct RejectDecoder(usize);
+
+    impl ParseProtocol for RejectDecoder {
+        fn parse_protocol_frame(&mut self, buf: &mut BytesMut) -> Option<Vec<Command>> {
+            buf.clear();
+            self.0 += 1;
+            None
+        }
+
+        fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+            let rejected = std::mem::take(&mut self.0);
+            vec![
+                Diagnostic::ChecksumMismatch {
+                    expected: 0,
+                    actual: 0
+                };
+                rejected
+            ]
+        }
+    }
+
+    impl FrameGenerator for RejectProtocol {
+        fn create_frame(&self, command: Command) -> Result<Bytes, ProtocolError> {
+            Ok(command.payload_bytes().clone())
+        }
+    }
+
+    impl ProtocolSplit for RejectProtocol {
+        type Encoder = RejectProtocol;
+        type Decode = RejectDecoder;
+
+        fn into_split(self) -> (Self::Decode, Self::Encoder) {
+            (RejectDecoder::default(), RejectProtocol)
+        }
+    }
+
+    impl MessageProtocol for RejectProtocol {
+        type Message = RawFrame;
+    }
+
+    fn registry() -> ProtocolRegistry {
+        ProtocolRegistry::default()
+            .register("raw", RawProtocol)
+            .register("other", RawProtocol)
+    }
+
+    #[test]
+    fn test_registry_rejects_unknown_protocol() {
+        assert_eq!(
+            ProtocolSwitch::new(registry(), "missing").err(),
+            Some(ProtocolError::UnknownProtocol("missing".to_string()))
+        );
+        assert_eq!(registry().names().collect::<Vec<_>>(), ["other", "raw"]);
+    }
+
+    #[test]
+    fn test_switch_next_cycles_names() {
+        let switch = ProtocolSwitch::new(registry(), "raw").unwrap();
+        assert_eq!(switch.switch_next(), "other");
+        assert_eq!(switch.switch_next(), "raw");
+        assert!(switch.switch_to("missing").is_err());
+        assert_eq!(switch.name(), "raw");
+    }
+
+    #[test]
+    fn test_switchable_protocol_follows_switch() {
+        let switch = ProtocolSwitch::new(registry(), "raw").unwrap();
+        let (mut decoder, encoder) = SwitchableProtocol::new(switch.clone()).into_split();
+
+        let mut buf = BytesMut::from(&b"abc"[..]);
+        let commands = decoder.parse_protocol_frame(&mut buf).unwrap();
+        assert_eq!(commands[0].payload(), b"abc");
+        assert_eq!(
+            switch.describe(&commands[0]),
+            Ok(format!("{:?}", RawFrame(Bytes::from_static(b"abc"))))
+        );
+
+        switch.switch_to("other").unwrap();
+        let frame = encoder
+            .create_frame(Command::new(0).with_payload(&b"xyz"[..]))
+            .unwrap();
+        assert_eq!(frame.as_ref(), b"xyz");
+        assert_eq!(decoder.generation, 0);
+        decoder.parse_protocol_frame(&mut buf);
+        assert_eq!(decoder.generation, 1);
+    }
+
+    #[test]
+    fn test_auto_detect_waits_for_sample() {
+        let switch = ProtocolSwitch::new(registry(), "raw").unwrap();
+        let detector = ProtocolDetector::new(registry())
+            .sample_len(4)
+            .min_frames(1);
+        let (mut decoder, _) = SwitchableProtocol::new(switch.clone())
+            .auto_detect(detector)
+            .into_split();
+
+        let mut buf = BytesMut::from(&b"ab"[..]);
+        assert_eq!(decoder.parse_protocol_frame(&mut buf), None);
+        assert_eq!(buf.as_ref(), b"ab");
+
+        buf.extend_from_slice(b"cd");
+        let commands = decoder.parse_protocol_frame(&mut buf).unwrap();
+        assert_eq!(commands[0].payload(), b"abcd");
+        // 两个协议得分相同时按名称顺序选择
+        assert_eq!(switch.name(), "other");
+        assert_eq!(switch.detection().unwrap().confidence, 1.0);
+    }
+
+    #[test]
+    fn test_switch_keeps_old_diagnostics() {
+        let registry = registry().register("reject", RejectProtocol);
+        let switch = ProtocolSwitch::new(registry, "reject").unwrap();
+        let (mut decoder, _) = SwitchableProtocol::new(switch.clone()).into_split();
+
+        let mut buf = BytesMut::from(&b"abc"[..]);
+        assert_eq!(decoder.parse_protocol_frame(&mut buf), None);
+        switch.switch_to("raw").unwrap();
+        buf.extend_from_slice(b"def");
+        let commands = decoder.parse_protocol_frame(&mut buf).unwrap();
+        assert_eq!(commands[0].payload(), b"def");
+        assert_eq!(
+            decoder.take_diagnostics(),
+            vec![Diagnostic::ChecksumMismatch {
+                expected: 0,
+                actual: 0
+            }]
+        );
+        assert!(decoder.take_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn test_auto_detect_after_max_wait() {
+        let switch = ProtocolSwitch::new(registry(), "raw").unwrap();
+        let detector = ProtocolDetector::new(registry())
+            .min_frames(1)
+            .max_wait(Duration::from_millis(20));
+        let (mut decoder, _) = SwitchableProtocol::new(switch.clone())
+            .auto_detect(detector)
+            .into_split();
+
+        let mut buf = BytesMut::from(&b"ab"[..]);
+        assert_eq!(decoder.parse_protocol_frame(&mut buf), None);
+        std::thread::sleep(Duration::from_millis(30));
+        // 样本远不足 256 字节, 但等待已超时
+        buf.extend_from_slice(b"cd");
+        let commands = decoder.parse_protocol_frame(&mut buf).unwrap();
+        assert_eq!(commands[0].payload(), b"abcd");
+        assert_eq!(switch.name(), "other");
+    }
+}
@@ -0,0 +1,426 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU16, Ordering},
+    time::{Duration, Instant},
+};
+
+use bytes::{Bytes, BytesMut};
+use tracing::{debug, info};
+
+use crate::{
+    traits::{FrameGenerator, MessageProtocol, ParseProtocol, ProtocolSplit},
+    types::{Command, Diagnostic, Diagnostics, ProtocolError},
+};
+
+/// 分片信息, 保存在 4 字节的保留字段中.
+///
+/// 布局为 `[消息标识高字节, 消息标识低字节, 分片序号, 分片总数]`.
+/// 分片总数小于 2 的保留字段 (如全 0) 表示这是一个未分片的普通帧.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentHeader {
+    /// 同一条逻辑消息的所有分片共享的标识.
+    pub message_id: u16,
+    /// 分片序号, 从 0 开始.
+    pub index: u8,
+    /// 分片总数.
+    pub count: u8,
+}
+
+impl FragmentHeader {
+    /// 分片信息占用的保留字段长度.
+    pub const LEN: usize = 4;
+
+    /// 从保留字段中读取分片信息, 不是合法的分片时返回 `None`.
+    pub fn parse(reserved: &[u8]) -> Option<Self> {
+        let [id0, id1, index, count] = *reserved else {
+            return None;
+        };
+        (count >= 2 && index < count).then_some(Self {
+            message_id: u16::from_be_bytes([id0, id1]),
+            index,
+            count,
+        })
+    }
+
+    /// 编码为 4 字节的保留字段.
+    pub fn to_bytes(self) -> [u8; Self::LEN] {
+        let [id0, id1] = self.message_id.to_be_bytes();
+        [id0, id1, self.index, self.count]
+    }
+}
+
+/// `Fragmented` 为底层协议加上分片和重组, 以发送超过单帧负载上限的消息.
+///
+/// 负载超过 `max_fragment_len` 的命令会被拆分为多个帧, 分片信息写入保留字段 (见 [`FragmentHeader`]),
+/// 因此底层协议需要原样编码和解码 4 字节的保留字段. 未超过上限的命令按原样编码.
+/// 接收端按消息标识重组分片, 分片可以乱序到达, 超时未到齐的消息会被丢弃并记录诊断事件.
+pub struct Fragmented<P> {
+    /// 底层协议.
+    protocol: P,
+    /// 单个分片的最大负载长度.
+    max_fragment_len: usize,
+    /// 等待一条消息的全部分片到齐的最长时间.
+    timeout: Duration,
+}
+
+impl<P> Fragmented<P> {
+    /// 默认的重组超时时间.
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// 包装底层协议, `max_fragment_len` 不应超过底层解码器允许的负载长度.
+    ///
+    /// # Panics
+    /// 如果 `max_fragment_len` 为 0.
+    pub fn new(protocol: P, max_fragment_len: usize) -> Self {
+        assert!(max_fragment_len > 0, "分片长度不能为 0");
+        Self {
+            protocol,
+            max_fragment_len,
+            timeout: Self::TIMEOUT,
+        }
+    }
+
+    /// 设置重组超时时间.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl<P: ProtocolSplit> ProtocolSplit for Fragmented<P> {
+    type Encoder = FragmentEncoder<P::Encoder>;
+    type Decode = ReassemblyDecoder<P::Decode>;
+
+    fn into_split(self) -> (Self::Decode, Self::Encoder) {
+        let (decode, encoder) = self.protocol.into_split();
+        (
+            ReassemblyDecoder::new(decode, self.timeout),
+            FragmentEncoder::new(encoder, self.max_fragment_len),
+        )
+    }
+}
+
+impl<P: MessageProtocol> MessageProtocol for Fragmented<P> {
+    type Message = P::Message;
+}
+
+/// `FragmentEncoder` 将超长的命令拆分为多个分片, 并将各分片的帧依次拼接后返回.
+pub struct FragmentEncoder<E> {
+    /// 底层编码器.
+    inner: E,
+    /// 单个分片的最大负载长度.
+    max_fragment_len: usize,
+    /// 下一条分片消息使用的标识.
+    next_id: AtomicU16,
+}
+
+impl<E> FragmentEncoder<E> {
+    /// 包装底层编码器.
+    ///
+    /// # Panics
+    /// 如果 `max_fragment_len` 为 0.
+    pub fn new(inner: E, max_fragment_len: usize) -> Self {
+        assert!(max_fragment_len > 0, "分片长度不能为 0");
+        Self {
+            inner,
+            max_fragment_len,
+            next_id: AtomicU16::new(0),
+        }
+    }
+}
+
+impl<E: FrameGenerator> FrameGenerator for FragmentEncoder<E> {
+    /// 负载不超过分片长度时直接编码, 否则拆分为多个分片.
+    ///
+    /// 命令带有 4 字节的保留字段时, 其前 2 字节被用作消息标识, 否则使用编码器自增的标识.
+    fn create_frame(&self, command: Command) -> Result<Bytes, ProtocolError> {
+        let payload = command.payload_bytes().clone();
+        if payload.len() <= self.max_fragment_len {
+            return self.inner.create_frame(command);
+        }
+        let max_len = self.max_fragment_len * usize::from(u8::MAX);
+        let count = u8::try_from(payload.len().div_ceil(self.max_fragment_len)).map_err(|_| {
+            ProtocolError::PayloadTooLarge {
+                len: payload.len(),
+                max: max_len,
+            }
+        })?;
+        let message_id = match *command.reserved() {
+            [id0, id1, _, _] => u16::from_be_bytes([id0, id1]),
+            _ => self.next_id.fetch_add(1, Ordering::Relaxed),
+        };
+        debug!(
+            message_id,
+            count,
+            len = payload.len(),
+            "fragmenting command"
+        );
+
+        let mut frames = BytesMut::new();
+        for (index, chunk) in payload.chunks(self.max_fragment_len).enumerate() {
+            let header = FragmentHeader {
+                message_id,
+                index: index as u8,
+                count,
+            };
+            let mut fragment = Command::new(command.cmd_id())
+                .with_reserved(header.to_bytes().to_vec())
+                .with_payload(payload.slice_ref(chunk));
+            if let Some(status) = command.response_status() {
+                fragment = fragment.with_status(status);
+            }
+            frames.extend_from_slice(&self.inner.create_frame(fragment)?);
+        }
+        Ok(frames.freeze())
+    }
+}
+
+/// 一条正在重组的消息.
+struct PendingMessage {
+    /// 收到第一个分片的时间.
+    started: Instant,
+    /// 第一个分片的响应状态.
+    response_status: Option<u8>,
+    /// 按序号存放的分片负载.
+    fragments: Vec<Option<Bytes>>,
+    /// 已收到的不同分片数量.
+    received: usize,
+}
+
+/// `ReassemblyDecoder` 将底层解码器产生的分片重组为完整的命令.
+///
+/// 未分片的命令原样交给上层. 重组后的命令保留字段为 `[消息标识, 0, 0]`, 没有原始帧.
+/// 分片以 (命令字, 消息标识) 归组, 重复的分片会被忽略.
+pub struct ReassemblyDecoder<D> {
+    /// 底层解码器.
+    inner: D,
+    /// 等待一条消息的全部分片到齐的最长时间.
+    timeout: Duration,
+    /// 正在重组的消息.
+    pending: HashMap<(u8, u16), PendingMessage>,
+    /// 尚未被取出的诊断事件.
+    diagnostics: Diagnostics,
+}
+
+impl<D> ReassemblyDecoder<D> {
+    /// 同时重组的消息数量上限, 超出后丢弃最早开始的消息.
+    const MAX_PENDING: usize = 16;
+
+    /// 包装底层解码器.
+    pub fn new(inner: D, timeout: Duration) -> Self {
+        Self {
+            inner,
+            timeout,
+            pending: HashMap::new(),
+            diagnostics: Diagnostics::default(),
+        }
+    }
+
+    /// 丢弃一条未完成的消息, 并记录诊断事件.
+    fn discard(&mut self, key: (u8, u16)) {
+        if let Some(message) = self.pending.remove(&key) {
+            let diagnostic = Diagnostic::IncompleteMessage {
+                message_id: key.1,
+                received: message.received,
+                count: message.fragments.len(),
+            };
+            info!("{}", diagnostic);
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    /// 丢弃所有超时的消息.
+    fn expire(&mut self, now: Instant) {
+        let expired: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, message)| now.duration_since(message.started) >= self.timeout)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in expired {
+            self.discard(key);
+        }
+    }
+
+    /// 接收一个分片, 消息的全部分片到齐时返回重组后的命令.
+    fn accept(&mut self, header: FragmentHeader, command: Command) -> Option<Command> {
+        let key = (command.cmd_id(), header.message_id);
+        let count = usize::from(header.count);
+        if self
+            .pending
+            .get(&key)
+            .is_some_and(|message| message.fragments.len() != count)
+        {
+            // 分片总数不一致, 视为一条新的消息
+            self.discard(key);
+        }
+        if !self.pending.contains_key(&key) && self.pending.len() == Self::MAX_PENDING {
+            let oldest = self
+                .pending
+                .iter()
+                .min_by_key(|(_, message)| message.started)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                self.discard(oldest);
+            }
+        }
+
+        let message = self.pending.entry(key).or_insert_with(|| PendingMessage {
+            started: Instant::now(),
+            response_status: command.response_status(),
+            fragments: vec![None; count],
+            received: 0,
+        });
+        let slot = &mut message.fragments[usize::from(header.index)];
+        if slot.is_none() {
+            *slot = Some(command.payload_bytes().clone());
+            message.received += 1;
+        }
+        if message.received < count {
+            return None;
+        }
+
+        let message = self.pending.remove(&key)?;
+        let mut payload = BytesMut::new();
+        for fragment in message.fragments.into_iter().flatten() {
+            payload.extend_from_slice(&fragment);
+        }
+        let reserved = FragmentHeader {
+            message_id: header.message_id,
+            index: 0,
+            count: 0,
+        };
+        let mut reassembled = Command::new(key.0)
+            .with_reserved(reserved.to_bytes().to_vec())
+            .with_payload(payload.freeze());
+        if let Some(status) = message.response_status {
+            reassembled = reassembled.with_status(status);
+        }
+        debug!(message_id = header.message_id, count, "reassembled command");
+        Some(reassembled)
+    }
+}
+
+impl<D: ParseProtocol> ParseProtocol for ReassemblyDecoder<D> {
+    fn parse_protocol_frame(&mut self, buf: &mut BytesMut) -> Option<Vec<Command>> {
+        self.expire(Instant::now());
+        let commands = self.inner.parse_protocol_frame(buf)?;
+        let commands: Vec<_> = commands
+            .into_iter()
+            .filter_map(|command| match FragmentHeader::parse(command.reserved()) {
+                Some(header) => self.accept(header, command),
+                None => Some(command),
+            })
+            .collect();
+        (!commands.is_empty()).then_some(commands)
+    }
+
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.inner.take_diagnostics();
+        diagnostics.extend(self.diagnostics.take());
+        diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试用的简单帧格式: [命令字, 4 字节保留字段, 1 字节负载长度, 负载].
+    struct TestEncoder;
+    struct TestDecoder;
+
+    impl FrameGenerator for TestEncoder {
+        fn create_frame(&self, command: Command) -> Result<Bytes, ProtocolError> {
+            let mut reserved = [0; FragmentHeader::LEN];
+            if command.reserved().len() == FragmentHeader::LEN {
+                reserved.copy_from_slice(command.reserved());
+            }
+            let mut frame = vec![command.cmd_id()];
+            frame.extend_from_slice(&reserved);
+            frame.push(command.payload().len() as u8);
+            frame.extend_from_slice(command.payload());
+            Ok(frame.into())
+        }
+    }
+
+    impl ParseProtocol for TestDecoder {
+        fn parse_protocol_frame(&mut self, buf: &mut BytesMut) -> Option<Vec<Command>> {
+            let mut commands = Vec::new();
+            while buf.len() >= 6 && buf.len() >= 6 + usize::from(buf[5]) {
+                let len = 6 + usize::from(buf[5]);
+                let frame = buf.split_to(len).freeze();
+                let cmd_id = frame[0];
+                commands.push(Command::from_frame(frame, cmd_id, None, 1..5, 6..len));
+            }
+            (!commands.is_empty()).then_some(commands)
+        }
+    }
+
+    fn frames(encoder: &FragmentEncoder<TestEncoder>, command: Command) -> Vec<Bytes> {
+        let mut buf = encoder.create_frame(command).unwrap();
+        let mut frames = Vec::new();
+        while !buf.is_empty() {
+            frames.push(buf.split_to(6 + usize::from(buf[5])));
+        }
+        frames
+    }
+
+    #[test]
+    fn test_fragments_reassemble_out_of_order() {
+        let encoder = FragmentEncoder::new(TestEncoder, 4);
+        let mut decoder = ReassemblyDecoder::new(TestDecoder, Duration::from_secs(5));
+        let payload: Vec<u8> = (0..10).collect();
+        let mut frames = frames(&encoder, Command::new(0x42).with_payload(payload.clone()));
+        assert_eq!(frames.len(), 3);
+
+        frames.reverse();
+        let mut buf = BytesMut::new();
+        for frame in &frames[..2] {
+            buf.extend_from_slice(frame);
+        }
+        assert_eq!(decoder.parse_protocol_frame(&mut buf), None);
+
+        buf.extend_from_slice(&frames[2]);
+        let commands = decoder.parse_protocol_frame(&mut buf).unwrap();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].payload(), payload.as_slice());
+    }
+
+    #[test]
+    fn test_small_commands_are_not_fragmented() {
+        let encoder = FragmentEncoder::new(TestEncoder, 4);
+        let frame = encoder
+            .create_frame(Command::new(0x42).with_payload(vec![1, 2]))
+            .unwrap();
+        assert_eq!(frame.as_ref(), &[0x42, 0, 0, 0, 0, 2, 1, 2]);
+    }
+
+    #[test]
+    fn test_incomplete_message_times_out() {
+        let encoder = FragmentEncoder::new(TestEncoder, 4);
+        let mut decoder = ReassemblyDecoder::new(TestDecoder, Duration::ZERO);
+        let frames = frames(&encoder, Command::new(0x42).with_payload(vec![0; 8]));
+
+        let mut buf = BytesMut::from(frames[0].as_ref());
+        assert_eq!(decoder.parse_protocol_frame(&mut buf), None);
+        assert_eq!(decoder.parse_protocol_frame(&mut buf), None);
+        assert_eq!(
+            decoder.take_diagnostics(),
+            vec![Diagnostic::IncompleteMessage {
+                message_id: 0,
+                received: 1,
+                count: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn test_rejects_too_many_fragments() {
+        let encoder = FragmentEncoder::new(TestEncoder, 1);
+        assert_eq!(
+            encoder.create_frame(Command::new(0x42).with_payload(vec![0; 256])),
+            Err(ProtocolError::PayloadTooLarge { len: 256, max: 255 })
+        );
+    }
+}
@@ -1,3 +1,27 @@
+//! 协议无关的帧模型和编解码 trait.
+//!
+//! 默认的 `std` feature 提供桌面端的全部功能. 关闭默认 feature 后 crate 为 `no_std`:
+//! `alloc` feature 提供 `Command` 和编解码 trait, 不启用任何 feature 时只保留错误类型和校验函数,
+//! 以便嵌入式固件复用同一套协议定义.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod detect;
+#[cfg(feature = "std")]
+pub mod dynamic;
+#[cfg(feature = "std")]
+pub mod fragment;
+pub mod message;
+#[cfg(feature = "alloc")]
+pub mod raw;
+#[cfg(feature = "std")]
+pub mod security;
+#[cfg(feature = "serde")]
+pub mod serialize;
+#[cfg(feature = "alloc")]
 pub mod traits;
 pub mod types;
 pub mod utils;
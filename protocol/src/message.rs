@@ -0,0 +1,80 @@
+//! 类型化消息与命令字、响应状态和负载之间的转换.
+//!
+//! 协议的消息枚举通常使用 `#[derive(ProtocolMessage)]` 实现 [`ProtocolMessage`],
+//! 派生宏同时生成与 `Command` 之间的 `TryFrom` 转换 (需要 `alloc` feature).
+#[cfg(feature = "alloc")]
+use alloc::vec;
+
+#[cfg(feature = "alloc")]
+use crate::types::Command;
+use crate::types::ProtocolError;
+
+#[cfg(feature = "derive")]
+pub use protocol_derive::ProtocolMessage;
+
+/// 可以与命令字、响应状态和负载相互转换的类型化消息.
+///
+/// 这些方法只操作借用的字节, 不需要堆分配器, 因此固定缓冲区的编解码器也可以使用.
+pub trait ProtocolMessage: Sized {
+    /// 由命令字、响应状态和负载解析消息.
+    fn decode(
+        cmd_id: u8,
+        response_status: Option<u8>,
+        payload: &[u8],
+    ) -> Result<Self, ProtocolError>;
+
+    /// 消息的命令字.
+    fn cmd_id(&self) -> u8;
+
+    /// 消息的响应状态, 协议没有响应状态时为 `None`.
+    fn response_status(&self) -> Option<u8>;
+
+    /// 编码后的负载长度.
+    fn payload_len(&self) -> usize;
+
+    /// 将负载写入 `out`, 返回写入的字节数. `out` 不够长时返回 `FrameLength`.
+    fn encode_payload(&self, out: &mut [u8]) -> Result<usize, ProtocolError>;
+}
+
+/// 检查负载长度, 长度不符时返回带有期望和实际长度的错误.
+pub fn payload_of_len(cmd_id: u8, payload: &[u8], expected: usize) -> Result<&[u8], ProtocolError> {
+    if payload.len() != expected {
+        return Err(ProtocolError::PayloadLength {
+            cmd_id,
+            expected,
+            actual: payload.len(),
+        });
+    }
+    Ok(payload)
+}
+
+/// 将消息转换为 `Command`.
+#[cfg(feature = "alloc")]
+pub fn to_command<M: ProtocolMessage>(message: &M) -> Result<Command, ProtocolError> {
+    let mut payload = vec![0; message.payload_len()];
+    let len = message.encode_payload(&mut payload)?;
+    payload.truncate(len);
+    let command = Command::new(message.cmd_id()).with_payload(payload);
+    Ok(match message.response_status() {
+        Some(status) => command.with_status(status),
+        None => command,
+    })
+}
+
+/// 只在启用 `alloc` feature 时展开其中的内容, 供派生宏生成与 `Command` 之间的转换.
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __alloc_only {
+    ($($item:item)*) => {
+        $($item)*
+    };
+}
+
+/// 只在启用 `alloc` feature 时展开其中的内容, 供派生宏生成与 `Command` 之间的转换.
+#[cfg(not(feature = "alloc"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __alloc_only {
+    ($($item:item)*) => {};
+}
@@ -0,0 +1,73 @@
+use alloc::{vec, vec::Vec};
+
+use bytes::{Bytes, BytesMut};
+
+use crate::{
+    traits::{FrameGenerator, MessageProtocol, ParseProtocol, ProtocolSplit},
+    types::{Command, ProtocolError},
+};
+
+/// `RawProtocol` 是不做任何分帧的透传协议.
+///
+/// 每次读取到的全部字节都作为一个 `Command` 的负载交给上层, 发送时则原样写出负载.
+/// 适合调试未知设备或查看原始字节流.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RawProtocol;
+
+/// 透传协议的消息, 即原始字节.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawFrame(pub Bytes);
+
+impl TryFrom<Command> for RawFrame {
+    type Error = ProtocolError;
+
+    fn try_from(command: Command) -> Result<Self, Self::Error> {
+        Ok(Self(command.payload_bytes().clone()))
+    }
+}
+
+impl TryFrom<RawFrame> for Command {
+    type Error = ProtocolError;
+
+    fn try_from(frame: RawFrame) -> Result<Self, Self::Error> {
+        Ok(Command::new(0).with_payload(frame.0))
+    }
+}
+
+/// 透传协议的解码器.
+#[derive(Debug, Default)]
+pub struct RawDecoder;
+
+impl ParseProtocol for RawDecoder {
+    fn parse_protocol_frame(&mut self, buf: &mut BytesMut) -> Option<Vec<Command>> {
+        if buf.is_empty() {
+            return None;
+        }
+        let frame = buf.split().freeze();
+        let len = frame.len();
+        Some(vec![Command::from_frame(frame, 0, None, 0..0, 0..len)])
+    }
+}
+
+/// 透传协议的编码器.
+#[derive(Debug, Default)]
+pub struct RawEncoder;
+
+impl FrameGenerator for RawEncoder {
+    fn create_frame(&self, command: Command) -> Result<Bytes, ProtocolError> {
+        Ok(command.payload_bytes().clone())
+    }
+}
+
+impl ProtocolSplit for RawProtocol {
+    type Encoder = RawEncoder;
+    type Decode = RawDecoder;
+
+    fn into_split(self) -> (Self::Decode, Self::Encoder) {
+        (RawDecoder, RawEncoder)
+    }
+}
+
+impl MessageProtocol for RawProtocol {
+    type Message = RawFrame;
+}
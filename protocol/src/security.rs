@@ -0,0 +1,406 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::{Bytes, BytesMut};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tracing::info;
+
+use crate::{
+    traits::{FrameGenerator, MessageProtocol, ParseProtocol, ProtocolSplit},
+    types::{Command, Diagnostic, Diagnostics, ProtocolError},
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 计数器的长度.
+const COUNTER_LEN: usize = 8;
+/// 截断后的认证标签长度.
+const TAG_LEN: usize = 16;
+/// 附加在负载之后的安全尾部长度.
+pub const TRAILER_LEN: usize = COUNTER_LEN + TAG_LEN;
+
+/// 通信中的一方. 认证标签包含发送方的角色, 因此一方发出的帧被反射回来时无法通过校验.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// 上位机.
+    #[default]
+    Host,
+    /// 设备.
+    Device,
+}
+
+impl Role {
+    /// 通信的另一方.
+    pub fn peer(self) -> Self {
+        match self {
+            Self::Host => Self::Device,
+            Self::Device => Self::Host,
+        }
+    }
+}
+
+impl From<Role> for u8 {
+    fn from(role: Role) -> Self {
+        match role {
+            Role::Host => 0x01,
+            Role::Device => 0x02,
+        }
+    }
+}
+
+/// 预共享密钥, 编码器和解码器共享同一份.
+#[derive(Clone)]
+struct SharedKey(Arc<[u8]>);
+
+impl SharedKey {
+    /// 计算发送方角色、命令内容和计数器的认证标签.
+    ///
+    /// 标签覆盖发送方角色、命令字、响应状态、负载和计数器, 任何一项被篡改都会导致校验失败.
+    /// 编码前的命令和解码得到的命令在线上的表示可能不同 (例如请求没有响应状态, 解码后为 0;
+    /// 保留字段为空时底层协议写入填充字节), 因此响应状态按线上的形式取 0 作为缺省值,
+    /// 保留字段不参与计算.
+    fn mac(&self, sender: Role, command: &Command, payload: &[u8], counter: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.0).expect("HMAC 可以使用任意长度的密钥");
+        mac.update(&[sender.into()]);
+        mac.update(&[command.cmd_id()]);
+        mac.update(&[command.response_status().unwrap_or(0)]);
+        mac.update(payload);
+        mac.update(counter);
+        mac
+    }
+}
+
+/// `Secured` 为底层协议加上基于预共享密钥的消息认证 (HMAC-SHA256).
+///
+/// 发送时在每个命令的负载之后附加 8 字节的计数器和 16 字节的认证标签,
+/// 接收时校验标签并要求计数器严格递增, 以拒绝伪造、篡改和重放的帧.
+/// 被拒绝的帧不会交给上层, 而是记录为诊断事件.
+///
+/// 计数器取当前的 UNIX 时间 (微秒), 并保证严格递增. 解码器只接受与本机时间相差不超过
+/// 时间窗口的计数器, 因此即使是新建的解码器 (重新连接或重启后) 也会拒绝窗口之外录制的帧;
+/// 窗口之内的重放由严格递增的要求拒绝. 双方的时钟偏差必须小于时间窗口.
+/// 认证标签包含发送方的 [`Role`], 本方发出的帧被反射回来时会认证失败.
+///
+/// 这一层只做认证, 不加密负载. 保留字段不在认证范围内.
+pub struct Secured<P> {
+    /// 底层协议.
+    protocol: P,
+    /// 预共享密钥.
+    key: SharedKey,
+    /// 本方的角色.
+    role: Role,
+    /// 接收时允许的计数器与本机时间的最大偏差.
+    freshness: Duration,
+}
+
+impl<P> Secured<P> {
+    /// 默认的时间窗口.
+    const FRESHNESS: Duration = Duration::from_secs(30);
+
+    /// 使用预共享密钥包装底层协议, 本方角色为 [`Role::Host`].
+    pub fn new(protocol: P, key: impl AsRef<[u8]>) -> Self {
+        Self {
+            protocol,
+            key: SharedKey(key.as_ref().into()),
+            role: Role::default(),
+            freshness: Self::FRESHNESS,
+        }
+    }
+
+    /// 设置本方的角色. 编码器以该角色签名, 解码器只接受另一方签名的帧.
+    pub fn role(mut self, role: Role) -> Self {
+        self.role = role;
+        self
+    }
+
+    /// 设置接收时允许的计数器与本机时间的最大偏差.
+    pub fn freshness(mut self, freshness: Duration) -> Self {
+        self.freshness = freshness;
+        self
+    }
+}
+
+impl<P: ProtocolSplit> ProtocolSplit for Secured<P> {
+    type Encoder = SecureEncoder<P::Encoder>;
+    type Decode = SecureDecoder<P::Decode>;
+
+    fn into_split(self) -> (Self::Decode, Self::Encoder) {
+        let (decode, encoder) = self.protocol.into_split();
+        (
+            SecureDecoder {
+                inner: decode,
+                key: self.key.clone(),
+                sender: self.role.peer(),
+                freshness: self.freshness.as_micros() as u64,
+                last_counter: 0,
+                diagnostics: Diagnostics::default(),
+            },
+            SecureEncoder {
+                inner: encoder,
+                key: self.key,
+                role: self.role,
+                counter: AtomicU64::new(0),
+            },
+        )
+    }
+}
+
+impl<P: MessageProtocol> MessageProtocol for Secured<P> {
+    type Message = P::Message;
+}
+
+/// 当前的 UNIX 时间 (微秒), 用作计数器的时间基准.
+fn now_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(1, |elapsed| elapsed.as_micros() as u64)
+}
+
+/// `SecureEncoder` 为命令附加计数器和认证标签后交给底层编码器.
+pub struct SecureEncoder<E> {
+    /// 底层编码器.
+    inner: E,
+    /// 预共享密钥.
+    key: SharedKey,
+    /// 本方的角色.
+    role: Role,
+    /// 上一个帧使用的计数器.
+    counter: AtomicU64,
+}
+
+impl<E> SecureEncoder<E> {
+    /// 分配下一个计数器: 取当前时间, 但至少比上一个计数器大 1.
+    fn next_counter(&self) -> u64 {
+        let now = now_micros();
+        let next = |last: u64| last.saturating_add(1).max(now);
+        let last = self
+            .counter
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+                Some(next(last))
+            })
+            .unwrap_or_else(|last| last);
+        next(last)
+    }
+}
+
+impl<E: FrameGenerator> FrameGenerator for SecureEncoder<E> {
+    fn create_frame(&self, command: Command) -> Result<Bytes, ProtocolError> {
+        let counter = self.next_counter().to_be_bytes();
+        let tag = self
+            .key
+            .mac(self.role, &command, command.payload(), &counter)
+            .finalize()
+            .into_bytes();
+
+        let mut payload = BytesMut::with_capacity(command.payload().len() + TRAILER_LEN);
+        payload.extend_from_slice(command.payload());
+        payload.extend_from_slice(&counter);
+        payload.extend_from_slice(&tag[..TAG_LEN]);
+        self.inner
+            .create_frame(command.with_payload(payload.freeze()))
+    }
+}
+
+/// `SecureDecoder` 校验底层解码器产生的命令, 并去掉负载之后的安全尾部.
+pub struct SecureDecoder<D> {
+    /// 底层解码器.
+    inner: D,
+    /// 预共享密钥.
+    key: SharedKey,
+    /// 期望的发送方角色, 即本方的另一方.
+    sender: Role,
+    /// 计数器与本机时间允许的最大偏差 (微秒).
+    freshness: u64,
+    /// 最近一个通过校验的帧的计数器.
+    last_counter: u64,
+    /// 尚未被取出的诊断事件.
+    diagnostics: Diagnostics,
+}
+
+impl<D> SecureDecoder<D> {
+    /// 记录一个诊断事件.
+    fn report(&mut self, diagnostic: Diagnostic) {
+        info!("{}", diagnostic);
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// 校验一个命令, 通过时返回去掉安全尾部的命令.
+    fn verify(&mut self, command: Command) -> Option<Command> {
+        let cmd_id = command.cmd_id();
+        let payload = command.payload_bytes().clone();
+        let Some(data_len) = payload.len().checked_sub(TRAILER_LEN) else {
+            self.report(Diagnostic::AuthenticationFailed { cmd_id });
+            return None;
+        };
+        let (data, trailer) = payload.split_at(data_len);
+        let (counter, tag) = trailer.split_at(COUNTER_LEN);
+        if self
+            .key
+            .mac(self.sender, &command, data, counter)
+            .verify_truncated_left(tag)
+            .is_err()
+        {
+            self.report(Diagnostic::AuthenticationFailed { cmd_id });
+            return None;
+        }
+
+        let counter = u64::from_be_bytes(counter.try_into().ok()?);
+        let now = now_micros();
+        if counter.abs_diff(now) > self.freshness {
+            self.report(Diagnostic::StaleFrame {
+                cmd_id,
+                counter,
+                now,
+            });
+            return None;
+        }
+        if counter <= self.last_counter {
+            self.report(Diagnostic::ReplayedFrame {
+                cmd_id,
+                counter,
+                last_counter: self.last_counter,
+            });
+            return None;
+        }
+        self.last_counter = counter;
+        Some(command.with_payload(payload.slice(..data_len)))
+    }
+}
+
+impl<D: ParseProtocol> ParseProtocol for SecureDecoder<D> {
+    fn parse_protocol_frame(&mut self, buf: &mut BytesMut) -> Option<Vec<Command>> {
+        let commands: Vec<_> = self
+            .inner
+            .parse_protocol_frame(buf)?
+            .into_iter()
+            .filter_map(|command| self.verify(command))
+            .collect();
+        (!commands.is_empty()).then_some(commands)
+    }
+
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.inner.take_diagnostics();
+        diagnostics.extend(self.diagnostics.take());
+        diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw::RawProtocol;
+
+    const KEY: &[u8] = b"plant-network-key";
+
+    // 由上位机签名的帧.
+    fn frame(payload: &[u8]) -> Bytes {
+        let (_, encoder) = Secured::new(RawProtocol, KEY).into_split();
+        encoder
+            .create_frame(Command::new(0).with_payload(payload.to_vec()))
+            .unwrap()
+    }
+
+    // 设备一侧的解码器, 接受上位机签名的帧.
+    fn device() -> Secured<RawProtocol> {
+        Secured::new(RawProtocol, KEY).role(Role::Device)
+    }
+
+    fn decode(
+        decoder: &mut SecureDecoder<impl ParseProtocol>,
+        frame: &[u8],
+    ) -> Option<Vec<Command>> {
+        decoder.parse_protocol_frame(&mut BytesMut::from(frame))
+    }
+
+    #[test]
+    fn test_authenticated_round_trip() {
+        let (mut decoder, _) = device().into_split();
+        let frame = frame(b"move");
+        assert_eq!(frame.len(), 4 + TRAILER_LEN);
+
+        let commands = decode(&mut decoder, &frame).unwrap();
+        assert_eq!(commands[0].payload(), b"move");
+        assert!(decoder.take_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_tampered_and_foreign_frames() {
+        let (mut decoder, _) = Secured::new(RawProtocol, b"another-key")
+            .role(Role::Device)
+            .into_split();
+        assert_eq!(decode(&mut decoder, &frame(b"move")), None);
+
+        let (mut decoder, _) = device().into_split();
+        let mut tampered = frame(b"move").to_vec();
+        tampered[0] ^= 0x01;
+        assert_eq!(decode(&mut decoder, &tampered), None);
+        assert_eq!(decode(&mut decoder, b"short"), None);
+        assert_eq!(
+            decoder.take_diagnostics(),
+            vec![Diagnostic::AuthenticationFailed { cmd_id: 0 }; 2]
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_are_bounded() {
+        let (mut decoder, _) = device().into_split();
+        for _ in 0..Diagnostics::CAPACITY + 10 {
+            assert_eq!(decode(&mut decoder, b"short"), None);
+        }
+        assert_eq!(decoder.take_diagnostics().len(), Diagnostics::CAPACITY);
+    }
+
+    #[test]
+    fn test_rejects_reflected_frame() {
+        // 上位机发出的帧被反射回上位机自己的解码器
+        let (mut decoder, _) = Secured::new(RawProtocol, KEY).into_split();
+        assert_eq!(decode(&mut decoder, &frame(b"move")), None);
+        assert_eq!(
+            decoder.take_diagnostics(),
+            vec![Diagnostic::AuthenticationFailed { cmd_id: 0 }]
+        );
+    }
+
+    #[test]
+    fn test_rejects_replayed_frame() {
+        let (mut decoder, _) = device().into_split();
+        let frame = frame(b"move");
+        assert!(decode(&mut decoder, &frame).is_some());
+        assert_eq!(decode(&mut decoder, &frame), None);
+        assert!(matches!(
+            decoder.take_diagnostics()[..],
+            [Diagnostic::ReplayedFrame { counter, last_counter, .. }] if counter == last_counter
+        ));
+    }
+
+    #[test]
+    fn test_fresh_decoder_rejects_old_frame() {
+        let recorded = frame(b"move");
+        std::thread::sleep(Duration::from_millis(20));
+
+        // 重新连接后的新解码器没有之前的计数器, 只能依靠时间窗口拒绝录制的帧
+        let (mut decoder, _) = device().freshness(Duration::from_millis(10)).into_split();
+        assert_eq!(decode(&mut decoder, &recorded), None);
+        assert!(matches!(
+            decoder.take_diagnostics()[..],
+            [Diagnostic::StaleFrame { cmd_id: 0, .. }]
+        ));
+        assert!(decode(&mut decoder, &frame(b"move")).is_some());
+    }
+
+    #[test]
+    fn test_counter_follows_clock() {
+        let (_, encoder) = Secured::new(RawProtocol, KEY).into_split();
+        let before = now_micros();
+        let first = encoder.next_counter();
+        let second = encoder.next_counter();
+        assert!(first >= before);
+        assert!(second > first);
+    }
+}
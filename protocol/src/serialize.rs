@@ -0,0 +1,91 @@
+use bytes::Bytes;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::types::Command;
+
+/// 将字节序列化为以空格分隔的十六进制字符串, 如 `"55 AA 31"`.
+///
+/// 用于 `#[serde(with = "protocol::serialize::hex")]`, 反序列化时也接受不带空格的写法.
+pub mod hex {
+    use alloc::{format, string::String};
+
+    use bytes::Bytes;
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    use crate::utils::{from_hex, to_hex};
+
+    pub fn serialize<S: Serializer>(
+        bytes: impl AsRef<[u8]>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_hex(bytes.as_ref()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Bytes, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        from_hex(&text)
+            .map(Bytes::from)
+            .ok_or_else(|| D::Error::custom(format!("无效的十六进制字符串: {text}")))
+    }
+}
+
+/// `Command` 的序列化形式, 空的保留字段和原始帧会被省略.
+#[derive(Serialize, Deserialize)]
+struct CommandRepr {
+    cmd_id: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    response_status: Option<u8>,
+    #[serde(with = "hex", default, skip_serializing_if = "Bytes::is_empty")]
+    reserved: Bytes,
+    #[serde(with = "hex", default)]
+    payload: Bytes,
+    #[serde(with = "hex", default, skip_serializing_if = "Bytes::is_empty")]
+    frame: Bytes,
+}
+
+impl Serialize for Command {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        CommandRepr {
+            cmd_id: self.cmd_id(),
+            response_status: self.response_status(),
+            reserved: Bytes::copy_from_slice(self.reserved()),
+            payload: self.payload_bytes().clone(),
+            frame: self.frame().map(Bytes::copy_from_slice).unwrap_or_default(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Command {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = CommandRepr::deserialize(deserializer)?;
+        let command = Command::new(repr.cmd_id)
+            .with_reserved(repr.reserved)
+            .with_payload(repr.payload)
+            .with_frame(repr.frame);
+        Ok(match repr.response_status {
+            Some(status) => command.with_status(status),
+            None => command,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_command_json_round_trip() {
+        let command = Command::new(0x93)
+            .with_status(0x02)
+            .with_payload(vec![0x00, 0xAB]);
+        let json = serde_json::to_string(&command).unwrap();
+        assert_eq!(
+            json,
+            r#"{"cmd_id":147,"response_status":2,"payload":"00 AB"}"#
+        );
+        assert_eq!(serde_json::from_str::<Command>(&json).unwrap(), command);
+        assert!(serde_json::from_str::<Command>(r#"{"cmd_id":1,"payload":"0"}"#).is_err());
+    }
+}
@@ -1,7 +1,8 @@
+use alloc::vec::Vec;
+
 use bytes::{Buf, Bytes, BytesMut};
-use color_eyre::Result;
 
-use crate::types::{Command, ProtocolError};
+use crate::types::{Command, Diagnostic, ProtocolError};
 
 /// `ParseProtocol` trait 定义了一个通用的协议解析接口,
 /// 用于从字节流中解析出完整的协议帧.
@@ -23,6 +24,13 @@ pub trait ParseProtocol {
     /// 否则返回 `None`,表示缓冲区中没有足够的完整且有效的帧数据可供解析.
     fn parse_protocol_frame(&mut self, buf: &mut BytesMut) -> Option<Vec<Command>>;
 
+    /// 取出自上次调用以来解码器产生的诊断事件 (如校验失败、超长帧被丢弃).
+    ///
+    /// 默认实现不记录任何诊断事件.
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        Vec::new()
+    }
+
     /// 在缓冲区中查找协议帧的头部同步字.
     /// 如果找到, 会丢弃头部之前的所有数据, 并返回 `true`.
     fn find_frame_head(&self, buf: &mut bytes::BytesMut, head: &[u8]) -> bool {
@@ -69,3 +77,13 @@ pub trait ProtocolSplit {
     /// 这有助于满足 `tokio::spawn` 的 `'static` 生命周期要求.
     fn into_split(self) -> (Self::Decode, Self::Encoder);
 }
+
+/// `MessageProtocol` 为协议关联一个类型化的消息类型.
+///
+/// 解码得到的 `Command` 会被转换为 `Message` 再交给上层, 上层发送的 `Message`
+/// 也会先转换为 `Command` 再编码, 因此应用可以端到端地使用类型化消息,
+/// 转换失败时以 `ProtocolError` 报告.
+pub trait MessageProtocol: ProtocolSplit {
+    /// 协议的类型化消息, 如 BLN 协议的 `BlnProtocolType`.
+    type Message: TryFrom<Command, Error = ProtocolError> + TryInto<Command, Error = ProtocolError>;
+}
@@ -1,34 +1,263 @@
-use std::fmt::{Debug, Display};
+#[cfg(feature = "alloc")]
+use alloc::{
+    collections::VecDeque,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{Debug, Display};
+#[cfg(feature = "alloc")]
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "std")]
+use std::time::SystemTime;
 
-use bytes::BytesMut;
+#[cfg(feature = "alloc")]
+use bytes::Bytes;
 use thiserror::Error;
 
 /// 一个通用的命令结构体,作为协议特定类型 (如 `BlnProtocolType`) 和通用帧生成/解析逻辑之间的中间层.
-#[derive(Default, Debug, PartialEq)]
+///
+/// 由解码器产生的 `Command` 持有原始帧的冻结 `Bytes` 视图, 负载和保留字段都是该视图的切片,
+/// 因此解析一个帧不需要为命令字、负载或保留字段单独分配内存.
+/// 由程序构造的 `Command` 没有原始帧, 可通过 `with_*` 方法设置各个字段.
+#[cfg(feature = "alloc")]
+#[derive(Default, Debug, Clone)]
 pub struct Command {
-    /// 命令类型/ID, 通常是一个或多个字节.
-    pub cmd_type: BytesMut,
+    /// 命令类型/ID.
+    cmd_id: u8,
     /// 响应状态标志, 从长度字段的高位解析而来, 可选.
-    pub response_status: Option<u8>,
-    /// 命令的可选负载数据.
-    pub payload: Option<BytesMut>,
+    response_status: Option<u8>,
+    /// 帧中的保留字段.
+    reserved: Bytes,
+    /// 命令的负载数据, 没有负载时为空.
+    payload: Bytes,
+    /// 解码得到的完整原始帧, 由程序构造的命令为空.
+    frame: Bytes,
+}
+
+#[cfg(feature = "alloc")]
+impl Command {
+    /// 创建一个只有命令字的 `Command`.
+    pub fn new(cmd_id: u8) -> Self {
+        Self {
+            cmd_id,
+            ..Default::default()
+        }
+    }
+
+    /// 由解码器使用: 基于原始帧创建 `Command`, 保留字段和负载以帧内的字节范围给出.
+    ///
+    /// # Panics
+    /// 如果 `reserved` 或 `payload` 超出了 `frame` 的范围.
+    pub fn from_frame(
+        frame: Bytes,
+        cmd_id: u8,
+        response_status: Option<u8>,
+        reserved: Range<usize>,
+        payload: Range<usize>,
+    ) -> Self {
+        Self {
+            cmd_id,
+            response_status,
+            reserved: frame.slice(reserved),
+            payload: frame.slice(payload),
+            frame,
+        }
+    }
+
+    /// 设置响应状态.
+    pub fn with_status(mut self, response_status: u8) -> Self {
+        self.response_status = Some(response_status);
+        self
+    }
+
+    /// 设置负载数据.
+    pub fn with_payload(mut self, payload: impl Into<Bytes>) -> Self {
+        self.payload = payload.into();
+        self
+    }
+
+    /// 设置保留字段.
+    pub fn with_reserved(mut self, reserved: impl Into<Bytes>) -> Self {
+        self.reserved = reserved.into();
+        self
+    }
+
+    /// 设置原始帧, 用于还原序列化前的命令.
+    #[cfg(feature = "serde")]
+    pub(crate) fn with_frame(mut self, frame: Bytes) -> Self {
+        self.frame = frame;
+        self
+    }
+
+    /// 命令类型/ID.
+    pub fn cmd_id(&self) -> u8 {
+        self.cmd_id
+    }
+
+    /// 响应状态标志.
+    pub fn response_status(&self) -> Option<u8> {
+        self.response_status
+    }
+
+    /// 负载数据, 没有负载时为空切片.
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// 负载数据的 `Bytes` 视图, 克隆它不会复制数据.
+    pub fn payload_bytes(&self) -> &Bytes {
+        &self.payload
+    }
+
+    /// 帧中的保留字段.
+    pub fn reserved(&self) -> &[u8] {
+        &self.reserved
+    }
+
+    /// 解码得到的完整原始帧, 由程序构造的命令返回 `None`.
+    pub fn frame(&self) -> Option<&[u8]> {
+        (!self.frame.is_empty()).then_some(self.frame.as_ref())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl PartialEq for Command {
+    /// 只比较命令的逻辑内容, 不比较原始帧.
+    fn eq(&self, other: &Self) -> bool {
+        self.cmd_id == other.cmd_id
+            && self.response_status == other.response_status
+            && self.reserved == other.reserved
+            && self.payload == other.payload
+    }
 }
+
+#[cfg(feature = "alloc")]
 impl Display for Command {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let Some(ref payload) = self.payload {
-            write!(
-                f,
-                "Command {{ cmd_type: {:02X}, response_status: {:?}, payload: {:02X?} }}",
-                self.cmd_type,
-                self.response_status,
-                payload.as_ref(),
-            )
-        } else {
-            write!(
-                f,
-                "Command {{ cmd_type: {:02X}, response_status: {:?}, payload: {:?} }}",
-                self.cmd_type, self.response_status, self.payload
-            )
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Command {{ cmd_id: {:02X}, response_status: {:?}, payload: {:02X?} }}",
+            self.cmd_id,
+            self.response_status,
+            self.payload.as_ref(),
+        )
+    }
+}
+
+/// 帧的传输方向.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// 从设备接收的帧.
+    Rx,
+    /// 发送给设备的帧.
+    Tx,
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Rx => write!(f, "RX"),
+            Self::Tx => write!(f, "TX"),
+        }
+    }
+}
+
+/// 一条连接在进程内的唯一标识, 用于区分来自不同连接的帧.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ConnectionId(u64);
+
+#[cfg(feature = "std")]
+impl ConnectionId {
+    /// 分配一个新的连接标识, 进程内单调递增.
+    pub fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// 连接标识的数值.
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(feature = "std")]
+impl Display for ConnectionId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// 一条收发的帧记录.
+///
+/// 除了帧对应的消息外, 它还保留了原始帧 (包括保留字段和校验码)、时间戳、
+/// 传输方向和连接标识, 以便日志视图和导出同时展示解码结果和十六进制原文.
+/// 消息类型默认为通用的 `Command`, 可通过 [`FrameRecord::decode`] 转换为协议的类型化消息.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameRecord<M = Command> {
+    /// 帧的传输方向.
+    pub direction: Direction,
+    /// 帧所属的连接.
+    pub connection_id: ConnectionId,
+    /// 接收或发送该帧的时间.
+    pub timestamp: SystemTime,
+    /// 原始帧字节.
+    pub raw: Bytes,
+    /// 帧对应的消息.
+    pub message: M,
+}
+
+/// 携带类型化消息或其转换错误的帧记录.
+#[cfg(feature = "std")]
+pub type MessageRecord<M> = FrameRecord<Result<M, ProtocolError>>;
+
+#[cfg(feature = "std")]
+impl FrameRecord {
+    /// 为刚解码的命令创建一条接收记录, 时间戳为当前时间.
+    pub fn rx(connection_id: ConnectionId, command: Command) -> Self {
+        Self {
+            direction: Direction::Rx,
+            connection_id,
+            timestamp: SystemTime::now(),
+            raw: command.frame.clone(),
+            message: command,
+        }
+    }
+
+    /// 将记录中的 `Command` 转换为类型化消息, 转换失败时保留错误.
+    pub fn decode<M>(self) -> MessageRecord<M>
+    where
+        M: TryFrom<Command, Error = ProtocolError>,
+    {
+        self.map(M::try_from)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<M> FrameRecord<M> {
+    /// 为刚发送的消息创建一条发送记录, `frame` 为编码后实际写出的字节.
+    pub fn tx(connection_id: ConnectionId, message: M, frame: impl Into<Bytes>) -> Self {
+        Self {
+            direction: Direction::Tx,
+            connection_id,
+            timestamp: SystemTime::now(),
+            raw: frame.into(),
+            message,
+        }
+    }
+
+    /// 转换记录中的消息, 保留其余字段.
+    pub fn map<N>(self, f: impl FnOnce(M) -> N) -> FrameRecord<N> {
+        FrameRecord {
+            direction: self.direction,
+            connection_id: self.connection_id,
+            timestamp: self.timestamp,
+            raw: self.raw,
+            message: f(self.message),
         }
     }
 }
@@ -36,12 +265,222 @@ impl Display for Command {
 /// 定义了在协议处理过程中可能发生的通用错误.
 ///
 /// 作为一个通用的错误枚举,它可以用于表示来自不同协议实现的错误,
-#[derive(Error, Debug, PartialEq, Eq)]
+/// 每个变体都携带了定位问题所需的上下文 (如命令字、期望与实际的长度).
+/// `Display` 输出中文信息, 英文信息可通过 [`ProtocolError::message`] 获取.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum ProtocolError {
     #[error("命令不适用于此协议")]
     CommandNotApplicable,
-    #[error("此协议的命令类型无效")]
-    InvalidCommandType,
-    #[error("此命令的负载无效")]
-    InvalidPayload,
+    #[error("此协议的命令类型无效: {cmd_id:#04X}")]
+    InvalidCommandType { cmd_id: u8 },
+    #[error("命令 {cmd_id:#04X} 的负载长度无效: 期望 {expected}, 实际 {actual}")]
+    PayloadLength {
+        cmd_id: u8,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("命令 {cmd_id:#04X} 的响应状态不符: {status:#04X}")]
+    UnexpectedStatus { cmd_id: u8, status: u8 },
+    #[error("命令 {cmd_id:#04X} 缺少响应状态")]
+    MissingStatus { cmd_id: u8 },
+    #[error("命令 {cmd_id:#04X} 的保留字段长度无效: 期望 {expected}, 实际 {actual}")]
+    ReservedLength {
+        cmd_id: u8,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("负载长度 {len} 超过上限 {max}")]
+    PayloadTooLarge { len: usize, max: usize },
+    #[error("缺少帧头")]
+    MissingFrameHead,
+    #[error("帧长度无效: 期望 {expected}, 实际 {actual}")]
+    FrameLength { expected: usize, actual: usize },
+    #[error("校验码不匹配: 期望 {expected:#04X}, 实际 {actual:#04X}")]
+    ChecksumMismatch { expected: u8, actual: u8 },
+    #[cfg(feature = "alloc")]
+    #[error("未注册的协议: {0}")]
+    UnknownProtocol(String),
+}
+
+/// 错误信息使用的语言.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    /// 中文, 与 `Display` 输出一致.
+    #[default]
+    Chinese,
+    /// 英文.
+    English,
+}
+
+#[cfg(feature = "alloc")]
+impl ProtocolError {
+    /// 以指定语言返回错误信息.
+    pub fn message(&self, language: Language) -> String {
+        if language == Language::Chinese {
+            return self.to_string();
+        }
+        match self {
+            Self::CommandNotApplicable => "command is not applicable to this protocol".to_string(),
+            Self::InvalidCommandType { cmd_id } => {
+                format!("invalid command type for this protocol: {cmd_id:#04X}")
+            }
+            Self::PayloadLength {
+                cmd_id,
+                expected,
+                actual,
+            } => format!(
+                "invalid payload length for command {cmd_id:#04X}: expected {expected}, got {actual}"
+            ),
+            Self::UnexpectedStatus { cmd_id, status } => {
+                format!("unexpected response status for command {cmd_id:#04X}: {status:#04X}")
+            }
+            Self::MissingStatus { cmd_id } => {
+                format!("missing response status for command {cmd_id:#04X}")
+            }
+            Self::ReservedLength {
+                cmd_id,
+                expected,
+                actual,
+            } => format!(
+                "invalid reserved field length for command {cmd_id:#04X}: expected {expected}, got {actual}"
+            ),
+            Self::PayloadTooLarge { len, max } => {
+                format!("payload length {len} exceeds the limit of {max}")
+            }
+            Self::MissingFrameHead => "missing frame head".to_string(),
+            Self::FrameLength { expected, actual } => {
+                format!("invalid frame length: expected {expected}, got {actual}")
+            }
+            Self::ChecksumMismatch { expected, actual } => {
+                format!("checksum mismatch: expected {expected:#04X}, got {actual:#04X}")
+            }
+            #[cfg(feature = "alloc")]
+            Self::UnknownProtocol(name) => format!("unknown protocol: {name}"),
+        }
+    }
+}
+
+/// 解码过程中产生的诊断事件.
+///
+/// 解码器在丢弃数据时会记录诊断事件, 以便上层将其报告出来, 而不是静默地丢弃.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// 帧的块校验码不匹配, 该帧头已被跳过.
+    ChecksumMismatch { expected: u8, actual: u8 },
+    /// 帧声明的负载长度超过了上限, 该帧头已被跳过.
+    OversizeFrame {
+        payload_len: usize,
+        max_payload_len: usize,
+    },
+    /// 分片消息未能在超时前到齐, 已收到的分片已被丢弃.
+    IncompleteMessage {
+        message_id: u16,
+        received: usize,
+        count: usize,
+    },
+    /// 收到了重复的响应, 已被丢弃.
+    DuplicateFrame { sequence: u16 },
+    /// 响应的序列号跳过了一段, 中间的响应可能已丢失.
+    SequenceGap { expected: u16, actual: u16 },
+    /// 响应的序列号找不到对应的请求.
+    UnmatchedResponse { sequence: u16 },
+    /// 帧的认证标签校验失败 (伪造、篡改或密钥不符), 已被丢弃.
+    AuthenticationFailed { cmd_id: u8 },
+    /// 帧的计数器没有递增, 视为重放并丢弃.
+    ReplayedFrame {
+        cmd_id: u8,
+        counter: u64,
+        last_counter: u64,
+    },
+    /// 帧的计数器与本机时间相差超过时间窗口, 视为重放 (或时钟偏差过大) 并丢弃.
+    StaleFrame { cmd_id: u8, counter: u64, now: u64 },
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::ChecksumMismatch { expected, actual } => {
+                write!(f, "校验码不匹配: 期望 {expected:02X}, 实际 {actual:02X}")
+            }
+            Self::OversizeFrame {
+                payload_len,
+                max_payload_len,
+            } => write!(
+                f,
+                "帧负载长度 {payload_len} 超过上限 {max_payload_len}, 已跳过帧头"
+            ),
+            Self::IncompleteMessage {
+                message_id,
+                received,
+                count,
+            } => write!(
+                f,
+                "分片消息 {message_id} 不完整 (收到 {received}/{count}), 已丢弃"
+            ),
+            Self::DuplicateFrame { sequence } => {
+                write!(f, "序列号 {sequence} 的响应重复, 已丢弃")
+            }
+            Self::SequenceGap { expected, actual } => {
+                write!(f, "序列号跳跃: 期望 {expected}, 实际 {actual}")
+            }
+            Self::UnmatchedResponse { sequence } => {
+                write!(f, "序列号 {sequence} 的响应没有对应的请求")
+            }
+            Self::AuthenticationFailed { cmd_id } => {
+                write!(f, "命令 {cmd_id:#04X} 的帧认证失败, 已丢弃")
+            }
+            Self::ReplayedFrame {
+                cmd_id,
+                counter,
+                last_counter,
+            } => write!(
+                f,
+                "命令 {cmd_id:#04X} 的帧计数器 {counter} 未超过 {last_counter}, 视为重放并丢弃"
+            ),
+            Self::StaleFrame {
+                cmd_id,
+                counter,
+                now,
+            } => write!(
+                f,
+                "命令 {cmd_id:#04X} 的帧计数器 {counter} 与本机时间 {now} 相差超过时间窗口, 视为重放并丢弃"
+            ),
+        }
+    }
+}
+
+/// 解码器尚未被取出的诊断事件.
+///
+/// 最多保留 [`Diagnostics::CAPACITY`] 个事件, 超出后丢弃最旧的事件, 因此对端持续发送错误数据
+/// 而上层迟迟不取出事件时, 占用的内存也不会无限增长.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Default, Clone)]
+pub struct Diagnostics(VecDeque<Diagnostic>);
+
+#[cfg(feature = "alloc")]
+impl Diagnostics {
+    /// 保留的诊断事件的最大数量.
+    pub const CAPACITY: usize = 64;
+
+    /// 记录一个诊断事件, 已满时丢弃最旧的事件.
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        if self.0.len() == Self::CAPACITY {
+            self.0.pop_front();
+        }
+        self.0.push_back(diagnostic);
+    }
+
+    /// 按发生顺序取出所有诊断事件.
+    pub fn take(&mut self) -> Vec<Diagnostic> {
+        core::mem::take(&mut self.0).into()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Extend<Diagnostic> for Diagnostics {
+    fn extend<I: IntoIterator<Item = Diagnostic>>(&mut self, iter: I) {
+        for diagnostic in iter {
+            self.push(diagnostic);
+        }
+    }
 }
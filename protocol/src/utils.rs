@@ -1,3 +1,8 @@
+#[cfg(feature = "alloc")]
+use alloc::{format, string::String, vec::Vec};
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
 /// 计算给定数据的块校验码 (BCC).
 ///
 /// BCC 是通过对数据进行异或 (XOR) 累加计算得出的.
@@ -10,3 +15,47 @@
 pub fn calculate_bcc(data: &[u8]) -> u8 {
     data.iter().fold(0, |bcc, &byte| bcc ^ byte)
 }
+
+/// 将字节格式化为以空格分隔的大写十六进制字符串, 如 `55 AA 31`.
+#[cfg(feature = "alloc")]
+pub fn to_hex(data: &[u8]) -> String {
+    data.iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 解析十六进制字符串, 字节之间可以有空白, 如 `55 AA 31` 或 `55aa31`.
+///
+/// 含有非十六进制字符或位数为奇数时返回 `None`.
+#[cfg(feature = "alloc")]
+pub fn from_hex(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<_>>()?;
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    Some(
+        digits
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect(),
+    )
+}
+
+/// 将时间格式化为 UTC 的 `HH:MM:SS.mmm`, 用于日志中的时间戳.
+#[cfg(feature = "std")]
+pub fn time_of_day(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs() % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        since_epoch.subsec_millis()
+    )
+}
@@ -0,0 +1,398 @@
+use std::{sync::Arc, time::Duration};
+
+use bln::{
+    client::{BlnClient, BlnClientError},
+    protocol::types::BlnConfigId,
+};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyList};
+use stream::client::{NetClient, connect};
+use tokio::{
+    net::tcp::{OwnedReadHalf, OwnedWriteHalf},
+    runtime::{Builder, Runtime},
+    sync::Mutex,
+    time,
+};
+
+use crate::{
+    codec::Message,
+    errors::{BlnError, client_error},
+};
+
+/// 基于 TCP 连接的 BLN 客户端.
+type TcpClient = BlnClient<OwnedReadHalf, OwnedWriteHalf>;
+
+/// 将 Python 传入的秒数转换为 `Duration`.
+fn seconds(value: f64) -> PyResult<Duration> {
+    Duration::try_from_secs_f64(value).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// 将配置项转换为 ID 列表. `Vec<u8>` 会被转换为 `bytes`, 因此显式构造列表.
+fn config_ids(py: Python<'_>, items: Vec<BlnConfigId>) -> PyResult<Bound<'_, PyList>> {
+    PyList::new(py, items.into_iter().map(u8::from))
+}
+
+/// 建立连接并创建客户端, `timeout` 同时用作连接超时和请求的响应超时.
+async fn open(addr: String, timeout: Duration) -> Result<TcpClient, BlnClientError> {
+    let stream: NetClient = connect(addr, timeout)
+        .await
+        .map_err(BlnClientError::Transport)?;
+    Ok(BlnClient::new(stream).timeout(timeout))
+}
+
+/// 接收下一条消息, 给出 `timeout` 时超时返回 `Timeout`.
+async fn recv(
+    client: &mut TcpClient,
+    timeout: Option<Duration>,
+) -> Result<Message, BlnClientError> {
+    let message = match timeout {
+        Some(timeout) => time::timeout(timeout, client.recv())
+            .await
+            .map_err(|_| BlnClientError::Timeout)??,
+        None => client.recv().await?,
+    };
+    Ok(Message(message))
+}
+
+/// 阻塞式的 BLN 客户端, 每次调用都等待设备响应后返回.
+///
+/// 等待期间释放 GIL, 其他 Python 线程可以继续运行.
+#[pyclass(name = "BlnClient", module = "pybln")]
+pub struct Client {
+    /// 驱动客户端的单线程运行时.
+    runtime: Runtime,
+    /// BLN 客户端.
+    client: TcpClient,
+}
+
+#[pymethods]
+impl Client {
+    /// 连接到设备或模拟器, 如 `BlnClient.connect("127.0.0.1:5006")`.
+    #[staticmethod]
+    #[pyo3(signature = (addr, timeout = 1.0))]
+    fn connect(py: Python<'_>, addr: String, timeout: f64) -> PyResult<Self> {
+        let timeout = seconds(timeout)?;
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| BlnError::new_err(e.to_string()))?;
+        let client = py
+            .allow_threads(|| runtime.block_on(open(addr, timeout)))
+            .map_err(client_error)?;
+        Ok(Self { runtime, client })
+    }
+
+    /// 移动到指定位置并等待到达, 返回设备回报的实际位置.
+    fn move_to(&mut self, py: Python<'_>, x: f32, y: f32) -> PyResult<(f32, f32)> {
+        py.allow_threads(|| self.runtime.block_on(self.client.move_to(x, y)))
+            .map_err(client_error)
+    }
+
+    /// 查询设备的当前位置, 返回 `(x, y, status)`, `status` 为设备状态字节.
+    fn position(&mut self, py: Python<'_>) -> PyResult<(f32, f32, u8)> {
+        let (x, y, status) = py
+            .allow_threads(|| self.runtime.block_on(self.client.position()))
+            .map_err(client_error)?;
+        Ok((x, y, status.into()))
+    }
+
+    /// 急停, 设备立即停止所有轴.
+    fn emergency_stop(&mut self, py: Python<'_>) -> PyResult<()> {
+        py.allow_threads(|| self.runtime.block_on(self.client.emergency_stop()))
+            .map_err(client_error)
+    }
+
+    /// 减速停止当前的运动, 返回停止时的位置.
+    fn stop(&mut self, py: Python<'_>) -> PyResult<(f32, f32)> {
+        py.allow_threads(|| self.runtime.block_on(self.client.stop()))
+            .map_err(client_error)
+    }
+
+    /// 回零并等待完成, 返回回零后的位置.
+    fn home(&mut self, py: Python<'_>) -> PyResult<(f32, f32)> {
+        py.allow_threads(|| self.runtime.block_on(self.client.home()))
+            .map_err(client_error)
+    }
+
+    /// 从当前位置移动给定的位移并等待到达, 返回到达后的位置.
+    fn move_by(&mut self, py: Python<'_>, dx: f32, dy: f32) -> PyResult<(f32, f32)> {
+        py.allow_threads(|| self.runtime.block_on(self.client.move_by(dx, dy)))
+            .map_err(client_error)
+    }
+
+    /// 以给定速度点动一个轴, `axis` 为 0 (X 轴) 或 1 (Y 轴), 速度的符号表示方向.
+    fn jog_start(&mut self, py: Python<'_>, axis: u8, velocity: f32) -> PyResult<()> {
+        py.allow_threads(|| {
+            self.runtime
+                .block_on(self.client.jog_start(axis.into(), velocity))
+        })
+        .map_err(client_error)
+    }
+
+    /// 停止一个轴的点动, 返回停止时的位置.
+    fn jog_stop(&mut self, py: Python<'_>, axis: u8) -> PyResult<(f32, f32)> {
+        py.allow_threads(|| self.runtime.block_on(self.client.jog_stop(axis.into())))
+            .map_err(client_error)
+    }
+
+    /// 设置之后的运动使用的速度, 不会写入闪存.
+    fn set_velocity(&mut self, py: Python<'_>, velocity: f32) -> PyResult<()> {
+        py.allow_threads(|| self.runtime.block_on(self.client.set_velocity(velocity)))
+            .map_err(client_error)
+    }
+
+    /// 读取一个配置项的值, `item` 为配置项 ID.
+    fn read_config(&mut self, py: Python<'_>, item: u8) -> PyResult<f32> {
+        py.allow_threads(|| self.runtime.block_on(self.client.read_config(item.into())))
+            .map_err(client_error)
+    }
+
+    /// 写入一个配置项的值.
+    fn write_config(&mut self, py: Python<'_>, item: u8, value: f32) -> PyResult<()> {
+        py.allow_threads(|| {
+            self.runtime
+                .block_on(self.client.write_config(item.into(), value))
+        })
+        .map_err(client_error)
+    }
+
+    /// 列出设备支持的配置项 ID.
+    fn list_config<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        let items = py
+            .allow_threads(|| self.runtime.block_on(self.client.list_config()))
+            .map_err(client_error)?;
+        config_ids(py, items)
+    }
+
+    /// 将当前配置保存到设备的闪存.
+    fn save_config(&mut self, py: Python<'_>) -> PyResult<()> {
+        py.allow_threads(|| self.runtime.block_on(self.client.save_config()))
+            .map_err(client_error)
+    }
+
+    /// 发送一条消息, 不等待响应.
+    fn send(&mut self, py: Python<'_>, message: Message) -> PyResult<()> {
+        py.allow_threads(|| self.runtime.block_on(self.client.send(message.0)))
+            .map_err(client_error)
+    }
+
+    /// 接收下一条消息, 给出 `timeout` (秒) 时超时抛出 `TimeoutError`.
+    #[pyo3(signature = (timeout = None))]
+    fn recv(&mut self, py: Python<'_>, timeout: Option<f64>) -> PyResult<Message> {
+        let timeout = timeout.map(seconds).transpose()?;
+        py.allow_threads(|| self.runtime.block_on(recv(&mut self.client, timeout)))
+            .map_err(client_error)
+    }
+}
+
+/// 异步的 BLN 客户端, 方法返回可以在 `asyncio` 中等待的对象.
+///
+/// 同一个客户端上的调用按顺序执行.
+#[pyclass(name = "AsyncBlnClient", module = "pybln", frozen)]
+pub struct AsyncClient {
+    /// BLN 客户端, 在各个调用之间共享.
+    client: Arc<Mutex<TcpClient>>,
+}
+
+#[pymethods]
+impl AsyncClient {
+    /// 连接到设备或模拟器, 如 `await AsyncBlnClient.connect("127.0.0.1:5006")`.
+    #[staticmethod]
+    #[pyo3(signature = (addr, timeout = 1.0))]
+    fn connect(py: Python<'_>, addr: String, timeout: f64) -> PyResult<Bound<'_, PyAny>> {
+        let timeout = seconds(timeout)?;
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let client = open(addr, timeout).await.map_err(client_error)?;
+            Ok(Self {
+                client: Arc::new(Mutex::new(client)),
+            })
+        })
+    }
+
+    /// 移动到指定位置并等待到达, 返回设备回报的实际位置.
+    fn move_to<'py>(&self, py: Python<'py>, x: f32, y: f32) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            client
+                .lock()
+                .await
+                .move_to(x, y)
+                .await
+                .map_err(client_error)
+        })
+    }
+
+    /// 查询设备的当前位置, 返回 `(x, y, status)`, `status` 为设备状态字节.
+    fn position<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let position = client.lock().await.position().await;
+            let (x, y, status) = position.map_err(client_error)?;
+            Ok((x, y, u8::from(status)))
+        })
+    }
+
+    /// 急停, 设备立即停止所有轴.
+    fn emergency_stop<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            client
+                .lock()
+                .await
+                .emergency_stop()
+                .await
+                .map_err(client_error)
+        })
+    }
+
+    /// 减速停止当前的运动, 返回停止时的位置.
+    fn stop<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            client.lock().await.stop().await.map_err(client_error)
+        })
+    }
+
+    /// 回零并等待完成, 返回回零后的位置.
+    fn home<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            client.lock().await.home().await.map_err(client_error)
+        })
+    }
+
+    /// 从当前位置移动给定的位移并等待到达, 返回到达后的位置.
+    fn move_by<'py>(&self, py: Python<'py>, dx: f32, dy: f32) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            client
+                .lock()
+                .await
+                .move_by(dx, dy)
+                .await
+                .map_err(client_error)
+        })
+    }
+
+    /// 以给定速度点动一个轴, `axis` 为 0 (X 轴) 或 1 (Y 轴), 速度的符号表示方向.
+    fn jog_start<'py>(
+        &self,
+        py: Python<'py>,
+        axis: u8,
+        velocity: f32,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            client
+                .lock()
+                .await
+                .jog_start(axis.into(), velocity)
+                .await
+                .map_err(client_error)
+        })
+    }
+
+    /// 停止一个轴的点动, 返回停止时的位置.
+    fn jog_stop<'py>(&self, py: Python<'py>, axis: u8) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            client
+                .lock()
+                .await
+                .jog_stop(axis.into())
+                .await
+                .map_err(client_error)
+        })
+    }
+
+    /// 设置之后的运动使用的速度, 不会写入闪存.
+    fn set_velocity<'py>(&self, py: Python<'py>, velocity: f32) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            client
+                .lock()
+                .await
+                .set_velocity(velocity)
+                .await
+                .map_err(client_error)
+        })
+    }
+
+    /// 读取一个配置项的值, `item` 为配置项 ID.
+    fn read_config<'py>(&self, py: Python<'py>, item: u8) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            client
+                .lock()
+                .await
+                .read_config(item.into())
+                .await
+                .map_err(client_error)
+        })
+    }
+
+    /// 写入一个配置项的值.
+    fn write_config<'py>(
+        &self,
+        py: Python<'py>,
+        item: u8,
+        value: f32,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            client
+                .lock()
+                .await
+                .write_config(item.into(), value)
+                .await
+                .map_err(client_error)
+        })
+    }
+
+    /// 列出设备支持的配置项 ID.
+    fn list_config<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let items = client.lock().await.list_config().await;
+            let items = items.map_err(client_error)?;
+            Python::with_gil(|py| config_ids(py, items).map(Bound::unbind))
+        })
+    }
+
+    /// 将当前配置保存到设备的闪存.
+    fn save_config<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            client
+                .lock()
+                .await
+                .save_config()
+                .await
+                .map_err(client_error)
+        })
+    }
+
+    /// 发送一条消息, 不等待响应.
+    fn send<'py>(&self, py: Python<'py>, message: Message) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            client
+                .lock()
+                .await
+                .send(message.0)
+                .await
+                .map_err(client_error)
+        })
+    }
+
+    /// 接收下一条消息, 给出 `timeout` (秒) 时超时抛出 `TimeoutError`.
+    #[pyo3(signature = (timeout = None))]
+    fn recv<'py>(&self, py: Python<'py>, timeout: Option<f64>) -> PyResult<Bound<'py, PyAny>> {
+        let timeout = timeout.map(seconds).transpose()?;
+        let client = self.client.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            recv(&mut *client.lock().await, timeout)
+                .await
+                .map_err(client_error)
+        })
+    }
+}
@@ -0,0 +1,449 @@
+use bln::protocol::types::{
+    BlnCommandDecode, BlnCommandEncoder, BlnConfigId, BlnDeviceStatus, BlnProtocolType,
+};
+use bytes::BytesMut;
+use protocol::{
+    traits::{FrameGenerator, ParseProtocol},
+    types::Command,
+};
+use pyo3::{
+    prelude::*,
+    types::{PyBytes, PyList},
+};
+
+use crate::errors::protocol_error;
+
+/// 一条 BLN 消息, 对应 Rust 端的 `BlnProtocolType`.
+///
+/// 通过静态方法构造, 如 `BlnProtocolType.set_position_rsq(1.0, 2.0)`,
+/// `kind` 为变体名, 其余字段在变体不携带时为 `None`.
+#[pyclass(name = "BlnProtocolType", module = "pybln", frozen, eq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Message(pub BlnProtocolType);
+
+#[pymethods]
+impl Message {
+    #[staticmethod]
+    fn set_position_rsq(x: f32, y: f32) -> Self {
+        Self(BlnProtocolType::SetPositionRsq(x, y))
+    }
+
+    #[staticmethod]
+    fn set_position_rsp() -> Self {
+        Self(BlnProtocolType::SetPositionRsp)
+    }
+
+    #[staticmethod]
+    fn position_reached(x: f32, y: f32) -> Self {
+        Self(BlnProtocolType::PositionReached(x, y))
+    }
+
+    #[staticmethod]
+    fn get_position_rsq() -> Self {
+        Self(BlnProtocolType::GetPositionRsq)
+    }
+
+    #[staticmethod]
+    fn get_position_rsp(x: f32, y: f32, status: u8) -> Self {
+        Self(BlnProtocolType::GetPositionRsp(x, y, status.into()))
+    }
+
+    #[staticmethod]
+    fn read_config_rsq(item: u8) -> Self {
+        Self(BlnProtocolType::ReadConfigRsq(item.into()))
+    }
+
+    #[staticmethod]
+    fn read_config_rsp(item: u8, value: f32) -> Self {
+        Self(BlnProtocolType::ReadConfigRsp(item.into(), value))
+    }
+
+    #[staticmethod]
+    fn write_config_rsq(item: u8, value: f32) -> Self {
+        Self(BlnProtocolType::WriteConfigRsq(item.into(), value))
+    }
+
+    #[staticmethod]
+    fn write_config_rsp(item: u8) -> Self {
+        Self(BlnProtocolType::WriteConfigRsp(item.into()))
+    }
+
+    #[staticmethod]
+    fn list_config_rsq() -> Self {
+        Self(BlnProtocolType::ListConfigRsq)
+    }
+
+    #[staticmethod]
+    fn list_config_rsp(items: Vec<u8>) -> Self {
+        let items = items.into_iter().map(BlnConfigId::from);
+        Self(BlnProtocolType::ListConfigRsp(BlnConfigId::to_mask(items)))
+    }
+
+    #[staticmethod]
+    fn save_config_rsq() -> Self {
+        Self(BlnProtocolType::SaveConfigRsq)
+    }
+
+    #[staticmethod]
+    fn save_config_rsp() -> Self {
+        Self(BlnProtocolType::SaveConfigRsp)
+    }
+
+    #[staticmethod]
+    fn emergency_stop_rsq() -> Self {
+        Self(BlnProtocolType::EmergencyStopRsq)
+    }
+
+    #[staticmethod]
+    fn emergency_stop_rsp() -> Self {
+        Self(BlnProtocolType::EmergencyStopRsp)
+    }
+
+    #[staticmethod]
+    fn stop_rsq() -> Self {
+        Self(BlnProtocolType::StopRsq)
+    }
+
+    #[staticmethod]
+    fn stop_rsp() -> Self {
+        Self(BlnProtocolType::StopRsp)
+    }
+
+    #[staticmethod]
+    fn stopped(x: f32, y: f32) -> Self {
+        Self(BlnProtocolType::Stopped(x, y))
+    }
+
+    #[staticmethod]
+    fn home_rsq() -> Self {
+        Self(BlnProtocolType::HomeRsq)
+    }
+
+    #[staticmethod]
+    fn home_rsp() -> Self {
+        Self(BlnProtocolType::HomeRsp)
+    }
+
+    #[staticmethod]
+    fn home_reached(x: f32, y: f32) -> Self {
+        Self(BlnProtocolType::HomeReached(x, y))
+    }
+
+    #[staticmethod]
+    fn move_relative_rsq(dx: f32, dy: f32) -> Self {
+        Self(BlnProtocolType::MoveRelativeRsq(dx, dy))
+    }
+
+    #[staticmethod]
+    fn move_relative_rsp() -> Self {
+        Self(BlnProtocolType::MoveRelativeRsp)
+    }
+
+    #[staticmethod]
+    fn move_relative_reached(x: f32, y: f32) -> Self {
+        Self(BlnProtocolType::MoveRelativeReached(x, y))
+    }
+
+    #[staticmethod]
+    fn jog_start_rsq(axis: u8, velocity: f32) -> Self {
+        Self(BlnProtocolType::JogStartRsq(axis.into(), velocity))
+    }
+
+    #[staticmethod]
+    fn jog_start_rsp() -> Self {
+        Self(BlnProtocolType::JogStartRsp)
+    }
+
+    #[staticmethod]
+    fn jog_stop_rsq(axis: u8) -> Self {
+        Self(BlnProtocolType::JogStopRsq(axis.into()))
+    }
+
+    #[staticmethod]
+    fn jog_stop_rsp() -> Self {
+        Self(BlnProtocolType::JogStopRsp)
+    }
+
+    #[staticmethod]
+    fn jog_stopped(x: f32, y: f32) -> Self {
+        Self(BlnProtocolType::JogStopped(x, y))
+    }
+
+    #[staticmethod]
+    fn set_velocity_rsq(velocity: f32) -> Self {
+        Self(BlnProtocolType::SetVelocityRsq(velocity))
+    }
+
+    #[staticmethod]
+    fn set_velocity_rsp() -> Self {
+        Self(BlnProtocolType::SetVelocityRsp)
+    }
+
+    #[staticmethod]
+    fn error_rsp(cause: u8) -> Self {
+        Self(BlnProtocolType::ErrorRsp(cause.into()))
+    }
+
+    /// 变体名, 如 `"GetPositionRsp"`.
+    #[getter]
+    fn kind(&self) -> &'static str {
+        match self.0 {
+            BlnProtocolType::SetPositionRsq(..) => "SetPositionRsq",
+            BlnProtocolType::SetPositionRsp => "SetPositionRsp",
+            BlnProtocolType::PositionReached(..) => "PositionReached",
+            BlnProtocolType::GetPositionRsq => "GetPositionRsq",
+            BlnProtocolType::GetPositionRsp(..) => "GetPositionRsp",
+            BlnProtocolType::ReadConfigRsq(_) => "ReadConfigRsq",
+            BlnProtocolType::ReadConfigRsp(..) => "ReadConfigRsp",
+            BlnProtocolType::WriteConfigRsq(..) => "WriteConfigRsq",
+            BlnProtocolType::WriteConfigRsp(_) => "WriteConfigRsp",
+            BlnProtocolType::ListConfigRsq => "ListConfigRsq",
+            BlnProtocolType::ListConfigRsp(_) => "ListConfigRsp",
+            BlnProtocolType::SaveConfigRsq => "SaveConfigRsq",
+            BlnProtocolType::SaveConfigRsp => "SaveConfigRsp",
+            BlnProtocolType::EmergencyStopRsq => "EmergencyStopRsq",
+            BlnProtocolType::EmergencyStopRsp => "EmergencyStopRsp",
+            BlnProtocolType::StopRsq => "StopRsq",
+            BlnProtocolType::StopRsp => "StopRsp",
+            BlnProtocolType::Stopped(..) => "Stopped",
+            BlnProtocolType::HomeRsq => "HomeRsq",
+            BlnProtocolType::HomeRsp => "HomeRsp",
+            BlnProtocolType::HomeReached(..) => "HomeReached",
+            BlnProtocolType::MoveRelativeRsq(..) => "MoveRelativeRsq",
+            BlnProtocolType::MoveRelativeRsp => "MoveRelativeRsp",
+            BlnProtocolType::MoveRelativeReached(..) => "MoveRelativeReached",
+            BlnProtocolType::JogStartRsq(..) => "JogStartRsq",
+            BlnProtocolType::JogStartRsp => "JogStartRsp",
+            BlnProtocolType::JogStopRsq(_) => "JogStopRsq",
+            BlnProtocolType::JogStopRsp => "JogStopRsp",
+            BlnProtocolType::JogStopped(..) => "JogStopped",
+            BlnProtocolType::SetVelocityRsq(_) => "SetVelocityRsq",
+            BlnProtocolType::SetVelocityRsp => "SetVelocityRsp",
+            BlnProtocolType::ErrorRsp(_) => "ErrorRsp",
+        }
+    }
+
+    #[getter]
+    fn x(&self) -> Option<f32> {
+        self.position().map(|(x, _)| x)
+    }
+
+    #[getter]
+    fn y(&self) -> Option<f32> {
+        self.position().map(|(_, y)| y)
+    }
+
+    /// `GetPositionRsp` 中的设备状态字节, 可以通过 `status_flags` 转换为标志名.
+    #[getter]
+    fn status(&self) -> Option<u8> {
+        match self.0 {
+            BlnProtocolType::GetPositionRsp(_, _, status) => Some(status.into()),
+            _ => None,
+        }
+    }
+
+    /// 配置消息中的配置项 ID.
+    #[getter]
+    fn item(&self) -> Option<u8> {
+        match self.0 {
+            BlnProtocolType::ReadConfigRsq(item)
+            | BlnProtocolType::ReadConfigRsp(item, _)
+            | BlnProtocolType::WriteConfigRsq(item, _)
+            | BlnProtocolType::WriteConfigRsp(item) => Some(item.into()),
+            _ => None,
+        }
+    }
+
+    /// 配置消息中的配置项的值.
+    #[getter]
+    fn value(&self) -> Option<f32> {
+        match self.0 {
+            BlnProtocolType::ReadConfigRsp(_, value)
+            | BlnProtocolType::WriteConfigRsq(_, value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// `ListConfigRsp` 中的配置项 ID 列表.
+    #[getter]
+    fn items<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyList>>> {
+        match self.0 {
+            BlnProtocolType::ListConfigRsp(mask) => {
+                let items: Vec<_> = BlnConfigId::from_mask(mask).map(u8::from).collect();
+                PyList::new(py, items).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// 点动消息中的轴, 0 为 X 轴, 1 为 Y 轴.
+    #[getter]
+    fn axis(&self) -> Option<u8> {
+        match self.0 {
+            BlnProtocolType::JogStartRsq(axis, _) | BlnProtocolType::JogStopRsq(axis) => {
+                Some(axis.into())
+            }
+            _ => None,
+        }
+    }
+
+    /// 点动和设置速度消息中的速度.
+    #[getter]
+    fn velocity(&self) -> Option<f32> {
+        match self.0 {
+            BlnProtocolType::JogStartRsq(_, velocity)
+            | BlnProtocolType::SetVelocityRsq(velocity) => Some(velocity),
+            _ => None,
+        }
+    }
+
+    /// `ErrorRsp` 中的错误码.
+    #[getter]
+    fn cause(&self) -> Option<u8> {
+        match self.0 {
+            BlnProtocolType::ErrorRsp(cause) => Some(cause.into()),
+            _ => None,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+impl Message {
+    /// 消息携带的位置, `MoveRelativeRsq` 为位移.
+    fn position(&self) -> Option<(f32, f32)> {
+        match self.0 {
+            BlnProtocolType::SetPositionRsq(x, y)
+            | BlnProtocolType::PositionReached(x, y)
+            | BlnProtocolType::GetPositionRsp(x, y, _)
+            | BlnProtocolType::Stopped(x, y)
+            | BlnProtocolType::HomeReached(x, y)
+            | BlnProtocolType::MoveRelativeRsq(x, y)
+            | BlnProtocolType::MoveRelativeReached(x, y)
+            | BlnProtocolType::JogStopped(x, y) => Some((x, y)),
+            _ => None,
+        }
+    }
+}
+
+/// 将设备状态字节转换为标志名列表, 如 `["MOVING", "HOMED"]`. 本协议未定义的位被忽略.
+#[pyfunction]
+pub fn status_flags(status: u8) -> Vec<&'static str> {
+    BlnDeviceStatus::from(status)
+        .iter_names()
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// BLN 帧编码器.
+#[pyclass(name = "BlnCommandEncoder", module = "pybln", frozen)]
+#[derive(Default)]
+pub struct Encoder(BlnCommandEncoder);
+
+#[pymethods]
+impl Encoder {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// 将消息编码为完整的帧.
+    fn encode<'py>(&self, py: Python<'py>, message: Message) -> PyResult<Bound<'py, PyBytes>> {
+        let command = Command::try_from(message.0).map_err(protocol_error)?;
+        self.create_frame(py, command)
+    }
+
+    /// 由命令字、负载和响应状态直接编码帧, 用于构造协议之外的或故意出错的帧.
+    #[pyo3(signature = (cmd_id, payload, status = None))]
+    fn encode_raw<'py>(
+        &self,
+        py: Python<'py>,
+        cmd_id: u8,
+        payload: Vec<u8>,
+        status: Option<u8>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let command = Command::new(cmd_id).with_payload(payload);
+        let command = match status {
+            Some(status) => command.with_status(status),
+            None => command,
+        };
+        self.create_frame(py, command)
+    }
+}
+
+impl Encoder {
+    fn create_frame<'py>(
+        &self,
+        py: Python<'py>,
+        command: Command,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let frame = self.0.create_frame(command).map_err(protocol_error)?;
+        Ok(PyBytes::new(py, &frame))
+    }
+}
+
+/// BLN 流式解码器.
+///
+/// 通过 `feed` 写入接收到的字节, 返回其中所有完整帧解析出的消息.
+/// 校验失败的帧和无法转换为消息的帧不会抛出异常, 而是记录在 `take_diagnostics` 中.
+#[pyclass(name = "BlnCommandDecode", module = "pybln")]
+#[derive(Default)]
+pub struct Decoder {
+    /// BLN 协议解码器.
+    decoder: BlnCommandDecode,
+    /// 尚未组成完整帧的字节.
+    buf: BytesMut,
+    /// 无法转换为消息的帧.
+    errors: Vec<String>,
+}
+
+#[pymethods]
+impl Decoder {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// 写入接收到的字节, 返回解析出的消息.
+    fn feed(&mut self, data: &[u8]) -> Vec<Message> {
+        self.buf.extend_from_slice(data);
+        let Some(commands) = self.decoder.parse_protocol_frame(&mut self.buf) else {
+            return Vec::new();
+        };
+        commands
+            .into_iter()
+            .filter_map(|command| match BlnProtocolType::try_from(command) {
+                Ok(message) => Some(Message(message)),
+                Err(error) => {
+                    self.errors.push(error.to_string());
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// 取出自上次调用以来的诊断信息.
+    fn take_diagnostics(&mut self) -> Vec<String> {
+        let mut diagnostics: Vec<_> = self
+            .decoder
+            .take_diagnostics()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        diagnostics.append(&mut self.errors);
+        diagnostics
+    }
+
+    /// 解析一个完整的帧, 帧不完整、校验失败或无法转换时抛出 `ProtocolError`.
+    #[staticmethod]
+    fn decode_frame(frame: &[u8]) -> PyResult<Message> {
+        let command = BlnCommandDecode::default()
+            .decode_frame(frame)
+            .map_err(protocol_error)?;
+        BlnProtocolType::try_from(command)
+            .map(Message)
+            .map_err(protocol_error)
+    }
+}
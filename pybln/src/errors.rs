@@ -0,0 +1,48 @@
+use bln::client::BlnClientError;
+use pyo3::{
+    create_exception,
+    exceptions::{PyConnectionError, PyException, PyTimeoutError, PyValueError},
+    prelude::*,
+};
+
+create_exception!(pybln, BlnError, PyException, "BLN 绑定抛出的异常的基类.");
+create_exception!(pybln, ProtocolError, BlnError, "帧无法解析或消息无法编码.");
+create_exception!(
+    pybln,
+    DeviceError,
+    BlnError,
+    "设备返回了错误响应, 参数为 (错误描述, 错误码)."
+);
+
+/// 将异常类型注册到模块中.
+pub(crate) fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add("BlnError", py.get_type::<BlnError>())?;
+    m.add("ProtocolError", py.get_type::<ProtocolError>())?;
+    m.add("DeviceError", py.get_type::<DeviceError>())?;
+    Ok(())
+}
+
+/// 将协议错误转换为 Python 异常.
+pub(crate) fn protocol_error(error: protocol::types::ProtocolError) -> PyErr {
+    ProtocolError::new_err(error.to_string())
+}
+
+/// 将客户端错误转换为 Python 异常.
+///
+/// 超时映射为内置的 `TimeoutError`, 连接关闭和传输错误映射为 `ConnectionError`,
+/// 未通过发送前校验的参数映射为 `ValueError`.
+pub(crate) fn client_error(error: BlnClientError) -> PyErr {
+    match error {
+        BlnClientError::Device(cause) => DeviceError::new_err((error.to_string(), u8::from(cause))),
+        BlnClientError::Protocol(error) => protocol_error(error),
+        BlnClientError::Invalid(_) => PyValueError::new_err(error.to_string()),
+        BlnClientError::Move(_) | BlnClientError::Stopped | BlnClientError::DryRun => {
+            BlnError::new_err(error.to_string())
+        }
+        BlnClientError::Timeout => PyTimeoutError::new_err(error.to_string()),
+        BlnClientError::Closed | BlnClientError::Transport(_) => {
+            PyConnectionError::new_err(error.to_string())
+        }
+    }
+}
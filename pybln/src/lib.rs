@@ -0,0 +1,22 @@
+//! BLN 协议的 Python 绑定.
+//!
+//! 暴露与 Rust 端相同的编解码器 (`BlnCommandEncoder`, `BlnCommandDecode`)、
+//! 消息类型 (`BlnProtocolType`) 以及阻塞和异步的设备客户端, 供 Python 自动化测试使用.
+//! 使用 `maturin develop` 构建并安装到当前的 Python 环境中.
+use pyo3::prelude::*;
+
+pub mod client;
+pub mod codec;
+pub mod errors;
+
+#[pymodule]
+fn pybln(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<codec::Message>()?;
+    m.add_class::<codec::Encoder>()?;
+    m.add_class::<codec::Decoder>()?;
+    m.add_class::<client::Client>()?;
+    m.add_class::<client::AsyncClient>()?;
+    m.add_function(wrap_pyfunction!(codec::status_flags, m)?)?;
+    errors::register(m)?;
+    Ok(())
+}
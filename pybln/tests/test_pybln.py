@@ -0,0 +1,161 @@
+"""pybln 的测试, 客户端部分连接到本地启动的模拟器 (blnsim).
+
+运行前先安装绑定并构建模拟器:
+
+    maturin develop -m pybln/Cargo.toml
+    cargo build -p simulator
+    python -m unittest discover pybln/tests
+
+模拟器默认从 target/debug/blnsim 启动, 可以通过环境变量 BLNSIM 指定其他路径.
+"""
+
+import asyncio
+import os
+import socket
+import subprocess
+import time
+import unittest
+from pathlib import Path
+
+import pybln
+from pybln import AsyncBlnClient, BlnClient, BlnCommandDecode, BlnCommandEncoder, BlnProtocolType
+
+ROOT = Path(__file__).resolve().parents[2]
+BLNSIM = os.environ.get("BLNSIM", str(ROOT / "target" / "debug" / "blnsim"))
+
+# BlnErrorCause::InvalidArgument
+INVALID_ARGUMENT = 0x02
+# BlnErrorCause::ConfigNotFound
+CONFIG_NOT_FOUND = 0x04
+# BlnConfigId::Speed
+SPEED = 0x01
+# BlnAxis::X
+AXIS_X = 0x00
+
+
+def free_port():
+    with socket.socket() as sock:
+        sock.bind(("127.0.0.1", 0))
+        return sock.getsockname()[1]
+
+
+class CodecTest(unittest.TestCase):
+    def test_round_trip(self):
+        frame = BlnCommandEncoder().encode(BlnProtocolType.get_position_rsp(1.5, -2.0, 1))
+        self.assertEqual(frame[:3], b"\x55\xaa\x93")
+
+        message = BlnCommandDecode.decode_frame(frame)
+        self.assertEqual(message, BlnProtocolType.get_position_rsp(1.5, -2.0, 1))
+        self.assertEqual((message.kind, message.x, message.y, message.status), ("GetPositionRsp", 1.5, -2.0, 1))
+        self.assertIsNone(message.cause)
+        self.assertEqual(pybln.status_flags(message.status), ["MOVING"])
+
+        message = BlnCommandDecode.decode_frame(BlnCommandEncoder().encode(BlnProtocolType.list_config_rsp([1, 3])))
+        self.assertEqual(message.items, [1, 3])
+
+        message = BlnCommandDecode.decode_frame(BlnCommandEncoder().encode(BlnProtocolType.jog_start_rsq(AXIS_X, -2.5)))
+        self.assertEqual((message.kind, message.axis, message.velocity), ("JogStartRsq", AXIS_X, -2.5))
+
+    def test_stream_decoding_and_diagnostics(self):
+        encoder = BlnCommandEncoder()
+        data = encoder.encode(BlnProtocolType.set_position_rsp()) + encoder.encode_raw(0x93, b"\x00", status=2)
+
+        decoder = BlnCommandDecode()
+        self.assertEqual(decoder.feed(data[:5]), [])
+        self.assertEqual(decoder.feed(data[5:]), [BlnProtocolType.set_position_rsp()])
+        self.assertEqual(len(decoder.take_diagnostics()), 1)
+
+    def test_invalid_frame_raises(self):
+        frame = bytearray(BlnCommandEncoder().encode(BlnProtocolType.get_position_rsq()))
+        frame[-1] ^= 0xFF
+        with self.assertRaises(pybln.ProtocolError):
+            BlnCommandDecode.decode_frame(bytes(frame))
+
+
+class ClientTest(unittest.TestCase):
+    @classmethod
+    def setUpClass(cls):
+        cls.addr = f"127.0.0.1:{free_port()}"
+        cls.simulator = subprocess.Popen(
+            [BLNSIM, "--listen", cls.addr, "--speed", "1000"],
+            stdout=subprocess.DEVNULL,
+            stderr=subprocess.DEVNULL,
+        )
+        host, port = cls.addr.split(":")
+        deadline = time.monotonic() + 5
+        while True:
+            try:
+                socket.create_connection((host, int(port)), timeout=0.1).close()
+                break
+            except OSError:
+                if time.monotonic() > deadline:
+                    cls.simulator.kill()
+                    raise
+                time.sleep(0.05)
+
+    @classmethod
+    def tearDownClass(cls):
+        cls.simulator.kill()
+        cls.simulator.wait()
+
+    def test_blocking_client(self):
+        client = BlnClient.connect(self.addr)
+        self.assertEqual(client.move_to(3.0, 4.0), (3.0, 4.0))
+        x, y, _ = client.position()
+        self.assertEqual((x, y), (3.0, 4.0))
+
+        with self.assertRaises(pybln.DeviceError) as error:
+            client.move_to(1e9, 0.0)
+        self.assertEqual(error.exception.args[1], INVALID_ARGUMENT)
+
+        client.send(BlnProtocolType.get_position_rsq())
+        self.assertEqual(client.recv(timeout=1.0).kind, "GetPositionRsp")
+        with self.assertRaises(TimeoutError):
+            client.recv(timeout=0.05)
+
+    def test_config(self):
+        client = BlnClient.connect(self.addr)
+        self.assertEqual(client.list_config(), [SPEED, 2, 3, 4, 5, 6, 7, 8])
+        client.write_config(SPEED, 500.0)
+        self.assertEqual(client.read_config(SPEED), 500.0)
+        client.save_config()
+        with self.assertRaises(pybln.DeviceError) as error:
+            client.read_config(0x40)
+        self.assertEqual(error.exception.args[1], CONFIG_NOT_FOUND)
+
+    def test_motion(self):
+        client = BlnClient.connect(self.addr)
+        self.assertEqual(client.home(), (0.0, 0.0))
+        self.assertEqual(client.move_by(1.0, 2.0), (1.0, 2.0))
+        client.jog_start(AXIS_X, 10.0)
+        x, y = client.jog_stop(AXIS_X)
+        self.assertGreaterEqual(x, 1.0)
+        self.assertEqual(y, 2.0)
+        client.set_velocity(1000.0)
+        with self.assertRaises(pybln.DeviceError) as error:
+            client.set_velocity(0.0)
+        self.assertEqual(error.exception.args[1], INVALID_ARGUMENT)
+        client.emergency_stop()
+        _, _, status = client.position()
+        self.assertIn("EMERGENCY_STOP", pybln.status_flags(status))
+        with self.assertRaises(pybln.DeviceError):
+            client.move_to(0.0, 0.0)
+        client.home()
+        self.assertIn("HOMED", pybln.status_flags(client.position()[2]))
+
+    def test_async_client(self):
+        async def scenario():
+            client = await AsyncBlnClient.connect(self.addr)
+            reached = await client.move_to(-1.0, 2.0)
+            x, y, _ = await client.position()
+            return reached, (x, y)
+
+        self.assertEqual(asyncio.run(scenario()), ((-1.0, 2.0), (-1.0, 2.0)))
+
+    def test_connect_failure(self):
+        with self.assertRaises(ConnectionError):
+            BlnClient.connect(f"127.0.0.1:{free_port()}", timeout=0.5)
+
+
+if __name__ == "__main__":
+    unittest.main()
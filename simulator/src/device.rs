@@ -0,0 +1,717 @@
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use bln::protocol::types::{BlnAxis, BlnConfigId, BlnDeviceStatus, BlnErrorCause, BlnProtocolType};
+use protocol::types::{Command, ProtocolError};
+use tracing::{debug, info};
+
+/// 模拟设备的配置.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimConfig {
+    /// 移动速度, 单位为 位置单位/秒.
+    pub speed: f32,
+    /// 每个轴允许的最小位置.
+    pub min: f32,
+    /// 每个轴允许的最大位置.
+    pub max: f32,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            speed: 50.0,
+            min: -1000.0,
+            max: 1000.0,
+        }
+    }
+}
+
+/// 模拟设备产生的一条响应.
+#[derive(Debug, PartialEq)]
+pub struct Reply {
+    /// 距离收到请求后多久发出该响应.
+    pub delay: Duration,
+    /// 响应消息.
+    pub message: BlnProtocolType,
+    /// 延迟响应所属的运动编号. 运动在响应发出前被停止时, 响应应被丢弃,
+    /// 可以通过 [`SimDevice::is_current`] 判断.
+    pub motion: Option<u64>,
+}
+
+impl Reply {
+    /// 创建一个立即发出的响应.
+    fn now(message: BlnProtocolType) -> Self {
+        Self {
+            delay: Duration::ZERO,
+            message,
+            motion: None,
+        }
+    }
+
+    /// 创建一个立即发出的错误响应.
+    fn error(cause: BlnErrorCause) -> Self {
+        Self::now(BlnProtocolType::ErrorRsp(cause))
+    }
+}
+
+/// 运动的种类.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MotionKind {
+    /// 移动到给定位置.
+    Move,
+    /// 回零, 完成后设备变为已回零, 并解除急停锁定.
+    Home,
+    /// 单个轴的点动.
+    Jog(BlnAxis),
+}
+
+/// 一次正在进行的移动.
+#[derive(Debug, Clone, Copy)]
+struct Motion {
+    from: (f32, f32),
+    to: (f32, f32),
+    start: Instant,
+    duration: Duration,
+    kind: MotionKind,
+}
+
+/// `SimDevice` 模拟 BLN 设备固件对请求的处理逻辑.
+///
+/// 它只负责根据请求计算响应和延迟, 不涉及任何网络 I/O,
+/// 所有与时间相关的方法都接收一个 `now` 参数.
+#[derive(Debug)]
+pub struct SimDevice {
+    /// 配置项的当前值, 以配置项 ID 为键.
+    settings: BTreeMap<u8, f32>,
+    /// 最近一次静止时的位置.
+    position: (f32, f32),
+    /// 当前正在进行的移动.
+    motion: Option<Motion>,
+    /// 运动编号, 每次开始或停止运动时递增.
+    generation: u64,
+    /// 是否已回零.
+    homed: bool,
+    /// 急停是否锁定. 锁定期间只接受回零.
+    emergency_stop: bool,
+}
+
+impl SimDevice {
+    /// 使用给定配置创建一个位于原点的模拟设备.
+    pub fn new(config: SimConfig) -> Self {
+        let settings = [
+            (BlnConfigId::Speed, config.speed),
+            (BlnConfigId::Acceleration, 0.0),
+            (BlnConfigId::SoftLimitMinX, config.min),
+            (BlnConfigId::SoftLimitMaxX, config.max),
+            (BlnConfigId::SoftLimitMinY, config.min),
+            (BlnConfigId::SoftLimitMaxY, config.max),
+            (BlnConfigId::HomeOffsetX, 0.0),
+            (BlnConfigId::HomeOffsetY, 0.0),
+        ];
+        Self {
+            settings: settings
+                .into_iter()
+                .map(|(item, value)| (item.into(), value))
+                .collect(),
+            position: (0.0, 0.0),
+            motion: None,
+            generation: 0,
+            homed: false,
+            emergency_stop: false,
+        }
+    }
+
+    /// 返回设备在 `now` 时刻的位置. 移动中的位置按线性插值计算.
+    pub fn position_at(&mut self, now: Instant) -> (f32, f32) {
+        let Some(motion) = self.motion else {
+            return self.position;
+        };
+        let elapsed = now.saturating_duration_since(motion.start);
+        if elapsed >= motion.duration {
+            self.position = motion.to;
+            self.motion = None;
+            if motion.kind == MotionKind::Home {
+                self.homed = true;
+                self.emergency_stop = false;
+            }
+            return self.position;
+        }
+        let t = elapsed.as_secs_f32() / motion.duration.as_secs_f32();
+        (
+            motion.from.0 + (motion.to.0 - motion.from.0) * t,
+            motion.from.1 + (motion.to.1 - motion.from.1) * t,
+        )
+    }
+
+    /// 设备在 `now` 时刻是否正在移动.
+    pub fn is_moving(&mut self, now: Instant) -> bool {
+        self.position_at(now);
+        self.motion.is_some()
+    }
+
+    /// 编号为 `motion` 的运动是否仍是最近一次运动, 即没有被停止或被新的运动取代.
+    pub fn is_current(&self, motion: u64) -> bool {
+        self.generation == motion
+    }
+
+    /// 返回设备在 `now` 时刻的状态.
+    pub fn status_at(&mut self, now: Instant) -> BlnDeviceStatus {
+        let (x, y) = self.position_at(now);
+        let at_limit = self.at_limit(x, BlnConfigId::SoftLimitMinX, BlnConfigId::SoftLimitMaxX)
+            || self.at_limit(y, BlnConfigId::SoftLimitMinY, BlnConfigId::SoftLimitMaxY);
+        let mut status = BlnDeviceStatus::X_ENABLED | BlnDeviceStatus::Y_ENABLED;
+        status.set(BlnDeviceStatus::MOVING, self.motion.is_some());
+        status.set(BlnDeviceStatus::HOMED, self.homed);
+        status.set(BlnDeviceStatus::AT_LIMIT, at_limit);
+        status.set(BlnDeviceStatus::EMERGENCY_STOP, self.emergency_stop);
+        status
+    }
+
+    /// 处理一条解码后的请求帧, 返回需要发出的响应.
+    pub fn handle(&mut self, command: Command, now: Instant) -> Vec<Reply> {
+        let message = BlnProtocolType::try_from(command);
+        if self.emergency_stop
+            && matches!(
+                message,
+                Ok(BlnProtocolType::SetPositionRsq(..)
+                    | BlnProtocolType::MoveRelativeRsq(..)
+                    | BlnProtocolType::JogStartRsq(..))
+            )
+        {
+            info!("急停锁定中, 需要先回零");
+            return vec![Reply::error(BlnErrorCause::StateMismatch)];
+        }
+        match message {
+            Ok(BlnProtocolType::SetPositionRsq(x, y)) => self.move_to(
+                (x, y),
+                now,
+                BlnProtocolType::SetPositionRsp,
+                BlnProtocolType::PositionReached,
+                MotionKind::Move,
+            ),
+            Ok(BlnProtocolType::MoveRelativeRsq(dx, dy)) => {
+                let (x, y) = self.position_at(now);
+                self.move_to(
+                    (x + dx, y + dy),
+                    now,
+                    BlnProtocolType::MoveRelativeRsp,
+                    BlnProtocolType::MoveRelativeReached,
+                    MotionKind::Move,
+                )
+            }
+            Ok(BlnProtocolType::HomeRsq) => self.move_to(
+                (
+                    self.setting(BlnConfigId::HomeOffsetX),
+                    self.setting(BlnConfigId::HomeOffsetY),
+                ),
+                now,
+                BlnProtocolType::HomeRsp,
+                BlnProtocolType::HomeReached,
+                MotionKind::Home,
+            ),
+            Ok(BlnProtocolType::EmergencyStopRsq) => {
+                self.halt(now);
+                self.emergency_stop = true;
+                self.homed = false;
+                info!(position = ?self.position, "急停");
+                vec![Reply::now(BlnProtocolType::EmergencyStopRsp)]
+            }
+            Ok(BlnProtocolType::StopRsq) => {
+                let (x, y) = self.halt(now);
+                vec![
+                    Reply::now(BlnProtocolType::StopRsp),
+                    Reply::now(BlnProtocolType::Stopped(x, y)),
+                ]
+            }
+            Ok(BlnProtocolType::JogStartRsq(axis, velocity)) => {
+                vec![self.jog_start(axis, velocity, now)]
+            }
+            Ok(BlnProtocolType::JogStopRsq(axis)) => self.jog_stop(axis, now),
+            Ok(BlnProtocolType::SetVelocityRsq(velocity)) => {
+                if !velocity.is_finite() || velocity <= 0.0 {
+                    return vec![Reply::error(BlnErrorCause::InvalidArgument)];
+                }
+                self.settings.insert(BlnConfigId::Speed.into(), velocity);
+                vec![Reply::now(BlnProtocolType::SetVelocityRsp)]
+            }
+            Ok(BlnProtocolType::GetPositionRsq) => self.get_position(now),
+            Ok(BlnProtocolType::ReadConfigRsq(item)) => vec![self.read_config(item)],
+            Ok(BlnProtocolType::WriteConfigRsq(item, value)) => {
+                vec![self.write_config(item, value)]
+            }
+            Ok(BlnProtocolType::ListConfigRsq) => {
+                let items = self.settings.keys().map(|&id| BlnConfigId::from(id));
+                vec![Reply::now(BlnProtocolType::ListConfigRsp(
+                    BlnConfigId::to_mask(items),
+                ))]
+            }
+            Ok(BlnProtocolType::SaveConfigRsq) => {
+                info!(settings = ?self.settings, "保存配置");
+                vec![Reply::now(BlnProtocolType::SaveConfigRsp)]
+            }
+            Ok(message) => {
+                debug!(?message, "设备不处理响应消息");
+                vec![Reply::error(BlnErrorCause::StateMismatch)]
+            }
+            Err(ProtocolError::InvalidCommandType { .. }) => {
+                vec![Reply::error(BlnErrorCause::UnspecifiedError)]
+            }
+            Err(_) => vec![Reply::error(BlnErrorCause::InvalidArgument)],
+        }
+    }
+
+    /// 处理移动到绝对位置的请求 (设置位置、相对移动和回零): 立即回复确认 `ack`,
+    /// 并在模拟的移动时间后回复 `reached` 报告到达.
+    fn move_to(
+        &mut self,
+        target: (f32, f32),
+        now: Instant,
+        ack: BlnProtocolType,
+        reached: fn(f32, f32) -> BlnProtocolType,
+        kind: MotionKind,
+    ) -> Vec<Reply> {
+        if !self.in_range(
+            target.0,
+            BlnConfigId::SoftLimitMinX,
+            BlnConfigId::SoftLimitMaxX,
+        ) || !self.in_range(
+            target.1,
+            BlnConfigId::SoftLimitMinY,
+            BlnConfigId::SoftLimitMaxY,
+        ) {
+            info!(?target, "目标位置超出范围");
+            return vec![Reply::error(BlnErrorCause::InvalidArgument)];
+        }
+        if self.is_moving(now) {
+            return vec![Reply::error(BlnErrorCause::StateMismatch)];
+        }
+
+        let duration = self.start(target, self.setting(BlnConfigId::Speed), kind, now);
+        vec![
+            Reply::now(ack),
+            Reply {
+                delay: duration,
+                message: reached(target.0, target.1),
+                motion: Some(self.generation),
+            },
+        ]
+    }
+
+    /// 处理点动开始请求: 轴以 `velocity` 的速率向软限位运动, 到达软限位后停止.
+    fn jog_start(&mut self, axis: BlnAxis, velocity: f32, now: Instant) -> Reply {
+        let (min, max) = match axis {
+            BlnAxis::X => (BlnConfigId::SoftLimitMinX, BlnConfigId::SoftLimitMaxX),
+            BlnAxis::Y => (BlnConfigId::SoftLimitMinY, BlnConfigId::SoftLimitMaxY),
+            BlnAxis::Other(_) => return Reply::error(BlnErrorCause::InvalidArgument),
+        };
+        if !velocity.is_finite() || velocity == 0.0 {
+            return Reply::error(BlnErrorCause::InvalidArgument);
+        }
+        if self.is_moving(now) {
+            return Reply::error(BlnErrorCause::StateMismatch);
+        }
+        let limit = self.setting(if velocity > 0.0 { max } else { min });
+        let (x, y) = self.position;
+        let target = if axis == BlnAxis::X {
+            (limit, y)
+        } else {
+            (x, limit)
+        };
+        self.start(target, velocity.abs(), MotionKind::Jog(axis), now);
+        Reply::now(BlnProtocolType::JogStartRsp)
+    }
+
+    /// 处理点动停止请求. 该轴没有在点动时同样回复已停止, 其他运动进行中时拒绝.
+    fn jog_stop(&mut self, axis: BlnAxis, now: Instant) -> Vec<Reply> {
+        self.position_at(now);
+        if self
+            .motion
+            .is_some_and(|motion| motion.kind != MotionKind::Jog(axis))
+        {
+            return vec![Reply::error(BlnErrorCause::StateMismatch)];
+        }
+        let (x, y) = self.halt(now);
+        vec![
+            Reply::now(BlnProtocolType::JogStopRsp),
+            Reply::now(BlnProtocolType::JogStopped(x, y)),
+        ]
+    }
+
+    /// 开始一次以 `speed` 匀速运动到 `target` 的运动, 返回运动所需的时间.
+    fn start(
+        &mut self,
+        target: (f32, f32),
+        speed: f32,
+        kind: MotionKind,
+        now: Instant,
+    ) -> Duration {
+        let from = self.position;
+        let distance = (target.0 - from.0).hypot(target.1 - from.1);
+        let duration = Duration::try_from_secs_f32(distance / speed).unwrap_or(Duration::MAX);
+        self.motion = Some(Motion {
+            from,
+            to: target,
+            start: now,
+            duration,
+            kind,
+        });
+        self.generation += 1;
+        info!(?from, ?target, ?duration, ?kind, "开始移动");
+        duration
+    }
+
+    /// 立即停止当前的运动, 返回停止时的位置. 尚未发出的到达响应随之作废.
+    fn halt(&mut self, now: Instant) -> (f32, f32) {
+        self.position = self.position_at(now);
+        if self.motion.take().is_some() {
+            self.generation += 1;
+            info!(position = ?self.position, "运动被停止");
+        }
+        self.position
+    }
+
+    /// 处理获取位置请求: 立即返回当前位置和状态.
+    fn get_position(&mut self, now: Instant) -> Vec<Reply> {
+        let status = self.status_at(now);
+        let (x, y) = self.position_at(now);
+        vec![Reply::now(BlnProtocolType::GetPositionRsp(x, y, status))]
+    }
+
+    /// 处理读取配置项请求.
+    fn read_config(&self, item: BlnConfigId) -> Reply {
+        match self.settings.get(&item.into()) {
+            Some(&value) => Reply::now(BlnProtocolType::ReadConfigRsp(item, value)),
+            None => Reply::error(BlnErrorCause::ConfigNotFound),
+        }
+    }
+
+    /// 处理写入配置项请求. 值必须是有限数, 速度还必须为正数.
+    fn write_config(&mut self, item: BlnConfigId, value: f32) -> Reply {
+        let Some(setting) = self.settings.get_mut(&item.into()) else {
+            return Reply::error(BlnErrorCause::ConfigNotFound);
+        };
+        if !value.is_finite() || (item == BlnConfigId::Speed && value <= 0.0) {
+            return Reply::error(BlnErrorCause::InvalidArgument);
+        }
+        *setting = value;
+        info!(?item, value, "写入配置");
+        Reply::now(BlnProtocolType::WriteConfigRsp(item))
+    }
+
+    /// 配置项的当前值, 配置项都在创建时写入, 不会缺失.
+    fn setting(&self, item: BlnConfigId) -> f32 {
+        self.settings.get(&item.into()).copied().unwrap_or_default()
+    }
+
+    /// 单个轴是否停在该轴的软限位上.
+    fn at_limit(&self, value: f32, min: BlnConfigId, max: BlnConfigId) -> bool {
+        value == self.setting(min) || value == self.setting(max)
+    }
+
+    /// 检查单个轴的目标位置是否在该轴的软限位之内.
+    fn in_range(&self, value: f32, min: BlnConfigId, max: BlnConfigId) -> bool {
+        value.is_finite() && (self.setting(min)..=self.setting(max)).contains(&value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_position(x: f32, y: f32) -> Command {
+        BlnProtocolType::SetPositionRsq(x, y).try_into().unwrap()
+    }
+
+    fn get_position() -> Command {
+        BlnProtocolType::GetPositionRsq.try_into().unwrap()
+    }
+
+    #[test]
+    fn test_set_position_acks_then_reaches() {
+        let now = Instant::now();
+        let mut device = SimDevice::new(SimConfig {
+            speed: 10.0,
+            ..Default::default()
+        });
+
+        let replies = device.handle(set_position(30.0, 40.0), now);
+        assert_eq!(
+            replies,
+            vec![
+                Reply::now(BlnProtocolType::SetPositionRsp),
+                // 距离 50, 速度 10, 需要 5 秒.
+                Reply {
+                    delay: Duration::from_secs(5),
+                    message: BlnProtocolType::PositionReached(30.0, 40.0),
+                    motion: Some(1),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_position_interpolates_while_moving() {
+        let now = Instant::now();
+        let mut device = SimDevice::new(SimConfig {
+            speed: 10.0,
+            ..Default::default()
+        });
+        device.handle(set_position(10.0, 0.0), now);
+
+        assert_eq!(
+            device.position_at(now + Duration::from_millis(500)),
+            (5.0, 0.0)
+        );
+        assert!(device.is_moving(now + Duration::from_millis(500)));
+        assert_eq!(
+            device.position_at(now + Duration::from_secs(2)),
+            (10.0, 0.0)
+        );
+        assert!(!device.is_moving(now + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn test_get_position_reports_moving_status() {
+        let now = Instant::now();
+        let mut device = SimDevice::new(SimConfig::default());
+        device.handle(set_position(100.0, 0.0), now);
+
+        let replies = device.handle(get_position(), now);
+        assert_eq!(
+            replies,
+            vec![Reply::now(BlnProtocolType::GetPositionRsp(
+                0.0,
+                0.0,
+                BlnDeviceStatus::MOVING | BlnDeviceStatus::X_ENABLED | BlnDeviceStatus::Y_ENABLED
+            ))]
+        );
+    }
+
+    #[test]
+    fn test_out_of_range_target_is_rejected() {
+        let now = Instant::now();
+        let mut device = SimDevice::new(SimConfig::default());
+
+        for target in [(2000.0, 0.0), (0.0, f32::NAN), (f32::INFINITY, 0.0)] {
+            let replies = device.handle(set_position(target.0, target.1), now);
+            assert_eq!(replies, vec![Reply::error(BlnErrorCause::InvalidArgument)]);
+        }
+        assert!(!device.is_moving(now));
+    }
+
+    #[test]
+    fn test_set_position_while_moving_is_rejected() {
+        let now = Instant::now();
+        let mut device = SimDevice::new(SimConfig::default());
+        device.handle(set_position(100.0, 0.0), now);
+
+        let replies = device.handle(set_position(0.0, 0.0), now);
+        assert_eq!(replies, vec![Reply::error(BlnErrorCause::StateMismatch)]);
+    }
+
+    #[test]
+    fn test_stop_cancels_motion() {
+        let now = Instant::now();
+        let mut device = SimDevice::new(SimConfig {
+            speed: 10.0,
+            ..Default::default()
+        });
+        let replies = device.handle(set_position(10.0, 0.0), now);
+        let motion = replies[1].motion.unwrap();
+        assert!(device.is_current(motion));
+
+        let stop = BlnProtocolType::StopRsq.try_into().unwrap();
+        assert_eq!(
+            device.handle(stop, now + Duration::from_millis(500)),
+            vec![
+                Reply::now(BlnProtocolType::StopRsp),
+                Reply::now(BlnProtocolType::Stopped(5.0, 0.0)),
+            ]
+        );
+        assert!(!device.is_current(motion));
+        assert!(!device.is_moving(now + Duration::from_secs(2)));
+        assert_eq!(device.position_at(now + Duration::from_secs(2)), (5.0, 0.0));
+    }
+
+    #[test]
+    fn test_relative_move_and_home() {
+        let now = Instant::now();
+        let mut device = SimDevice::new(SimConfig::default());
+        device.handle(set_position(10.0, 20.0), now);
+        let later = now + Duration::from_secs(1);
+
+        let relative = BlnProtocolType::MoveRelativeRsq(-5.0, 5.0)
+            .try_into()
+            .unwrap();
+        let replies = device.handle(relative, later);
+        assert_eq!(replies[0], Reply::now(BlnProtocolType::MoveRelativeRsp));
+        assert_eq!(
+            replies[1].message,
+            BlnProtocolType::MoveRelativeReached(5.0, 25.0)
+        );
+
+        let relative = BlnProtocolType::MoveRelativeRsq(2000.0, 0.0)
+            .try_into()
+            .unwrap();
+        let later = later + Duration::from_secs(1);
+        assert_eq!(
+            device.handle(relative, later),
+            vec![Reply::error(BlnErrorCause::InvalidArgument)]
+        );
+
+        let home = BlnProtocolType::HomeRsq.try_into().unwrap();
+        let replies = device.handle(home, later);
+        assert_eq!(replies[0], Reply::now(BlnProtocolType::HomeRsp));
+        assert_eq!(replies[1].message, BlnProtocolType::HomeReached(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_jog_moves_towards_soft_limit() {
+        let now = Instant::now();
+        let mut device = SimDevice::new(SimConfig::default());
+
+        let jog = BlnProtocolType::JogStartRsq(BlnAxis::Y, -100.0)
+            .try_into()
+            .unwrap();
+        assert_eq!(
+            device.handle(jog, now),
+            vec![Reply::now(BlnProtocolType::JogStartRsp)]
+        );
+        assert_eq!(
+            device.position_at(now + Duration::from_secs(1)),
+            (0.0, -100.0)
+        );
+
+        // 点动时不能停止其他轴的点动
+        let stop_x = BlnProtocolType::JogStopRsq(BlnAxis::X).try_into().unwrap();
+        assert_eq!(
+            device.handle(stop_x, now),
+            vec![Reply::error(BlnErrorCause::StateMismatch)]
+        );
+        let stop_y = BlnProtocolType::JogStopRsq(BlnAxis::Y).try_into().unwrap();
+        assert_eq!(
+            device.handle(stop_y, now + Duration::from_secs(2)),
+            vec![
+                Reply::now(BlnProtocolType::JogStopRsp),
+                Reply::now(BlnProtocolType::JogStopped(0.0, -200.0)),
+            ]
+        );
+
+        // 到达软限位后自动停止
+        let jog = BlnProtocolType::JogStartRsq(BlnAxis::X, 1000.0)
+            .try_into()
+            .unwrap();
+        let later = now + Duration::from_secs(3);
+        device.handle(jog, later);
+        assert_eq!(
+            device.position_at(later + Duration::from_secs(5)),
+            (1000.0, -200.0)
+        );
+
+        let jog = BlnProtocolType::JogStartRsq(BlnAxis::X, 0.0)
+            .try_into()
+            .unwrap();
+        assert_eq!(
+            device.handle(jog, later + Duration::from_secs(5)),
+            vec![Reply::error(BlnErrorCause::InvalidArgument)]
+        );
+    }
+
+    #[test]
+    fn test_set_velocity() {
+        let now = Instant::now();
+        let mut device = SimDevice::new(SimConfig::default());
+
+        let velocity = BlnProtocolType::SetVelocityRsq(100.0).try_into().unwrap();
+        assert_eq!(
+            device.handle(velocity, now),
+            vec![Reply::now(BlnProtocolType::SetVelocityRsp)]
+        );
+        let replies = device.handle(set_position(100.0, 0.0), now);
+        assert_eq!(replies[1].delay, Duration::from_secs(1));
+
+        let velocity = BlnProtocolType::SetVelocityRsq(f32::NAN)
+            .try_into()
+            .unwrap();
+        assert_eq!(
+            device.handle(velocity, now),
+            vec![Reply::error(BlnErrorCause::InvalidArgument)]
+        );
+    }
+
+    #[test]
+    fn test_emergency_stop_latches_until_homed() {
+        let now = Instant::now();
+        let mut device = SimDevice::new(SimConfig::default());
+        device.handle(set_position(1000.0, 0.0), now);
+        let later = now + Duration::from_secs(30);
+        assert!(device.status_at(later).contains(BlnDeviceStatus::AT_LIMIT));
+
+        let stop = BlnProtocolType::EmergencyStopRsq.try_into().unwrap();
+        device.handle(stop, later);
+        assert!(!device.status_at(later).is_ready());
+        assert_eq!(
+            device.handle(set_position(0.0, 0.0), later),
+            vec![Reply::error(BlnErrorCause::StateMismatch)]
+        );
+
+        let home = BlnProtocolType::HomeRsq.try_into().unwrap();
+        let replies = device.handle(home, later);
+        assert_eq!(replies[0], Reply::now(BlnProtocolType::HomeRsp));
+        // 回零完成前急停仍然锁定
+        let status = device.status_at(later);
+        assert!(status.contains(BlnDeviceStatus::MOVING | BlnDeviceStatus::EMERGENCY_STOP));
+
+        let status = device.status_at(later + replies[1].delay);
+        assert!(status.contains(BlnDeviceStatus::HOMED));
+        assert!(status.is_ready());
+    }
+
+    #[test]
+    fn test_config_read_write() {
+        let now = Instant::now();
+        let mut device = SimDevice::new(SimConfig::default());
+
+        let write = BlnProtocolType::WriteConfigRsq(BlnConfigId::SoftLimitMaxY, 10.0);
+        assert_eq!(
+            device.handle(write.try_into().unwrap(), now),
+            vec![Reply::now(BlnProtocolType::WriteConfigRsp(
+                BlnConfigId::SoftLimitMaxY
+            ))]
+        );
+        let read = BlnProtocolType::ReadConfigRsq(BlnConfigId::SoftLimitMaxY);
+        assert_eq!(
+            device.handle(read.try_into().unwrap(), now),
+            vec![Reply::now(BlnProtocolType::ReadConfigRsp(
+                BlnConfigId::SoftLimitMaxY,
+                10.0
+            ))]
+        );
+        // 新的软限位立即生效
+        assert_eq!(
+            device.handle(set_position(0.0, 20.0), now),
+            vec![Reply::error(BlnErrorCause::InvalidArgument)]
+        );
+
+        let speed = BlnProtocolType::WriteConfigRsq(BlnConfigId::Speed, 0.0);
+        assert_eq!(
+            device.handle(speed.try_into().unwrap(), now),
+            vec![Reply::error(BlnErrorCause::InvalidArgument)]
+        );
+        let unknown = BlnProtocolType::ReadConfigRsq(BlnConfigId::Other(0x40));
+        assert_eq!(
+            device.handle(unknown.try_into().unwrap(), now),
+            vec![Reply::error(BlnErrorCause::ConfigNotFound)]
+        );
+    }
+
+    #[test]
+    fn test_unknown_command_is_rejected() {
+        let command = Command::new(0x7F).with_status(0);
+
+        let replies = SimDevice::new(SimConfig::default()).handle(command, Instant::now());
+        assert_eq!(replies, vec![Reply::error(BlnErrorCause::UnspecifiedError)]);
+    }
+}
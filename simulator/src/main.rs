@@ -0,0 +1,53 @@
+use std::sync::{Arc, Mutex};
+
+use clap::Parser;
+use color_eyre::{Result, eyre::ensure};
+use simulator::{
+    device::{SimConfig, SimDevice},
+    server::serve,
+};
+use tokio::net::TcpListener;
+use tracing_subscriber::EnvFilter;
+
+/// BLN 设备模拟器: 在 TCP 上模拟 BLN 固件对请求的响应.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Args {
+    /// 监听地址.
+    #[arg(long, default_value = "127.0.0.1:5006")]
+    listen: String,
+    /// 移动速度, 单位为 位置单位/秒.
+    #[arg(long, default_value_t = SimConfig::default().speed)]
+    speed: f32,
+    /// 每个轴允许的最小位置.
+    #[arg(long, default_value_t = SimConfig::default().min, allow_negative_numbers = true)]
+    min: f32,
+    /// 每个轴允许的最大位置.
+    #[arg(long, default_value_t = SimConfig::default().max, allow_negative_numbers = true)]
+    max: f32,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    color_eyre::install()?;
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("INFO")),
+        )
+        .init();
+
+    let args = Args::parse();
+    ensure!(
+        args.speed.is_finite() && args.speed > 0.0,
+        "移动速度必须为正数"
+    );
+    ensure!(args.min <= args.max, "最小位置不能大于最大位置");
+
+    let device = SimDevice::new(SimConfig {
+        speed: args.speed,
+        min: args.min,
+        max: args.max,
+    });
+    let listener = TcpListener::bind(&args.listen).await?;
+    serve(listener, Arc::new(Mutex::new(device))).await
+}
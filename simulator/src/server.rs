@@ -0,0 +1,181 @@
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use bln::protocol::types::{BlnCommandDecode, BlnCommandEncoder};
+use bytes::{Bytes, BytesMut};
+use color_eyre::eyre::{Result, eyre};
+use protocol::{
+    traits::{FrameGenerator, ParseProtocol},
+    types::Command,
+};
+use stream::traits::{AsyncFrameReader, AsyncFrameWriter};
+use tokio::{
+    net::{TcpListener, tcp::OwnedReadHalf},
+    sync::mpsc,
+};
+use tracing::{info, instrument};
+
+use crate::device::SimDevice;
+
+/// 在给定的监听器上接受连接, 所有连接共享同一个模拟设备.
+#[instrument(skip_all, err)]
+pub async fn serve(listener: TcpListener, device: Arc<Mutex<SimDevice>>) -> Result<()> {
+    info!("模拟设备监听于 {}", listener.local_addr()?);
+    loop {
+        let (socket, peer) = listener.accept().await?;
+        info!("接受来自 {} 的连接", peer);
+        socket.set_nodelay(true)?;
+        let device = device.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(socket.into_split(), device).await {
+                info!("[Connection {}] closed: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// 处理单个连接: 读取并解码请求, 按模拟设备给出的延迟依次发出响应.
+async fn handle_connection<W>(
+    (mut reader, mut writer): (OwnedReadHalf, W),
+    device: Arc<Mutex<SimDevice>>,
+) -> Result<()>
+where
+    W: AsyncFrameWriter + Send + 'static,
+{
+    let (frame_sender, mut frame_receiver) = mpsc::channel::<Bytes>(16);
+
+    // --- Writer 任务 ---
+    let writer_handle = tokio::spawn(async move {
+        while let Some(frame) = frame_receiver.recv().await {
+            if let Err(e) = writer.write_frame(&frame).await {
+                info!("[Writer Task] Failed to write frame: {}", e);
+                break;
+            }
+        }
+    });
+
+    let encoder = BlnCommandEncoder;
+    let mut decoder = BlnCommandDecode::default();
+    let mut buf = BytesMut::with_capacity(1024);
+    let result = loop {
+        match reader.read_frame(&mut buf).await {
+            Ok(0) => break Err(eyre!("连接已关闭")),
+            Ok(_) => {}
+            Err(e) => break Err(e),
+        }
+        let commands = decoder.parse_protocol_frame(&mut buf).unwrap_or_default();
+        for diagnostic in decoder.take_diagnostics() {
+            info!("[Connection] Decoder diagnostic: {}", diagnostic);
+        }
+        for command in commands {
+            // 响应原样带回请求的保留字段 (如序列号)
+            let reserved = Bytes::copy_from_slice(command.reserved());
+            let replies = device
+                .lock()
+                .map_err(|_| eyre!("模拟设备状态已损坏"))?
+                .handle(command, Instant::now());
+            for reply in replies {
+                let frame = encoder.create_frame(
+                    Command::try_from(reply.message)?.with_reserved(reserved.clone()),
+                )?;
+                let sender = frame_sender.clone();
+                if reply.delay.is_zero() {
+                    let _ = sender.send(frame).await;
+                } else {
+                    let device = device.clone();
+                    tokio::spawn(async move {
+                        tokio::time::sleep(reply.delay).await;
+                        // 运动在此期间被停止时, 它的到达响应不再发出
+                        let current = reply.motion.is_none_or(|motion| {
+                            device.lock().is_ok_and(|device| device.is_current(motion))
+                        });
+                        if current {
+                            let _ = sender.send(frame).await;
+                        }
+                    });
+                }
+            }
+        }
+    };
+
+    drop(frame_sender);
+    writer_handle.abort();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::SimConfig;
+    use bln::{
+        client::{BlnClient, BlnClientError},
+        protocol::types::{BlnAxis, BlnDeviceStatus, BlnErrorCause, BlnProtocolType},
+    };
+    use std::time::Duration;
+    use stream::client::connect;
+
+    async fn start(config: SimConfig) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let device = Arc::new(Mutex::new(SimDevice::new(config)));
+        tokio::spawn(serve(listener, device));
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_client_moves_simulated_device() {
+        let addr = start(SimConfig {
+            speed: 1000.0,
+            ..Default::default()
+        })
+        .await;
+        let mut client = BlnClient::new(connect(&addr, Duration::from_secs(1)).await.unwrap());
+
+        assert_eq!(client.move_to(30.0, 40.0).await.unwrap(), (30.0, 40.0));
+        let (x, y, status) = client.position().await.unwrap();
+        assert_eq!((x, y), (30.0, 40.0));
+        assert!(!status.contains(BlnDeviceStatus::MOVING));
+    }
+
+    #[tokio::test]
+    async fn test_stopped_motion_does_not_report_reached() {
+        let addr = start(SimConfig {
+            speed: 1000.0,
+            ..Default::default()
+        })
+        .await;
+        let mut client = BlnClient::new(connect(&addr, Duration::from_secs(1)).await.unwrap());
+
+        client.jog_start(BlnAxis::X, 100.0).await.unwrap();
+        let (x, _) = client.jog_stop(BlnAxis::X).await.unwrap();
+        assert!(x < 100.0);
+
+        // 移动需要 200 毫秒, 在到达之前停止
+        client
+            .send(BlnProtocolType::SetPositionRsq(x + 200.0, 0.0))
+            .await
+            .unwrap();
+        assert_eq!(
+            client.recv().await.unwrap(),
+            BlnProtocolType::SetPositionRsp
+        );
+        let (stopped, _) = client.stop().await.unwrap();
+        assert!(stopped < x + 200.0);
+
+        // 被停止的移动不会再报告到达
+        let late = tokio::time::timeout(Duration::from_millis(300), client.recv()).await;
+        assert!(late.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_client_receives_error_for_out_of_range_target() {
+        let addr = start(SimConfig::default()).await;
+        let mut client = BlnClient::new(connect(&addr, Duration::from_secs(1)).await.unwrap());
+
+        let result = client.move_to(5000.0, 0.0).await;
+        assert!(matches!(
+            result,
+            Err(BlnClientError::Device(BlnErrorCause::InvalidArgument))
+        ));
+    }
+}
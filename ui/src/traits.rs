@@ -5,3 +5,12 @@ pub trait RenderUi {
     /// 在给定的框架和区域中渲染组件。
     fn render(&self, frame: &mut Frame, rect: Rect);
 }
+
+/// 可接收协议消息并更新自身状态的组件的 trait。
+pub trait HandleMessage<M> {
+    /// 处理一条收到的消息。
+    fn handle_message(&mut self, message: M);
+
+    /// 在每次刷新界面前调用, 用于推进与时间相关的状态 (如超时)。默认不做任何处理。
+    fn on_tick(&mut self) {}
+}